@@ -0,0 +1,182 @@
+use std::env;
+
+use crate::middleware::telegram_secret::WebhookSecret;
+use crate::services::telegram_api::TelegramApi;
+use crate::services::telegram_api_impl::RealTelegramApi;
+
+/// Path of the webhook route, appended to `PUBLIC_URL`.
+pub const WEBHOOK_PATH: &str = "/telegram/webhook";
+
+/// Usage text printed by `help` and on invalid arguments.
+pub const USAGE: &str = "\
+Usage: tg_ai_companion [COMMAND]
+
+Commands:
+  serve                                 Run the HTTP server (default)
+  set-webhook [URL]                     Register the webhook; URL defaults to $PUBLIC_URL/telegram/webhook
+  delete-webhook [--drop-pending-updates]
+                                        Remove the webhook (needed before long polling)
+  webhook-info                          Show the current webhook status
+  get-me                                Show the bot's user, to check the token
+  help                                  Show this help";
+
+/// A command of the binary, selected by the first command-line argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Run the HTTP server.
+    Serve,
+    /// Register the webhook at the given URL, or at the one configured by `PUBLIC_URL`.
+    SetWebhook { url: Option<String> },
+    /// Remove the webhook.
+    DeleteWebhook { drop_pending_updates: bool },
+    /// Show the current webhook status.
+    WebhookInfo,
+    /// Show the bot's user.
+    GetMe,
+    /// Show the usage text.
+    Help,
+}
+
+impl Command {
+    /// Parses the command-line arguments (without the program name).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the problem if the command or its arguments are unknown.
+    pub fn parse<I, S>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args: Vec<String> = args.into_iter().map(|a| a.as_ref().to_string()).collect();
+        let (command, rest) = match args.split_first() {
+            Some((command, rest)) => (command.as_str(), rest),
+            None => return Ok(Command::Serve),
+        };
+
+        let command = match (command, rest) {
+            ("serve", []) => Command::Serve,
+            ("set-webhook", []) => Command::SetWebhook { url: None },
+            ("set-webhook", [url]) => Command::SetWebhook {
+                url: Some(url.clone()),
+            },
+            ("delete-webhook", []) => Command::DeleteWebhook {
+                drop_pending_updates: false,
+            },
+            ("delete-webhook", [flag]) if flag == "--drop-pending-updates" => {
+                Command::DeleteWebhook {
+                    drop_pending_updates: true,
+                }
+            }
+            ("webhook-info", []) => Command::WebhookInfo,
+            ("get-me", []) => Command::GetMe,
+            ("help" | "--help" | "-h", _) => Command::Help,
+            ("serve" | "set-webhook" | "delete-webhook" | "webhook-info" | "get-me", _) => {
+                return Err(format!("Unexpected arguments for `{}`", command))
+            }
+            _ => return Err(format!("Unknown command `{}`", command)),
+        };
+
+        Ok(command)
+    }
+}
+
+/// Returns the webhook URL built from the configured public URL.
+///
+/// # Environment Variables
+///
+/// - `PUBLIC_URL`: (optional) public HTTPS base URL of the server, e.g. `https://bot.example.com`
+///
+/// # Returns
+///
+/// `Some("$PUBLIC_URL/telegram/webhook")`, or `None` if the variable is missing or empty.
+pub fn webhook_url_from_env() -> Option<String> {
+    env::var("PUBLIC_URL")
+        .ok()
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+        .map(|url| format!("{}{}", url, WEBHOOK_PATH))
+}
+
+/// Returns whether the server registers the webhook when it starts.
+///
+/// # Environment Variables
+///
+/// - `TELEGRAM_SET_WEBHOOK_ON_STARTUP`: (optional) `true` to register the webhook at
+///   the URL built from `PUBLIC_URL`; defaults to `false`
+pub fn set_webhook_on_startup_from_env() -> bool {
+    env::var("TELEGRAM_SET_WEBHOOK_ON_STARTUP")
+        .is_ok_and(|value| matches!(value.trim(), "true" | "1" | "yes"))
+}
+
+/// Registers the webhook together with the secret configured by `TELEGRAM_WEBHOOK_SECRET`.
+///
+/// # Arguments
+///
+/// * `telegram_api` - The client used to call `setWebhook`.
+/// * `url` - The webhook URL; defaults to the one built from `PUBLIC_URL`.
+///
+/// # Returns
+///
+/// The registered URL on success, or `Err(String)` if no URL is configured,
+/// the secret is invalid or Telegram refused the webhook.
+pub async fn set_webhook(
+    telegram_api: &RealTelegramApi,
+    url: Option<String>,
+) -> Result<String, String> {
+    let url = url
+        .or_else(webhook_url_from_env)
+        .ok_or("No webhook URL given and PUBLIC_URL is not set")?;
+    let secret = WebhookSecret::new_from_env()?;
+
+    telegram_api
+        .set_webhook(&url, secret.as_ref().map(WebhookSecret::as_str))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(url)
+}
+
+/// Runs a command that talks to the Telegram Bot API.
+///
+/// # Arguments
+///
+/// * `command` - The command to run; `Serve` and `Help` have nothing to do here.
+/// * `telegram_api` - The client used to call the Bot API.
+///
+/// # Returns
+///
+/// The text to print on success, or `Err(String)` with an error message on failure.
+pub async fn run_command(
+    command: Command,
+    telegram_api: &RealTelegramApi,
+) -> Result<String, String> {
+    match command {
+        Command::Serve => Err("`serve` is not a Bot API command".to_string()),
+        Command::Help => Ok(USAGE.to_string()),
+        Command::SetWebhook { url } => {
+            let url = set_webhook(telegram_api, url).await?;
+            Ok(format!("Webhook set to {}", url))
+        }
+        Command::DeleteWebhook {
+            drop_pending_updates,
+        } => {
+            telegram_api
+                .delete_webhook(drop_pending_updates)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok("Webhook deleted".to_string())
+        }
+        Command::WebhookInfo => {
+            let info = telegram_api
+                .get_webhook_info()
+                .await
+                .map_err(|e| e.to_string())?;
+            serde_json::to_string_pretty(&info).map_err(|e| e.to_string())
+        }
+        Command::GetMe => {
+            let me = telegram_api.get_me().await.map_err(|e| e.to_string())?;
+            serde_json::to_string_pretty(&me).map_err(|e| e.to_string())
+        }
+    }
+}
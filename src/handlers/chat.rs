@@ -1,39 +1,161 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde_json::json;
 
-use crate::models::chat::ChatRequest;
-use crate::services::chat_api::ChatApi;
+use crate::models::chat::{ChatMessage, ChatRequest};
+use crate::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use crate::services::persona_registry::PersonaRegistry;
 
 /// Handles incoming chat requests by forwarding the prompt to the chat API service.
 ///
 /// # Arguments
 ///
+/// * `req` - The HTTP request, used to check whether the client accepts `text/event-stream`.
 /// * `payload` - A JSON payload containing the `ChatRequest` with the user's prompt.
 /// * `chat_api` - Shared reference to an implementation of the `ChatApi` trait, used to process the prompt.
+/// * `personas` - The registry used to look up the requested persona.
 ///
 /// # Behavior
 ///
 /// - Validates that the `prompt` field in the request is not empty or whitespace only.
 /// - If the prompt is empty, returns `400 Bad Request` with an appropriate error message.
-/// - Calls the asynchronous chat API to get a response for the prompt.
+/// - Resolves the requested persona (or the default one); an unknown persona is a `400 Bad Request`.
+/// - Calls the asynchronous chat API with the persona's system prompt and options. A `backend`
+///   in the request overrides the persona's; an unknown backend is a `400 Bad Request`.
 /// - On success, returns `200 OK` with the chat API's response as the body.
-/// - On failure, logs the error and returns `500 Internal Server Error`.
+/// - On failure, logs the error and answers with the status matching the [`ChatApiError`]
+///   (e.g. `504 Gateway Timeout`, `429 Too Many Requests` with `Retry-After`) and the error's
+///   user message as the body.
+///
+/// # Streaming
+///
+/// If the request has `"stream": true` or an `Accept: text/event-stream` header, the reply is
+/// sent as Server-Sent Events while the model generates it:
+///
+/// ```text
+/// data: {"content":"Hel"}
+///
+/// data: {"content":"lo!"}
+///
+/// data: [DONE]
+/// ```
+///
+/// If the model fails mid-way, an `event: error` with `{"error": "..."}` data ends the stream;
+/// the error is the [`ChatApiError::user_message`].
 ///
 /// # Returns
 ///
 /// An `impl Responder` that corresponds to the HTTP response with either the chat response or an error message.
 pub async fn chat_endpoint(
+    req: HttpRequest,
     payload: web::Json<ChatRequest>,
     chat_api: web::Data<dyn ChatApi>,
+    personas: web::Data<PersonaRegistry>,
 ) -> impl Responder {
     if payload.prompt.trim().is_empty() {
         return HttpResponse::BadRequest().body("Prompt cannot be empty");
     }
 
-    match chat_api.call_chat_api(&payload.prompt).await {
-        Ok(response_text) => HttpResponse::Ok().body(response_text),
+    let persona = match payload.persona.as_deref() {
+        Some(name) => match personas.get(name) {
+            Some(persona) => persona,
+            None => return HttpResponse::BadRequest().body(format!("Unknown persona: {}", name)),
+        },
+        None => personas.default_persona(),
+    };
+
+    let mut options = persona.chat_options();
+    if let Some(backend) = &payload.backend {
+        let backends = chat_api.health();
+        if !backends.is_empty() && !backends.iter().any(|b| &b.name == backend) {
+            return HttpResponse::BadRequest().body(format!("Unknown backend: {}", backend));
+        }
+        options.backend = Some(backend.clone());
+    }
+
+    let messages = persona.build_messages(vec![ChatMessage::user(payload.prompt.clone())]);
+
+    if payload.stream || accepts_event_stream(&req) {
+        return match chat_api.complete_stream(&messages, &options).await {
+            Ok(fragments) => HttpResponse::Ok()
+                .content_type("text/event-stream")
+                .insert_header((header::CACHE_CONTROL, "no-cache"))
+                .streaming(sse_events(fragments)),
+            Err(e) => {
+                eprintln!("Error calling chat API: {}", e);
+                error_response(&e)
+            }
+        };
+    }
+
+    match chat_api.complete(&messages, &options).await {
+        Ok(completion) => HttpResponse::Ok().body(completion.content),
         Err(e) => {
             eprintln!("Error calling chat API: {}", e);
-            HttpResponse::InternalServerError().body("Error calling chat API")
+            error_response(&e)
         }
     }
 }
+
+/// Builds the response for a failed chat API call.
+///
+/// Failures of the backend itself are a `502 Bad Gateway`; the others get the status
+/// closest to their cause, so clients can tell what to change or when to retry.
+fn error_response(error: &ChatApiError) -> HttpResponse {
+    let status = match error {
+        ChatApiError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        ChatApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        ChatApiError::ContextLengthExceeded(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        ChatApiError::ContentFiltered(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ChatApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        ChatApiError::Transport(_)
+        | ChatApiError::Auth(_)
+        | ChatApiError::MalformedResponse(_)
+        | ChatApiError::Backend { .. } => StatusCode::BAD_GATEWAY,
+    };
+
+    let mut response = HttpResponse::build(status);
+    if let Some(retry_after) = error.retry_after() {
+        response.insert_header((header::RETRY_AFTER, retry_after.as_secs().to_string()));
+    }
+    response.body(error.user_message())
+}
+
+/// Returns `true` if the client's `Accept` header asks for Server-Sent Events.
+fn accepts_event_stream(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Converts the model's reply fragments into Server-Sent Events.
+///
+/// Every fragment becomes a `data: {"content": ...}` event. The stream ends with `data: [DONE]`,
+/// or with an `error` event if the model fails mid-way.
+fn sse_events(fragments: ChatStream) -> impl Stream<Item = Result<web::Bytes, actix_web::Error>> {
+    stream::unfold(Some(fragments), |fragments| async move {
+        let mut fragments = fragments?;
+
+        let (event, next) = match fragments.next().await {
+            Some(Ok(content)) => (
+                format!("data: {}\n\n", json!({ "content": content })),
+                Some(fragments),
+            ),
+            Some(Err(e)) => {
+                eprintln!("Error streaming from chat API: {}", e);
+                (
+                    format!(
+                        "event: error\ndata: {}\n\n",
+                        json!({ "error": e.user_message() })
+                    ),
+                    None,
+                )
+            }
+            None => ("data: [DONE]\n\n".to_string(), None),
+        };
+
+        Some((Ok(web::Bytes::from(event)), next))
+    })
+}
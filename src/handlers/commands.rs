@@ -0,0 +1,482 @@
+use async_trait::async_trait;
+use std::env;
+
+use crate::models::telegram::{BotCommand, TelegramMessage};
+use crate::services::persona_registry::PersonaRegistry;
+use crate::services::telegram_api::TelegramApi;
+use crate::storage::storage_api::Storage;
+
+/// A bot command parsed from a message text, e.g. `/persona@my_bot pirate`.
+///
+/// # Fields
+/// - `name`: Command name without the leading `/`, e.g. `persona`.
+/// - `bot_username`: The bot the command is addressed to (`@my_bot`), if any.
+/// - `args`: Everything after the command, trimmed; empty if there are no arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCommand<'a> {
+    pub name: &'a str,
+    pub bot_username: Option<&'a str>,
+    pub args: &'a str,
+}
+
+/// Parses a `/command@botname args` message text.
+///
+/// # Returns
+///
+/// The parsed command, or `None` if the text is not a command.
+pub fn parse_command(text: &str) -> Option<ParsedCommand<'_>> {
+    let rest = text.trim().strip_prefix('/')?;
+    let (head, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let (name, bot_username) = match head.split_once('@') {
+        Some((name, bot_username)) => (name, Some(bot_username)),
+        None => (head, None),
+    };
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    Some(ParsedCommand {
+        name,
+        bot_username,
+        args: args.trim(),
+    })
+}
+
+/// Everything a command handler may need to answer a command.
+///
+/// # Fields
+/// - `chat_id`: The chat the command was sent in.
+/// - `sender_id`: The user who sent the command; missing for messages sent on behalf of a chat.
+/// - `is_group`: Whether the chat is a group or a supergroup.
+/// - `args`: The command arguments, trimmed.
+/// - `storage`: The storage holding the chat's history and settings.
+/// - `personas`: The registry of personas the chats can pick from.
+/// - `telegram_api`: The Telegram Bot API, e.g. to look up the sender's membership.
+/// - `commands`: The registered commands, e.g. for `/help`.
+pub struct CommandContext<'a> {
+    pub chat_id: i64,
+    pub sender_id: Option<i64>,
+    pub is_group: bool,
+    pub args: &'a str,
+    pub storage: &'a dyn Storage,
+    pub personas: &'a PersonaRegistry,
+    pub telegram_api: &'a dyn TelegramApi,
+    pub commands: &'a [BotCommand],
+}
+
+impl CommandContext<'_> {
+    /// Returns whether the sender is an administrator of the chat.
+    ///
+    /// Everybody administers their private chat with the bot. In groups the sender's
+    /// membership is looked up with `getChatMember`; if that fails, the sender is not
+    /// treated as an administrator.
+    pub async fn sender_is_admin(&self) -> bool {
+        if !self.is_group {
+            return true;
+        }
+        let Some(sender_id) = self.sender_id else {
+            return false;
+        };
+
+        match self
+            .telegram_api
+            .get_chat_member(self.chat_id, sender_id)
+            .await
+        {
+            Ok(member) => member.is_admin(),
+            Err(e) => {
+                eprintln!(
+                    "Error getting member {} of chat {}: {}",
+                    sender_id, self.chat_id, e
+                );
+                false
+            }
+        }
+    }
+}
+
+/// `CommandHandler` answers one bot command.
+#[async_trait]
+pub trait CommandHandler: Send + Sync {
+    /// Handles the command and returns the reply for the chat.
+    ///
+    /// Errors are reported to the user in the reply, as there is no caller to return them to.
+    async fn handle(&self, context: &CommandContext<'_>) -> String;
+}
+
+/// `CommandRouter` dispatches bot commands to the registered handlers.
+///
+/// Commands addressed to another bot (`/start@other_bot`) are ignored, so the bot
+/// can share group chats with other bots.
+pub struct CommandRouter {
+    bot_username: Option<String>,
+    commands: Vec<BotCommand>,
+    handlers: Vec<Box<dyn CommandHandler>>,
+}
+
+impl Default for CommandRouter {
+    /// Creates a router with the built-in commands.
+    fn default() -> Self {
+        Self::empty()
+            .register("start", "Start talking to the bot", StartCommand)
+            .register("help", "Show the available commands", HelpCommand)
+            .register("reset", "Forget the conversation so far", ResetCommand)
+            .register("model", "Show or change the model", ModelCommand::default())
+            .register("persona", "Show or change the persona", PersonaCommand)
+    }
+}
+
+impl CommandRouter {
+    /// Creates a router without any commands; [`CommandRouter::default`] has the built-in ones.
+    pub fn empty() -> Self {
+        Self {
+            bot_username: None,
+            commands: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Sets the bot's username (without `@`), as returned by `getMe`.
+    ///
+    /// Without it, commands addressed to any bot are handled.
+    pub fn with_bot_username(mut self, bot_username: Option<String>) -> Self {
+        self.bot_username = bot_username;
+        self
+    }
+
+    /// Registers a command; a command registered twice replaces the earlier handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Command name without the leading `/`, lowercase.
+    /// * `description` - Short description for `/help` and the Telegram command menu.
+    /// * `handler` - The handler answering the command.
+    pub fn register(
+        mut self,
+        name: &str,
+        description: &str,
+        handler: impl CommandHandler + 'static,
+    ) -> Self {
+        let command = BotCommand {
+            command: name.to_string(),
+            description: description.to_string(),
+        };
+
+        match self.commands.iter().position(|c| c.command == name) {
+            Some(index) => {
+                self.commands[index] = command;
+                self.handlers[index] = Box::new(handler);
+            }
+            None => {
+                self.commands.push(command);
+                self.handlers.push(Box::new(handler));
+            }
+        }
+        self
+    }
+
+    /// Returns the registered commands, e.g. for `setMyCommands`.
+    pub fn commands(&self) -> &[BotCommand] {
+        &self.commands
+    }
+
+    /// Returns whether a command is addressed to this bot.
+    pub fn is_addressed_to_bot(&self, command: &ParsedCommand<'_>) -> bool {
+        match (command.bot_username, &self.bot_username) {
+            (Some(target), Some(bot_username)) => target.eq_ignore_ascii_case(bot_username),
+            _ => true,
+        }
+    }
+
+    /// Returns whether a command names this bot explicitly (`/start@my_bot`).
+    fn is_explicitly_addressed_to_bot(&self, command: &ParsedCommand<'_>) -> bool {
+        match (command.bot_username, &self.bot_username) {
+            (Some(target), Some(bot_username)) => target.eq_ignore_ascii_case(bot_username),
+            _ => false,
+        }
+    }
+
+    /// Dispatches a command to its handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The parsed command.
+    /// * `message` - The message the command was sent in, for its chat and sender.
+    /// * `storage` - The storage holding the chat's history and settings.
+    /// * `personas` - The registry of personas the chats can pick from.
+    /// * `telegram_api` - The Telegram Bot API, e.g. to look up the sender's membership.
+    ///
+    /// # Returns
+    ///
+    /// The reply for the chat, or `None` if the command is addressed to another bot.
+    /// Unknown commands get a hint to use `/help` in private chats; in groups they may be
+    /// meant for another bot, so they are ignored unless they name this bot (`/x@my_bot`).
+    pub async fn dispatch(
+        &self,
+        command: &ParsedCommand<'_>,
+        message: &TelegramMessage,
+        storage: &dyn Storage,
+        personas: &PersonaRegistry,
+        telegram_api: &dyn TelegramApi,
+    ) -> Option<String> {
+        if !self.is_addressed_to_bot(command) {
+            return None;
+        }
+
+        let Some(index) = self
+            .commands
+            .iter()
+            .position(|c| c.command.eq_ignore_ascii_case(command.name))
+        else {
+            if message.chat.is_group() && !self.is_explicitly_addressed_to_bot(command) {
+                return None;
+            }
+            return Some(format!(
+                "Unknown command /{}. Use /help to see the available commands.",
+                command.name
+            ));
+        };
+
+        let context = CommandContext {
+            chat_id: message.chat.id,
+            sender_id: message.from.as_ref().map(|user| user.id),
+            is_group: message.chat.is_group(),
+            args: command.args,
+            storage,
+            personas,
+            telegram_api,
+            commands: &self.commands,
+        };
+
+        Some(self.handlers[index].handle(&context).await)
+    }
+}
+
+/// `/start` — greets the user.
+pub struct StartCommand;
+
+#[async_trait]
+impl CommandHandler for StartCommand {
+    async fn handle(&self, _context: &CommandContext<'_>) -> String {
+        "Hi! I'm your AI companion. Send me a message and I'll answer.\n\n\
+         Use /help to see what else I can do."
+            .to_string()
+    }
+}
+
+/// `/help` — lists the registered commands.
+pub struct HelpCommand;
+
+#[async_trait]
+impl CommandHandler for HelpCommand {
+    async fn handle(&self, context: &CommandContext<'_>) -> String {
+        let list: Vec<String> = context
+            .commands
+            .iter()
+            .map(|c| format!("/{} — {}", c.command, c.description))
+            .collect();
+
+        format!("Available commands:\n{}", list.join("\n"))
+    }
+}
+
+/// `/reset` — clears the chat's conversation history.
+///
+/// In groups the history is shared, so only administrators can clear it.
+pub struct ResetCommand;
+
+#[async_trait]
+impl CommandHandler for ResetCommand {
+    async fn handle(&self, context: &CommandContext<'_>) -> String {
+        if !context.sender_is_admin().await {
+            return "Only the chat's administrators can reset the conversation.".to_string();
+        }
+
+        match context.storage.clear_messages(context.chat_id).await {
+            Ok(()) => "Done, I forgot our conversation.".to_string(),
+            Err(e) => {
+                eprintln!("Error clearing history of chat {}: {}", context.chat_id, e);
+                "Sorry, I could not clear the conversation.".to_string()
+            }
+        }
+    }
+}
+
+/// `/model` — shows the chat's model, or overrides the persona's model for the chat.
+///
+/// Only the models of the personas and the allowed models can be picked, and in groups
+/// only administrators can change the model. `/model default` removes the override.
+#[derive(Debug, Clone, Default)]
+pub struct ModelCommand {
+    allowed_models: Vec<String>,
+}
+
+impl ModelCommand {
+    /// Creates the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `allowed_models` - The models a chat may pick besides the personas' models.
+    pub fn new(allowed_models: Vec<String>) -> Self {
+        Self { allowed_models }
+    }
+
+    /// Creates the command using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `ALLOWED_MODELS`: (optional) comma-separated models a chat may pick besides
+    ///   the personas' models
+    pub fn new_from_env() -> Self {
+        let allowed_models = env::var("ALLOWED_MODELS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|model| !model.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Self::new(allowed_models)
+    }
+
+    /// Returns the models a chat may pick: the personas' models, then the allowed models.
+    fn available_models<'a>(&'a self, personas: &'a PersonaRegistry) -> Vec<&'a str> {
+        let mut models: Vec<&str> = Vec::new();
+        let persona_models = personas
+            .personas()
+            .iter()
+            .filter_map(|persona| persona.model.as_deref());
+        for model in persona_models.chain(self.allowed_models.iter().map(String::as_str)) {
+            if !models.contains(&model) {
+                models.push(model);
+            }
+        }
+        models
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ModelCommand {
+    async fn handle(&self, context: &CommandContext<'_>) -> String {
+        let chat_id = context.chat_id;
+        let mut settings = match context.storage.chat_settings(chat_id).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Error loading settings of chat {}: {}", chat_id, e);
+                return "Sorry, I could not load the chat settings.".to_string();
+            }
+        };
+        let models = self.available_models(context.personas);
+        let available = if models.is_empty() {
+            "none".to_string()
+        } else {
+            models.join(", ")
+        };
+
+        if context.args.is_empty() {
+            let persona = context.personas.resolve(settings.persona.as_deref());
+            let model = settings
+                .model
+                .as_deref()
+                .or(persona.model.as_deref())
+                .unwrap_or("the server's default model");
+
+            return format!(
+                "Current model: {}\nAvailable models: {}\n\n\
+                 Use /model <name> to switch, /model default to go back.",
+                model, available
+            );
+        }
+
+        if !context.sender_is_admin().await {
+            return "Only the chat's administrators can change the model.".to_string();
+        }
+
+        let (model, reply) = if context.args.eq_ignore_ascii_case("default") {
+            (None, "Model reset to the persona's default.".to_string())
+        } else if let Some(model) = models.iter().find(|model| **model == context.args) {
+            (
+                Some(model.to_string()),
+                format!("Model switched to {}.", model),
+            )
+        } else {
+            return format!(
+                "Unknown model: {}. Available models: {}",
+                context.args, available
+            );
+        };
+
+        settings.model = model;
+        if let Err(e) = context.storage.save_chat_settings(chat_id, &settings).await {
+            eprintln!("Error saving settings of chat {}: {}", chat_id, e);
+            return "Sorry, I could not save the chat settings.".to_string();
+        }
+
+        reply
+    }
+}
+
+/// `/persona` — lists the personas, or switches the chat's persona.
+///
+/// - Without arguments, lists the available personas and the chat's current one.
+/// - With a persona name, saves it in the chat's settings; in groups only administrators can.
+pub struct PersonaCommand;
+
+#[async_trait]
+impl CommandHandler for PersonaCommand {
+    async fn handle(&self, context: &CommandContext<'_>) -> String {
+        let chat_id = context.chat_id;
+        let personas = context.personas;
+        let mut settings = match context.storage.chat_settings(chat_id).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Error loading settings of chat {}: {}", chat_id, e);
+                return "Sorry, I could not load the chat settings.".to_string();
+            }
+        };
+
+        if context.args.is_empty() {
+            let current = personas.resolve(settings.persona.as_deref());
+            let list: Vec<String> = personas
+                .personas()
+                .iter()
+                .map(|p| match &p.description {
+                    Some(description) => format!("• {} — {}", p.name, description),
+                    None => format!("• {}", p.name),
+                })
+                .collect();
+
+            return format!(
+                "Current persona: {}\n\nAvailable personas:\n{}\n\nUse /persona <name> to switch.",
+                current.name,
+                list.join("\n")
+            );
+        }
+
+        if !context.sender_is_admin().await {
+            return "Only the chat's administrators can change the persona.".to_string();
+        }
+
+        let Some(persona) = personas.get(context.args) else {
+            let names: Vec<&str> = personas
+                .personas()
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect();
+            return format!(
+                "Unknown persona: {}. Available personas: {}",
+                context.args,
+                names.join(", ")
+            );
+        };
+
+        settings.persona = Some(persona.name.clone());
+        if let Err(e) = context.storage.save_chat_settings(chat_id, &settings).await {
+            eprintln!("Error saving settings of chat {}: {}", chat_id, e);
+            return "Sorry, I could not save the chat settings.".to_string();
+        }
+
+        format!("Persona switched to {}.", persona.name)
+    }
+}
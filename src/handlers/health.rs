@@ -0,0 +1,43 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::models::health::HealthReport;
+use crate::services::chat_api::ChatApi;
+use crate::services::telegram_rate_limiter::RateLimitedTelegramApi;
+
+/// Reports whether the chat backends are healthy, and how many messages wait to be sent to Telegram.
+///
+/// The endpoint needs no authentication, so load balancers and container orchestrators
+/// can probe it.
+///
+/// # Arguments
+///
+/// * `chat_api` - The chat API whose backends are reported.
+/// * `rate_limiter` - The scheduler of the bot's Telegram messages, if the app has one.
+///   A long queue does not make the service unavailable.
+///
+/// # Returns
+///
+/// An `impl Responder` with a [`HealthReport`] as JSON:
+/// - `200 OK` with `"status": "ok"` if at least one backend accepts requests,
+///   or the chat API does not track its health.
+/// - `503 Service Unavailable` with `"status": "unavailable"` if every backend's
+///   circuit breaker is open.
+pub async fn health_endpoint(
+    chat_api: web::Data<dyn ChatApi>,
+    rate_limiter: Option<web::Data<RateLimitedTelegramApi>>,
+) -> impl Responder {
+    let backends = chat_api.health();
+    let available = backends.is_empty() || backends.iter().any(|backend| backend.is_available());
+
+    let report = HealthReport {
+        status: if available { "ok" } else { "unavailable" }.to_string(),
+        backends,
+        telegram_queue_depth: rate_limiter.map(|rate_limiter| rate_limiter.total_queue_depth()),
+    };
+
+    if available {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
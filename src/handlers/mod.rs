@@ -1,2 +1,4 @@
 pub mod chat;
+pub mod commands;
+pub mod health;
 pub mod telegram;
@@ -1,26 +1,27 @@
 use actix_web::{web, HttpResponse, Responder};
 
 use crate::models::telegram::TelegramUpdate;
-use crate::services::chat_api::ChatApi;
-use crate::services::telegram_api::TelegramApi;
+use crate::services::update_processor::UpdateProcessor;
 
 /// Handles incoming Telegram webhook updates.
 ///
-/// This function processes an incoming Telegram update, extracts the chat ID and message text,
-/// sends the prompt to the AI chat API, and responds with the AI-generated text via the Telegram Bot API.
+/// This function acknowledges every update with `200 OK` and hands it to the [`UpdateProcessor`]
+/// in a background task (see [`UpdateProcessor::spawn_update`]), so Telegram gets its answer right away. Telegram redelivers updates
+/// answered with any other status, so even updates that cannot be parsed are acknowledged
+/// (and logged). The processor dispatches each update by its type; text messages are sent
+/// together with the chat's persona and recent history to the AI chat API, and the
+/// AI-generated text is streamed back via the Telegram Bot API.
 ///
 /// # Arguments
 ///
-/// * `update` - The deserialized Telegram update received via webhook.
-/// * `chat_api` - An implementation of the `ChatApi` trait used to get the AI-generated response.
-/// * `telegram_api` - An implementation of the `TelegramApi` trait used to send the message back to Telegram.
+/// * `body` - The raw JSON update received via webhook.
+/// * `processor` - The update-processing pipeline shared with long polling.
 ///
 /// # Returns
 ///
 /// An `impl Responder` representing the HTTP response:
-/// - `200 OK` with `"Message sent"` on success.
-/// - `400 Bad Request` if the message text is missing or empty.
-/// - `500 Internal Server Error` if the chat API or Telegram API calls fail.
+/// - `200 OK` with `"Processing"` once the update was handed over.
+/// - `200 OK` with `"Ignored"` if the body is not a valid update.
 ///
 /// # Example
 ///
@@ -38,38 +39,18 @@ use crate::services::telegram_api::TelegramApi;
 /// }
 /// ```
 pub async fn telegram_webhook(
-    update: web::Json<TelegramUpdate>,
-    chat_api: web::Data<dyn ChatApi>,
-    telegram_api: web::Data<dyn TelegramApi>,
+    body: web::Bytes,
+    processor: web::Data<UpdateProcessor>,
 ) -> impl Responder {
-    let (chat_id, prompt) = match update
-        .message
-        .as_ref()
-        .and_then(|m| Some((m.chat.id, m.text.as_ref()?)))
-    {
-        Some((chat_id, text)) if !text.trim().is_empty() => (chat_id, text.clone()),
-        _ => return HttpResponse::BadRequest().body("No Message Text"),
+    let update: TelegramUpdate = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            eprintln!("Ignoring invalid Telegram update: {}", e);
+            return HttpResponse::Ok().body("Ignored");
+        }
     };
 
-    let prompt = prompt.to_string();
-    let chat_api = chat_api.clone();
-    let telegram_api = telegram_api.clone();
-
-    tokio::spawn(async move {
-        match chat_api.call_chat_api(&prompt).await {
-            Ok(response_text) => {
-                if let Err(e) = telegram_api
-                    .send_telegram_message(chat_id, response_text)
-                    .await
-                {
-                    eprintln!("Error sending to Telegram: {}", e);
-                }
-            }
-            Err(e) => {
-                eprintln!("Error calling chat API: {}", e);
-            }
-        }
-    });
+    processor.into_inner().spawn_update(update);
 
     HttpResponse::Ok().body("Processing")
 }
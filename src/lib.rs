@@ -1,5 +1,7 @@
+pub mod cli;
 pub mod handlers;
 pub mod middleware;
 pub mod models;
 pub mod routes;
 pub mod services;
+pub mod storage;
@@ -1,39 +1,210 @@
 use actix_cors::Cors;
-use actix_web::{http::header, middleware::NormalizePath, App, HttpServer};
+use actix_web::{http::header, middleware::NormalizePath, web, App, HttpServer};
 use dotenv::dotenv;
 use std::env;
+use std::error::Error;
+use std::sync::Arc;
 
+use tg_ai_companion::cli::{self, Command, USAGE};
+use tg_ai_companion::handlers::commands::{CommandRouter, ModelCommand};
+use tg_ai_companion::middleware::telegram_secret::WebhookSecret;
 use tg_ai_companion::routes::chat::init_chat_routes;
+use tg_ai_companion::routes::health::init_health_routes;
 use tg_ai_companion::routes::telegram::init_telegram_routes;
+use tg_ai_companion::services::chat_api::ChatApi;
+use tg_ai_companion::services::failover_chat_api::FailoverChatApi;
+use tg_ai_companion::services::persona_registry::PersonaRegistry;
+use tg_ai_companion::services::reply_streamer::ReplyStreamer;
+use tg_ai_companion::services::telegram_api::TelegramApi;
+use tg_ai_companion::services::telegram_api_impl::RealTelegramApi;
+use tg_ai_companion::services::telegram_polling::{TelegramPoller, UpdateMode};
+use tg_ai_companion::services::telegram_rate_limiter::{RateLimitedTelegramApi, RateLimits};
+use tg_ai_companion::services::update_processor::UpdateProcessor;
+use tg_ai_companion::storage::storage_api::Storage;
+use tg_ai_companion::storage::storage_memory_impl::InMemoryStorage;
+#[cfg(feature = "sqlite")]
+use tg_ai_companion::storage::storage_sqlite_impl::SqliteStorage;
+
+/// Creates the storage shared by all workers.
+///
+/// With the `sqlite` feature enabled and `DATABASE_PATH` set, data is persisted
+/// in a SQLite database; otherwise it is kept in memory and lost on restart.
+fn init_storage() -> Result<Arc<dyn Storage>, Box<dyn Error + Send + Sync>> {
+    #[cfg(feature = "sqlite")]
+    if env::var("DATABASE_PATH").is_ok_and(|path| !path.trim().is_empty()) {
+        return Ok(Arc::new(SqliteStorage::new_from_env()?));
+    }
+
+    Ok(Arc::new(InMemoryStorage::new_from_env()?))
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Load environment variables from `.env` file into std::env
     dotenv().ok();
 
+    let command = match Command::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Serve => serve().await,
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => {
+            let telegram_api =
+                RealTelegramApi::new_from_env().expect("Failed to initialize Telegram API");
+            match cli::run_command(command, &telegram_api).await {
+                Ok(output) => {
+                    println!("{}", output);
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Runs the HTTP server (and the long polling runner in polling mode).
+async fn serve() -> std::io::Result<()> {
     // Read host and port from environment variables.
     let host = env::var("SERVER_HOST_NAME").expect("SERVER_HOST_NAME must be set in environment");
     let port = env::var("SERVER_HOST_PORT").expect("SERVER_HOST_PORT must be set in environment");
     let bind_address = format!("{}:{}", host, port);
 
+    // Shared state must outlive the per-worker app factories below.
+    let storage = init_storage().expect("Failed to initialize storage");
+    let personas =
+        Arc::new(PersonaRegistry::new_from_env().expect("Failed to initialize persona registry"));
+
+    // One chat API serves the bot and `/chat`, so both see the same backend health.
+    let failover_chat_api =
+        Arc::new(FailoverChatApi::new_from_env().expect("Failed to initialize Chat API"));
+    let prober = failover_chat_api.clone();
+    tokio::spawn(async move { prober.run_probes().await });
+    let chat_api: Arc<dyn ChatApi> = failover_chat_api;
+    let real_telegram_api =
+        RealTelegramApi::new_from_env().expect("Failed to initialize Telegram API");
+    let reply_streamer =
+        ReplyStreamer::new_from_env().expect("Failed to initialize Telegram reply streamer");
+    let update_mode = UpdateMode::from_env().expect("Failed to read Telegram update mode");
+    // Telegram refuses `getUpdates` while a webhook is set.
+    if update_mode == UpdateMode::Polling {
+        match real_telegram_api.delete_webhook(false).await {
+            Ok(()) => println!("🔗 Webhook removed, updates are fetched with long polling"),
+            Err(e) => eprintln!("Error removing the Telegram webhook before polling: {}", e),
+        }
+    }
+    if update_mode == UpdateMode::Webhook && cli::set_webhook_on_startup_from_env() {
+        match cli::set_webhook(&real_telegram_api, None).await {
+            Ok(url) => println!("🔗 Webhook set to {}", url),
+            Err(e) => eprintln!("Error setting Telegram webhook: {}", e),
+        }
+    }
+
+    // The bot's username tells commands for this bot (`/help@this_bot`) from commands for others,
+    // and mentions of and replies to the bot in groups from other messages.
+    let bot_username = match real_telegram_api.get_me().await {
+        Ok(me) => me.username,
+        Err(e) => {
+            eprintln!("Error getting the bot's user from Telegram: {}", e);
+            None
+        }
+    };
+    let commands = CommandRouter::default()
+        .register(
+            "model",
+            "Show or change the model",
+            ModelCommand::new_from_env(),
+        )
+        .with_bot_username(bot_username.clone());
+    if let Err(e) = real_telegram_api.set_my_commands(commands.commands()).await {
+        eprintln!("Error publishing bot commands to Telegram: {}", e);
+    }
+
+    // Everything the bot sends goes through one scheduler, so background tasks
+    // cannot exceed Telegram's rate limits together.
+    let rate_limits = RateLimits::new_from_env().expect("Failed to read Telegram rate limits");
+    let rate_limiter = Arc::new(RateLimitedTelegramApi::new(
+        Arc::new(real_telegram_api),
+        rate_limits,
+    ));
+    let telegram_api: Arc<dyn TelegramApi> = rate_limiter.clone();
+    let processor = Arc::new(
+        UpdateProcessor::new(
+            chat_api.clone(),
+            telegram_api.clone(),
+            storage,
+            personas.clone(),
+            reply_streamer,
+        )
+        .with_commands(commands)
+        .with_bot_username(bot_username),
+    );
+
+    // In polling mode the webhook route is not mounted: Telegram only delivers
+    // updates one way at a time.
+    if update_mode == UpdateMode::Polling {
+        let poller = TelegramPoller::new_from_env(telegram_api, processor.clone())
+            .expect("Failed to initialize Telegram polling");
+        tokio::spawn(async move {
+            if let Err(e) = poller.run().await {
+                eprintln!(
+                    "Telegram refused long polling, is another instance of the bot running? {}",
+                    e
+                );
+                std::process::exit(1);
+            }
+        });
+    }
+
+    let webhook_secret = WebhookSecret::new_from_env().expect("Failed to read webhook secret");
+    if update_mode == UpdateMode::Webhook && webhook_secret.is_none() {
+        println!("⚠️ TELEGRAM_WEBHOOK_SECRET is not set, the webhook accepts requests from anyone");
+    }
+
+    let chat_api: web::Data<dyn ChatApi> = web::Data::from(chat_api);
+    let personas = web::Data::from(personas);
+    let processor = web::Data::from(processor);
+    let rate_limiter = web::Data::from(rate_limiter);
+
     println!("🚀 Server running at {}", bind_address);
 
     HttpServer::new(move || {
-        App::new()
-            .service(init_chat_routes())
-            .service(init_telegram_routes())
-            .wrap(
-                Cors::permissive()
-                    .allowed_origin_fn(|origin, _req_head| {
-                        origin.as_bytes().starts_with(b"http://localhost") || origin == "null"
-                    })
-                    .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-                    .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
-                    .allowed_header(header::CONTENT_TYPE)
-                    .supports_credentials()
-                    .max_age(3600),
-            )
-            .wrap(NormalizePath::trim())
+        let mut app = App::new()
+            .service(init_health_routes(
+                chat_api.clone(),
+                Some(rate_limiter.clone()),
+            ))
+            .service(init_chat_routes(chat_api.clone(), personas.clone()));
+        if update_mode == UpdateMode::Webhook {
+            app = app.service(init_telegram_routes(
+                processor.clone(),
+                webhook_secret.clone(),
+            ));
+        }
+
+        app.wrap(
+            Cors::permissive()
+                .allowed_origin_fn(|origin, _req_head| {
+                    origin.as_bytes().starts_with(b"http://localhost") || origin == "null"
+                })
+                .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
+                .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])
+                .allowed_header(header::CONTENT_TYPE)
+                .supports_credentials()
+                .max_age(3600),
+        )
+        .wrap(NormalizePath::trim())
     })
     .bind(bind_address)?
     .run()
@@ -0,0 +1,118 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error,
+    middleware::Next,
+    Error,
+};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+/// Header in which Telegram sends the secret token with every webhook request.
+pub const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+/// The secret token registered together with the webhook (`secret_token` of `setWebhook`).
+///
+/// Telegram allows 1–256 characters: `A-Z`, `a-z`, `0-9`, `_` and `-`.
+/// The token is never printed by `Debug`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WebhookSecret(String);
+
+impl WebhookSecret {
+    /// Creates a new secret, checking that Telegram accepts it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is empty, longer than 256 characters or contains
+    /// characters other than letters, digits, `_` and `-`.
+    pub fn new(token: impl Into<String>) -> Result<Self, String> {
+        let token = token.into();
+
+        if token.is_empty() || token.len() > 256 {
+            return Err("The webhook secret must be 1-256 characters long".to_string());
+        }
+        if !token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(
+                "The webhook secret may only contain letters, digits, `_` and `-`".to_string(),
+            );
+        }
+
+        Ok(Self(token))
+    }
+
+    /// Reads the secret from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `TELEGRAM_WEBHOOK_SECRET`: (optional) the webhook secret token
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the variable is missing or empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable holds a token Telegram would reject.
+    pub fn new_from_env() -> Result<Option<Self>, String> {
+        match env::var("TELEGRAM_WEBHOOK_SECRET") {
+            Ok(token) if !token.trim().is_empty() => Self::new(token.trim())
+                .map(Some)
+                .map_err(|e| format!("Environment variable TELEGRAM_WEBHOOK_SECRET: {}", e)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the token, e.g. to pass it to `setWebhook`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Compares a received token with the secret in constant time.
+    pub fn matches(&self, token: &[u8]) -> bool {
+        self.0.as_bytes().ct_eq(token).into()
+    }
+}
+
+impl fmt::Debug for WebhookSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WebhookSecret(***)")
+    }
+}
+
+/// Rejects webhook requests without the configured secret token.
+///
+/// This function is used with the `from_fn(...)` middleware. Without a configured secret
+/// every request is let through.
+///
+/// # Arguments
+/// - `secret`: The expected secret token, if any
+/// - `req`: Incoming request
+/// - `next`: The rest of the service chain
+///
+/// # Returns
+/// - The response of the wrapped service if the `X-Telegram-Bot-Api-Secret-Token` header matches
+/// - `401 Unauthorized` if the header is missing or incorrect
+pub async fn verify_secret_token<B: MessageBody>(
+    secret: Option<Arc<WebhookSecret>>,
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error> {
+    if let Some(secret) = secret {
+        let token = req
+            .headers()
+            .get(SECRET_TOKEN_HEADER)
+            .map(|value| value.as_bytes())
+            .unwrap_or_default();
+
+        if !secret.matches(token) {
+            return Err(error::ErrorUnauthorized("Invalid Secret Token"));
+        }
+    }
+
+    next.call(req).await
+}
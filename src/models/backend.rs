@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The API a chat backend speaks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    /// The OpenAI Chat Completions API, also offered by LocalAI and many gateways.
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Ollama's native `/api/chat`.
+    Ollama,
+    /// The Anthropic Messages API.
+    Anthropic,
+}
+
+/// How a chat backend is reached.
+///
+/// # Fields
+///
+/// * `name` – Unique name of the backend, used in logs and `GET /health`.
+/// * `kind` – The API the backend speaks, `openai` by default.
+/// * `url` – Base URL of the API (e.g. `http://localai:8080` or `https://api.openai.com`);
+///   may be empty for `anthropic`, which defaults to `https://api.anthropic.com`.
+/// * `model` – The model to use unless a request overrides it.
+/// * `api_key` – Optional API key.
+/// * `api_key_env` – Optional name of an environment variable holding the API key,
+///   so keys need not be written into the file; used when `api_key` is not set.
+/// * `priority` – Backends with a lower priority are tried first.
+/// * `weight` – Share of the requests the backend gets with the `weighted` strategy.
+/// * `connect_timeout_secs` – Optional connect timeout, overriding the default.
+/// * `read_timeout_secs` – Optional read timeout, overriding the default.
+/// * `keep_alive` – Ollama only: how long the model stays loaded (e.g. `10m`, `-1` for ever).
+/// * `num_ctx` – Ollama only: the context window size in tokens.
+/// * `path` – OpenAI only: the endpoint path appended to `url`, `/v1/chat/completions` by
+///   default; `{model}` is replaced by the request's model (Azure's deployment).
+/// * `query` – OpenAI only: query parameters sent with every request (e.g. Azure's `api-version`).
+/// * `auth_header` – OpenAI only: header carrying the bare API key (e.g. Azure's `api-key`)
+///   instead of `Authorization: Bearer`.
+///
+/// Settings of another kind, and keys for `ollama`, are rejected rather than ignored.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "name": "openai",
+///   "url": "https://api.openai.com",
+///   "model": "gpt-4o-mini",
+///   "api_key_env": "OPENAI_API_KEY",
+///   "priority": 1
+/// }
+/// ```
+///
+/// An Azure OpenAI deployment:
+///
+/// ```json
+/// {
+///   "name": "azure",
+///   "url": "https://my-resource.openai.azure.com",
+///   "model": "gpt-4o-mini",
+///   "api_key_env": "AZURE_OPENAI_API_KEY",
+///   "path": "/openai/deployments/{model}/chat/completions",
+///   "query": { "api-version": "2024-10-21" },
+///   "auth_header": "api-key"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendConfig {
+    pub name: String,
+    #[serde(default)]
+    pub kind: BackendKind,
+    #[serde(default)]
+    pub url: String,
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    #[serde(default)]
+    pub priority: u32,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub connect_timeout_secs: Option<f64>,
+    #[serde(default)]
+    pub read_timeout_secs: Option<f64>,
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+    #[serde(default)]
+    pub num_ctx: Option<u32>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    #[serde(default)]
+    pub auth_header: Option<String>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: BackendKind::default(),
+            url: String::new(),
+            model: String::new(),
+            api_key: None,
+            api_key_env: None,
+            priority: 0,
+            weight: default_weight(),
+            connect_timeout_secs: None,
+            read_timeout_secs: None,
+            keep_alive: None,
+            num_ctx: None,
+            path: None,
+            query: BTreeMap::new(),
+            auth_header: None,
+        }
+    }
+}
+
+impl BackendConfig {
+    /// Returns the settings that are set but have no effect on a backend of this kind,
+    /// e.g. a `path` of an Ollama backend.
+    pub fn ignored_settings(&self) -> Vec<&'static str> {
+        let openai_only = [
+            ("path", self.path.is_some()),
+            ("query", !self.query.is_empty()),
+            ("auth_header", self.auth_header.is_some()),
+        ];
+        let ollama_only = [
+            ("keep_alive", self.keep_alive.is_some()),
+            ("num_ctx", self.num_ctx.is_some()),
+        ];
+        let api_key = [
+            ("api_key", self.api_key.is_some()),
+            ("api_key_env", self.api_key_env.is_some()),
+        ];
+
+        let ignored: Vec<(&'static str, bool)> = match self.kind {
+            BackendKind::OpenAi => ollama_only.to_vec(),
+            BackendKind::Ollama => [openai_only.as_slice(), api_key.as_slice()].concat(),
+            BackendKind::Anthropic => [openai_only.as_slice(), ollama_only.as_slice()].concat(),
+        };
+        ignored
+            .into_iter()
+            .filter(|(_, set)| *set)
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// The order in which a request tries the backends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailoverStrategy {
+    /// Always start with the backend with the lowest priority.
+    #[default]
+    Priority,
+    /// Spread requests over the healthy backends by their weight; the others follow
+    /// by priority if the first one fails.
+    Weighted,
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 /// Represents a request payload for the chat API endpoint.
 ///
@@ -8,15 +9,183 @@ use serde::{Deserialize, Serialize};
 /// # Fields
 ///
 /// * `prompt` – The user-provided input that will be sent to the chat model.
+/// * `persona` – Optional name of the persona to answer as; the default persona is used if omitted.
+/// * `stream` – If `true`, the reply is streamed as Server-Sent Events while it is generated.
+/// * `backend` – Optional name of the backend that should answer, overriding the persona's.
 ///
 /// # Example
 ///
 /// ```json
 /// {
-///   "prompt": "Tell me a joke."
+///   "prompt": "Tell me a joke.",
+///   "persona": "pirate",
+///   "stream": true,
+///   "backend": "anthropic"
 /// }
 /// ```
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChatRequest {
     pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persona: Option<String>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+}
+
+/// The author of a [`ChatMessage`], serialized in lowercase (`"system"`, `"user"`, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// Instructions that steer the model's behavior.
+    System,
+    /// A message written by the user.
+    User,
+    /// A reply generated by the model.
+    Assistant,
+    /// The result of a tool call requested by the model.
+    Tool,
+}
+
+impl ChatRole {
+    /// Returns the role name as used by the Chat Completions API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "tool",
+        }
+    }
+}
+
+impl FromStr for ChatRole {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "system" => Ok(ChatRole::System),
+            "user" => Ok(ChatRole::User),
+            "assistant" => Ok(ChatRole::Assistant),
+            "tool" => Ok(ChatRole::Tool),
+            other => Err(format!("Unknown chat role: {}", other)),
+        }
+    }
+}
+
+/// A single turn of a conversation, as sent in the `messages` array
+/// of the Chat Completions API.
+///
+/// # Fields
+///
+/// * `role` – Who authored the message.
+/// * `content` – The message text.
+/// * `name` – Optional name of the author, to tell participants with the same role apart.
+/// * `tool_call_id` – For [`ChatRole::Tool`] messages, the ID of the tool call being answered.
+///
+/// # Example
+///
+/// ```rust
+/// use tg_ai_companion::models::chat::{ChatMessage, ChatRole};
+///
+/// let message = ChatMessage::user("Hello!");
+/// assert_eq!(message.role, ChatRole::User);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    /// Creates a message with the given role and content.
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            name: None,
+            tool_call_id: None,
+        }
+    }
+
+    /// Creates a system message (instructions for the model).
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content)
+    }
+
+    /// Creates a message authored by the user.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content)
+    }
+
+    /// Creates a message authored by the assistant (the model).
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content)
+    }
+
+    /// Creates a message carrying the result of the tool call `tool_call_id`.
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(ChatRole::Tool, content)
+        }
+    }
+}
+
+/// Generation options sent along with the messages.
+///
+/// Every field is optional; unset fields are not sent, so the backend's defaults apply.
+///
+/// # Fields
+///
+/// * `model` – Overrides the model configured for the backend.
+/// * `temperature` – Sampling temperature.
+/// * `top_p` – Nucleus sampling probability mass.
+/// * `max_tokens` – Upper bound for the number of generated tokens.
+/// * `stop` – Sequences where the model stops generating.
+/// * `backend` – Name of the backend that must answer, for APIs with several backends
+///   (see [`FailoverChatApi`](crate::services::failover_chat_api::FailoverChatApi)).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+}
+
+/// Token accounting reported by the backend for a completion.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// A structured model response.
+///
+/// # Fields
+///
+/// * `content` – The assistant's reply.
+/// * `model` – The model that actually produced the reply, if reported.
+/// * `finish_reason` – Why generation stopped (e.g. `"stop"`, `"length"`), if reported.
+/// * `usage` – Token usage, if reported.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatCompletion {
+    pub content: String,
+    pub model: Option<String>,
+    pub finish_reason: Option<String>,
+    pub usage: Option<ChatUsage>,
 }
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// The state of a backend's circuit breaker, serialized in snake_case (`"half_open"`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// The backend is healthy and receives requests.
+    #[default]
+    Closed,
+    /// The backend failed repeatedly; requests fail right away without calling it.
+    Open,
+    /// The backend was open long enough; the next request tests whether it recovered.
+    HalfOpen,
+}
+
+/// The health of one chat backend.
+///
+/// # Fields
+///
+/// * `name` – The backend's name, e.g. `localai`.
+/// * `state` – The state of the backend's circuit breaker.
+/// * `consecutive_failures` – Failed calls since the last successful one.
+/// * `last_error` – The most recent failure, if any.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackendHealth {
+    pub name: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl BackendHealth {
+    /// Returns whether the backend currently accepts requests.
+    pub fn is_available(&self) -> bool {
+        self.state != CircuitState::Open
+    }
+}
+
+/// The body of `GET /health`.
+///
+/// # Fields
+///
+/// * `status` – `ok` if at least one backend is available (or none reports its health),
+///   `unavailable` otherwise.
+/// * `backends` – The health of every chat backend.
+/// * `telegram_queue_depth` – Messages waiting to be sent to Telegram (or being sent),
+///   when the bot's sends go through the rate limiter.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "status": "ok",
+///   "backends": [{ "name": "localai", "state": "closed", "consecutive_failures": 0 }],
+///   "telegram_queue_depth": 0
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: String,
+    pub backends: Vec<BackendHealth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telegram_queue_depth: Option<usize>,
+}
@@ -1,2 +1,6 @@
+pub mod backend;
 pub mod chat;
+pub mod health;
+pub mod persona;
+pub mod storage;
 pub mod telegram;
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::chat::{ChatMessage, ChatOptions};
+
+/// A named assistant personality: the system prompt and generation defaults
+/// used for a chat or a request.
+///
+/// # Fields
+///
+/// * `name` – Unique name used to select the persona (e.g. with `/persona pirate`).
+/// * `description` – Optional short description shown in persona lists.
+/// * `system_prompt` – Instructions sent to the model as the first message; empty means none.
+/// * `model` – Optional model overriding the backend's default.
+/// * `temperature` – Optional sampling temperature.
+/// * `backend` – Optional name of the backend that answers as this persona.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "name": "pirate",
+///   "description": "Talks like a pirate",
+///   "system_prompt": "You are a friendly pirate. Answer like one.",
+///   "model": "mistral",
+///   "temperature": 0.9,
+///   "backend": "localai"
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub system_prompt: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub backend: Option<String>,
+}
+
+impl Persona {
+    /// Returns the system message of this persona, or `None` if it has no system prompt.
+    pub fn system_message(&self) -> Option<ChatMessage> {
+        if self.system_prompt.trim().is_empty() {
+            None
+        } else {
+            Some(ChatMessage::system(self.system_prompt.clone()))
+        }
+    }
+
+    /// Returns the generation options of this persona.
+    pub fn chat_options(&self) -> ChatOptions {
+        ChatOptions {
+            model: self.model.clone(),
+            temperature: self.temperature,
+            backend: self.backend.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Builds the messages sent to the model: the system prompt (if any),
+    /// followed by the given conversation.
+    pub fn build_messages(&self, conversation: Vec<ChatMessage>) -> Vec<ChatMessage> {
+        self.system_message()
+            .into_iter()
+            .chain(conversation)
+            .collect()
+    }
+}
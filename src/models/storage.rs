@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::telegram::{ChatType, TelegramChat, TelegramUser};
+
+/// A Telegram user known to the bot, as persisted by the storage layer.
+///
+/// # Fields
+///
+/// * `id` – The Telegram user ID.
+/// * `username` – The user's `@username`, without the leading `@`.
+/// * `first_name` – The user's first name.
+/// * `last_name` – The user's last name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredUser {
+    pub id: i64,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+impl StoredUser {
+    /// Builds the record of a user as Telegram describes it.
+    pub fn from_telegram(user: &TelegramUser) -> Self {
+        Self {
+            id: user.id,
+            username: user.username.clone(),
+            first_name: Some(user.first_name.clone()),
+            last_name: user.last_name.clone(),
+        }
+    }
+}
+
+/// A Telegram chat known to the bot, as persisted by the storage layer.
+///
+/// # Fields
+///
+/// * `id` – The Telegram chat ID (`TelegramChat::id`).
+/// * `chat_type` – The chat type (`private`, `group`, `supergroup` or `channel`).
+/// * `title` – The title of group chats.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredChat {
+    pub id: i64,
+    pub chat_type: Option<String>,
+    pub title: Option<String>,
+}
+
+impl StoredChat {
+    /// Builds the record of a chat as Telegram describes it.
+    ///
+    /// Chat types this code does not know are left unset.
+    pub fn from_telegram(chat: &TelegramChat) -> Self {
+        Self {
+            id: chat.id,
+            chat_type: chat
+                .chat_type
+                .filter(|chat_type| *chat_type != ChatType::Unknown)
+                .map(|chat_type| chat_type.as_str().to_string()),
+            title: chat.title.clone(),
+        }
+    }
+}
+
+/// Per-chat settings chosen by the chat's users.
+///
+/// Unset fields mean "use the application default".
+///
+/// # Fields
+///
+/// * `persona` – The name of the selected persona.
+/// * `model` – The model overriding the backend's default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatSettings {
+    pub persona: Option<String>,
+    pub model: Option<String>,
+}
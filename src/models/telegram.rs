@@ -1,33 +1,607 @@
 use serde::{Deserialize, Serialize};
 
+// Incoming objects mirror the Telegram Bot API. They are tolerant: optional fields may be
+// missing, unknown fields are ignored, and optional values that are absent are not serialized,
+// so a parsed object serializes back to the JSON Telegram sent (minus unknown fields).
+
+/// The kind of a Telegram chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatType {
+    /// A one-to-one chat with a user.
+    Private,
+    /// A basic group.
+    Group,
+    /// A large group, possibly with forum topics.
+    Supergroup,
+    /// A broadcast channel.
+    Channel,
+    /// A chat type added to the Bot API after this code was written.
+    #[serde(other)]
+    Unknown,
+}
+
+impl ChatType {
+    /// Returns the name Telegram uses for the chat type, e.g. `supergroup`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatType::Private => "private",
+            ChatType::Group => "group",
+            ChatType::Supergroup => "supergroup",
+            ChatType::Channel => "channel",
+            ChatType::Unknown => "unknown",
+        }
+    }
+}
+
+/// Represents a Telegram user or bot, e.g. the author of a message or the bot itself
+/// as returned by `getMe`.
+///
+/// # Fields
+/// - `id`: Unique identifier of the user or bot.
+/// - `is_bot`: Whether this is a bot.
+/// - `first_name`: First name (the bot's display name).
+/// - `last_name`: Last name, if any.
+/// - `username`: Username without the leading `@`, if any.
+/// - `language_code`: IETF language tag of the user's language, if known.
+/// - `is_premium`: Whether the user has Telegram Premium.
+/// - `can_join_groups`, `can_read_all_group_messages`, `supports_inline_queries`:
+///   Only returned for the bot itself by `getMe`.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#user
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelegramUser {
+    pub id: i64,
+    pub is_bot: bool,
+    pub first_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_premium: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub can_join_groups: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub can_read_all_group_messages: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_inline_queries: Option<bool>,
+}
+
 /// Represents a Telegram chat.
 ///
+/// # Fields
+/// - `id`: Unique identifier of the chat; negative for groups and channels.
+/// - `chat_type`: The kind of chat (`type` in JSON).
+/// - `title`: Title of a group or channel.
+/// - `username`: Username of a private chat, supergroup or channel, if any.
+/// - `first_name`, `last_name`: Name of the other party in a private chat.
+/// - `is_forum`: Whether the supergroup has topics enabled.
+///
 /// Details in the Telegram API documentation:
 /// https://core.telegram.org/bots/api#chat
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TelegramChat {
     pub id: i64,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub chat_type: Option<ChatType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_forum: Option<bool>,
+}
+
+impl TelegramChat {
+    /// Returns whether the chat is a group or a supergroup, where the bot only answers
+    /// messages addressed to it.
+    pub fn is_group(&self) -> bool {
+        matches!(
+            self.chat_type,
+            Some(ChatType::Group) | Some(ChatType::Supergroup)
+        )
+    }
+}
+
+/// Represents a special part of a message text, such as a mention, a command or a link.
+///
+/// `offset` and `length` count UTF-16 code units, as Telegram does.
+///
+/// # Fields
+/// - `entity_type`: The entity type (`type` in JSON), e.g. `mention`, `bot_command`, `url`,
+///   `text_link`, `text_mention`, `pre` or `custom_emoji`.
+/// - `offset`, `length`: Where the entity is in the text.
+/// - `url`: Target of a `text_link`.
+/// - `user`: The mentioned user of a `text_mention` (users without a username).
+/// - `language`: Programming language of a `pre` block.
+/// - `custom_emoji_id`: Identifier of a `custom_emoji`.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#messageentity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageEntity {
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub offset: usize,
+    pub length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<TelegramUser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_emoji_id: Option<String>,
+}
+
+/// Represents one size of a photo or a file thumbnail.
+///
+/// `file_id` identifies the file for downloading (`getFile`) or resending; `file_unique_id`
+/// is stable across bots but cannot be used to download the file.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#photosize
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhotoSize {
+    pub file_id: String,
+    pub file_unique_id: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+}
+
+/// Represents a voice note.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#voice
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Voice {
+    pub file_id: String,
+    pub file_unique_id: String,
+    /// Duration in seconds, as defined by the sender.
+    pub duration: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+}
+
+/// Represents an audio file, shown in Telegram's music player.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#audio
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Audio {
+    pub file_id: String,
+    pub file_unique_id: String,
+    /// Duration in seconds, as defined by the sender.
+    pub duration: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub performer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+}
+
+/// Represents a general file, as opposed to photos, voice notes and audio files.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub file_id: String,
+    pub file_unique_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<PhotoSize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+}
+
+/// Represents a video file.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#video
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Video {
+    pub file_id: String,
+    pub file_unique_id: String,
+    pub width: u32,
+    pub height: u32,
+    /// Duration in seconds, as defined by the sender.
+    pub duration: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<PhotoSize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+}
+
+/// Represents a sticker.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#sticker
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sticker {
+    pub file_id: String,
+    pub file_unique_id: String,
+    /// `regular`, `mask` or `custom_emoji` (`type` in JSON).
+    #[serde(rename = "type")]
+    pub sticker_type: String,
+    pub width: u32,
+    pub height: u32,
+    pub is_animated: bool,
+    pub is_video: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+}
+
+/// Represents a point on the map.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#location
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Represents a button of an inline keyboard attached to a message.
+///
+/// Exactly one of the optional fields should be set.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#inlinekeyboardbutton
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InlineKeyboardButton {
+    pub text: String,
+    /// Opened when the button is pressed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Sent back in a [`CallbackQuery`] when the button is pressed (1-64 bytes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_data: Option<String>,
+}
+
+/// Represents an inline keyboard, shown below a message.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#inlinekeyboardmarkup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InlineKeyboardMarkup {
+    /// The buttons, row by row.
+    pub inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
 }
 
 /// Represents a message from Telegram.
 ///
+/// A message carries a `text`, or a media attachment (`photo`, `voice`, `document`, ...)
+/// with an optional `caption`, or a service event such as `new_chat_members`.
+///
+/// # Fields
+/// - `message_id`: Unique identifier of the message inside its chat.
+/// - `message_thread_id`: The forum topic (or reply thread) the message belongs to.
+/// - `from`: The sender; missing for messages sent on behalf of a chat.
+/// - `sender_chat`: The chat the message was sent on behalf of, e.g. a channel.
+/// - `date`: Unix time the message was sent.
+/// - `chat`: The chat the message belongs to.
+/// - `is_topic_message`: Whether the message was sent to a forum topic.
+/// - `reply_to_message`: The message this one replies to.
+/// - `via_bot`: The bot the message was sent through (inline mode).
+/// - `edit_date`: Unix time the message was last edited.
+/// - `text`, `entities`: The text of a text message and its special parts.
+/// - `caption`, `caption_entities`: The caption of a media message and its special parts.
+/// - `photo`: The available sizes of a photo, smallest first.
+/// - `media_group_id`: Shared by the messages of an album.
+/// - `migrate_to_chat_id`: Service message: the group was upgraded to a supergroup with this ID.
+/// - `migrate_from_chat_id`: Service message: the supergroup was upgraded from a group with this ID.
+/// - `reply_markup`: The inline keyboard attached to the message.
+///
 /// Details in the Telegram API documentation:
 /// https://core.telegram.org/bots/api#message
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TelegramMessage {
     pub message_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_thread_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<TelegramUser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender_chat: Option<TelegramChat>,
+    #[serde(default)]
+    pub date: i64,
     pub chat: TelegramChat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_topic_message: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_message: Option<Box<TelegramMessage>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via_bot: Option<TelegramUser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edit_date: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<MessageEntity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub photo: Vec<PhotoSize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<Voice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<Audio>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<Document>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video: Option<Video>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sticker: Option<Sticker>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub caption_entities: Vec<MessageEntity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub new_chat_members: Vec<TelegramUser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left_chat_member: Option<TelegramUser>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrate_to_chat_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrate_from_chat_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<InlineKeyboardMarkup>,
+}
+
+impl TelegramMessage {
+    /// Returns the largest size of the message's photo, if it has one.
+    pub fn largest_photo(&self) -> Option<&PhotoSize> {
+        self.photo
+            .iter()
+            .max_by_key(|size| u64::from(size.width) * u64::from(size.height))
+    }
+}
+
+/// Represents a press of an inline keyboard button.
+///
+/// # Fields
+/// - `id`: Unique identifier of the query, needed to answer it (`answerCallbackQuery`).
+/// - `from`: The user who pressed the button.
+/// - `message`: The message the button is attached to; missing if it is too old.
+/// - `inline_message_id`: Identifier of the message, if it was sent in inline mode.
+/// - `chat_instance`: Identifies the chat the message was shown in.
+/// - `data`: The button's `callback_data`.
+/// - `game_short_name`: The game to launch, for game buttons.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#callbackquery
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CallbackQuery {
+    pub id: String,
+    pub from: TelegramUser,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Box<TelegramMessage>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_message_id: Option<String>,
+    #[serde(default)]
+    pub chat_instance: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_short_name: Option<String>,
+}
+
+/// Represents a query typed after the bot's username in any chat (`@my_bot query`).
+///
+/// # Fields
+/// - `id`: Unique identifier of the query, needed to answer it (`answerInlineQuery`).
+/// - `from`: The user typing the query.
+/// - `query`: The text of the query (up to 256 characters).
+/// - `offset`: Offset of the results to return, for pagination.
+/// - `chat_type`: The kind of chat the query was sent from, e.g. `sender` or `private`.
+/// - `location`: The user's location, for bots that request it.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#inlinequery
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InlineQuery {
+    pub id: String,
+    pub from: TelegramUser,
+    pub query: String,
+    #[serde(default)]
+    pub offset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+/// Represents a member of a chat and their status.
+///
+/// # Fields
+/// - `status`: `creator`, `administrator`, `member`, `restricted`, `left` or `kicked`.
+/// - `user`: The member.
+/// - `until_date`: Unix time a restriction or ban ends; `0` means forever.
+/// - `is_anonymous`: Whether an owner or administrator is hidden in the member list.
+/// - `custom_title`: Custom title of an owner or administrator.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#chatmember
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatMember {
+    pub status: String,
+    pub user: TelegramUser,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until_date: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_anonymous: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_title: Option<String>,
+}
+
+impl ChatMember {
+    /// Returns whether the member is (still) part of the chat.
+    pub fn is_present(&self) -> bool {
+        !matches!(self.status.as_str(), "left" | "kicked")
+    }
+
+    /// Returns whether the member is the chat's owner or one of its administrators.
+    pub fn is_admin(&self) -> bool {
+        matches!(self.status.as_str(), "creator" | "administrator")
+    }
+}
+
+/// Represents a change of a chat member's status, e.g. the bot being added to a group.
+///
+/// # Fields
+/// - `chat`: The chat the member belongs to.
+/// - `from`: The user who made the change.
+/// - `date`: Unix time of the change.
+/// - `old_chat_member`, `new_chat_member`: The member before and after the change.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#chatmemberupdated
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatMemberUpdated {
+    pub chat: TelegramChat,
+    pub from: TelegramUser,
+    pub date: i64,
+    pub old_chat_member: ChatMember,
+    pub new_chat_member: ChatMember,
 }
 
 /// Represents an incoming update from Telegram.
 ///
+/// At most one of the optional fields is set. Update types the model does not cover
+/// leave all of them empty.
+///
+/// # Fields
+/// - `update_id`: Sequential identifier of the update, used as the `getUpdates` offset.
+/// - `message`, `edited_message`: A new or edited message in a private chat or group.
+/// - `channel_post`, `edited_channel_post`: A new or edited post in a channel.
+/// - `callback_query`: A press of an inline keyboard button.
+/// - `inline_query`: A query in inline mode.
+/// - `my_chat_member`: A change of the bot's own membership in a chat.
+/// - `chat_member`: A change of another member's status; only sent to administrators
+///   when requested in `allowed_updates`.
+///
 /// Details in the Telegram API documentation:
 /// https://core.telegram.org/bots/api#update
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TelegramUpdate {
     pub update_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub message: Option<TelegramMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_message: Option<TelegramMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_post: Option<TelegramMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_channel_post: Option<TelegramMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_query: Option<CallbackQuery>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_query: Option<InlineQuery>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub my_chat_member: Option<ChatMemberUpdated>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_member: Option<ChatMemberUpdated>,
+}
+
+impl TelegramUpdate {
+    /// Returns the name of the update's type, e.g. `message` or `callback_query`,
+    /// or `unknown` for types the model does not cover.
+    pub fn kind(&self) -> &'static str {
+        if self.message.is_some() {
+            "message"
+        } else if self.edited_message.is_some() {
+            "edited_message"
+        } else if self.channel_post.is_some() {
+            "channel_post"
+        } else if self.edited_channel_post.is_some() {
+            "edited_channel_post"
+        } else if self.callback_query.is_some() {
+            "callback_query"
+        } else if self.inline_query.is_some() {
+            "inline_query"
+        } else if self.my_chat_member.is_some() {
+            "my_chat_member"
+        } else if self.chat_member.is_some() {
+            "chat_member"
+        } else {
+            "unknown"
+        }
+    }
+
+    /// Returns the ID of the chat the update happened in, if it belongs to one.
+    ///
+    /// Inline queries and callback queries from inline messages have no chat.
+    pub fn chat_id(&self) -> Option<i64> {
+        let message = self
+            .message
+            .as_ref()
+            .or(self.edited_message.as_ref())
+            .or(self.channel_post.as_ref())
+            .or(self.edited_channel_post.as_ref())
+            .or(self
+                .callback_query
+                .as_ref()
+                .and_then(|query| query.message.as_deref()));
+        let member_update = self.my_chat_member.as_ref().or(self.chat_member.as_ref());
+
+        message
+            .map(|message| message.chat.id)
+            .or(member_update.map(|update| update.chat.id))
+    }
+}
+
+/// How Telegram should interpret the formatting of a message text.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#formatting-options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParseMode {
+    /// The text uses Telegram's HTML subset (`<b>`, `<i>`, `<code>`, `<pre>`, `<a>`, ...).
+    #[serde(rename = "HTML")]
+    Html,
+    /// The text uses Telegram's MarkdownV2 syntax.
+    MarkdownV2,
+}
+
+impl ParseMode {
+    /// Returns the value Telegram expects in the `parse_mode` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParseMode::Html => "HTML",
+            ParseMode::MarkdownV2 => "MarkdownV2",
+        }
+    }
 }
 
 /// Represents a request to send a message via the Telegram Bot API.
@@ -37,19 +611,347 @@ pub struct TelegramUpdate {
 ///
 /// # Fields
 /// - `chat_id`: Unique identifier for the target chat. This ID is provided in each incoming Telegram update.
+/// - `message_thread_id`: The forum topic to send the message to.
 /// - `text`: The message text to be sent to the specified chat.
+/// - `parse_mode`: How the text is formatted; `None` sends it as plain text.
+/// - `reply_to_message_id`: The message this one answers, shown quoted above it.
+/// - `reply_markup`: An inline keyboard shown below the message.
 ///
 /// # Example
 /// ```rust
-/// use tg_ai_companion::models::telegram::SendMessageRequest;
+/// use tg_ai_companion::models::telegram::{ParseMode, SendMessageRequest};
 ///
 /// let request = SendMessageRequest {
 ///     chat_id: 123456789,
-///     text: "Hello, Telegram!".to_string(),
+///     text: "Hello, <b>Telegram</b>!".to_string(),
+///     parse_mode: Some(ParseMode::Html),
+///     ..Default::default()
 /// };
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SendMessageRequest {
     pub chat_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_thread_id: Option<i64>,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<ParseMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_message_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<InlineKeyboardMarkup>,
+}
+
+/// Represents a request to edit the text of a message via the Telegram Bot API.
+///
+/// This struct is serialized into JSON and sent in a POST request
+/// to the Telegram `editMessageText` endpoint.
+///
+/// # Fields
+/// - `chat_id`: Unique identifier of the chat the message belongs to.
+/// - `message_id`: Identifier of the message to edit, as returned by `sendMessage`.
+/// - `text`: The new message text.
+/// - `parse_mode`: How the text is formatted; `None` sends it as plain text.
+/// - `reply_markup`: The new inline keyboard; `None` removes it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EditMessageTextRequest {
+    pub chat_id: i64,
+    pub message_id: i64,
     pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<ParseMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<InlineKeyboardMarkup>,
+}
+
+/// Represents a request to delete a message (`deleteMessage`).
+///
+/// Bots can delete their own messages, and other messages in groups where they are
+/// administrators, for up to 48 hours after they were sent.
+///
+/// # Fields
+/// - `chat_id`: Unique identifier of the chat the message belongs to.
+/// - `message_id`: Identifier of the message to delete.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteMessageRequest {
+    pub chat_id: i64,
+    pub message_id: i64,
+}
+
+/// A file to send with `sendDocument`, `sendPhoto` or `sendVoice`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputFile {
+    /// A file already stored by Telegram, e.g. the `file_id` of a received photo.
+    FileId(String),
+    /// An HTTP URL Telegram downloads the file from.
+    Url(String),
+    /// A file uploaded with the request as `multipart/form-data`.
+    Upload { file_name: String, content: Vec<u8> },
+}
+
+/// Optional parameters of `sendDocument`, `sendPhoto` and `sendVoice`.
+///
+/// # Fields
+/// - `caption`: Text shown below the file.
+/// - `parse_mode`: How the caption is formatted; `None` sends it as plain text.
+/// - `reply_to_message_id`: The message the file answers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SendMediaOptions {
+    pub caption: Option<String>,
+    pub parse_mode: Option<ParseMode>,
+    pub reply_to_message_id: Option<i64>,
+}
+
+/// Represents a request to prepare a file for downloading (`getFile`).
+///
+/// # Fields
+/// - `file_id`: Identifier of the file, e.g. from a received voice note or document.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetFileRequest {
+    pub file_id: String,
+}
+
+/// Represents a request to get a member of a chat (`getChatMember`).
+///
+/// # Fields
+/// - `chat_id`: The chat the user may be a member of.
+/// - `user_id`: The user to look up.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetChatMemberRequest {
+    pub chat_id: i64,
+    pub user_id: i64,
+}
+
+/// Represents a file ready to be downloaded, as returned by `getFile`.
+///
+/// # Fields
+/// - `file_id`, `file_unique_id`: Identifiers of the file.
+/// - `file_size`: Size in bytes, if known.
+/// - `file_path`: Path to download the file from, valid for at least an hour;
+///   missing for files over 20 MB, which bots cannot download.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelegramFile {
+    pub file_id: String,
+    pub file_unique_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+}
+
+/// Represents a request to answer a [`CallbackQuery`] (`answerCallbackQuery`).
+///
+/// Telegram clients show a progress indicator until the query is answered.
+///
+/// # Fields
+/// - `callback_query_id`: The `id` of the query.
+/// - `text`: Notification shown to the user (0-200 characters).
+/// - `show_alert`: Whether to show an alert instead of a notification at the top of the chat.
+/// - `url`: URL the user's client opens.
+/// - `cache_time`: Seconds the answer may be cached by the client.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnswerCallbackQueryRequest {
+    pub callback_query_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_alert: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_time: Option<u32>,
+}
+
+/// The text of the message sent when the user picks an inline query result.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#inputtextmessagecontent
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputTextMessageContent {
+    pub message_text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<ParseMode>,
+}
+
+/// One result of an inline query; serialized with its `type`.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#inlinequeryresult
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InlineQueryResult {
+    /// A text result, e.g. an answer of the model.
+    Article {
+        /// Unique identifier of the result (1-64 bytes).
+        id: String,
+        title: String,
+        input_message_content: InputTextMessageContent,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+}
+
+/// Represents a request to answer an [`InlineQuery`] (`answerInlineQuery`).
+///
+/// # Fields
+/// - `inline_query_id`: The `id` of the query.
+/// - `results`: The results to show (at most 50).
+/// - `cache_time`: Seconds Telegram may cache the results.
+/// - `is_personal`: Whether the results may only be cached for the user who sent the query.
+/// - `next_offset`: Offset the client sends to get more results; empty if there are none.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnswerInlineQueryRequest {
+    pub inline_query_id: String,
+    pub results: Vec<InlineQueryResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_time: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_personal: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<String>,
+}
+
+/// An activity shown to the chat's members while the bot prepares a reply,
+/// e.g. "typing…" next to the bot's name.
+///
+/// Details in the Telegram API documentation:
+/// https://core.telegram.org/bots/api#sendchataction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatAction {
+    /// The bot is writing a text message.
+    Typing,
+    /// The bot is uploading a file.
+    UploadDocument,
+}
+
+/// Represents a request to show a chat action (`sendChatAction`).
+///
+/// Telegram shows the action for up to 5 seconds, or until the bot sends a message.
+///
+/// # Fields
+/// - `chat_id`: Unique identifier of the target chat.
+/// - `message_thread_id`: The forum topic to show the action in.
+/// - `action`: The activity to show.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendChatActionRequest {
+    pub chat_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_thread_id: Option<i64>,
+    pub action: ChatAction,
+}
+
+/// Represents a request to receive incoming updates via long polling (`getUpdates`).
+///
+/// # Fields
+/// - `offset`: Identifier of the first update to return; must be one greater than the highest
+///   `update_id` already processed, which also confirms the earlier updates to Telegram.
+/// - `timeout`: How many seconds Telegram keeps the request open while there are no updates.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetUpdatesRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    pub timeout: u64,
+}
+
+/// Represents a request to register the bot's webhook (`setWebhook`).
+///
+/// # Fields
+/// - `url`: HTTPS URL Telegram posts the updates to.
+/// - `secret_token`: Sent back by Telegram in the `X-Telegram-Bot-Api-Secret-Token` header
+///   of every webhook request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetWebhookRequest {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_token: Option<String>,
+}
+
+/// Represents a request to remove the bot's webhook (`deleteWebhook`).
+///
+/// # Fields
+/// - `drop_pending_updates`: Whether Telegram should discard the updates not delivered yet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteWebhookRequest {
+    pub drop_pending_updates: bool,
+}
+
+/// Represents the current webhook status, as returned by `getWebhookInfo`.
+///
+/// # Fields
+/// - `url`: The webhook URL; empty if no webhook is set (the bot uses `getUpdates`).
+/// - `pending_update_count`: Number of updates awaiting delivery.
+/// - `last_error_date`: Unix time of the most recent delivery error, if any.
+/// - `last_error_message`: Description of the most recent delivery error, if any.
+/// - `max_connections`: Maximum simultaneous connections Telegram opens to the webhook.
+/// - `ip_address`: IP address Telegram currently delivers the updates to.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookInfo {
+    pub url: String,
+    pub pending_update_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_date: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+}
+
+/// Represents a bot command shown in the Telegram command menu.
+///
+/// # Fields
+/// - `command`: Command name without the leading `/`; 1-32 lowercase letters, digits and `_`.
+/// - `description`: Short description shown next to the command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BotCommand {
+    pub command: String,
+    pub description: String,
+}
+
+/// Represents a request to publish the bot's command list (`setMyCommands`).
+///
+/// # Fields
+/// - `commands`: The commands, in the order they are shown in the menu.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetMyCommandsRequest {
+    pub commands: Vec<BotCommand>,
+}
+
+/// Represents the envelope of every Bot API response.
+///
+/// # Fields
+/// - `ok`: Whether the request succeeded.
+/// - `result`: The method's result, present when `ok` is `true`.
+/// - `error_code`: The error code, usually the HTTP status, when `ok` is `false`.
+/// - `description`: Human-readable description of the error.
+/// - `parameters`: Hints on how to repeat a failed request.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TelegramResponse<T> {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<ResponseParameters>,
+}
+
+/// Represents the hints Telegram adds to some failed requests.
+///
+/// # Fields
+/// - `migrate_to_chat_id`: The group was upgraded to a supergroup with this ID.
+/// - `retry_after`: Seconds to wait before repeating the request (flood control).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseParameters {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migrate_to_chat_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
 }
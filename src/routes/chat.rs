@@ -1,23 +1,24 @@
 use crate::handlers::chat::chat_endpoint;
 use crate::middleware::auth::validator;
 use crate::services::chat_api::ChatApi;
-use crate::services::chat_api_impl::RealChatApi;
+use crate::services::persona_registry::PersonaRegistry;
 use actix_web::dev;
 use actix_web::web;
 use actix_web_httpauth::middleware::HttpAuthentication;
-use std::sync::Arc;
 
 /// Initializes all Chat-related routes.
-pub fn init_chat_routes() -> impl dev::HttpServiceFactory {
+///
+/// The chat API and the persona registry are created by the caller, so they are
+/// loaded only once and the chat API's circuit breaker is shared with the bot.
+pub fn init_chat_routes(
+    chat_api: web::Data<dyn ChatApi>,
+    personas: web::Data<PersonaRegistry>,
+) -> impl dev::HttpServiceFactory {
     let auth = HttpAuthentication::with_fn(validator);
 
-    let real_api: RealChatApi = RealChatApi::new_from_env().expect("Failed to initialize Chat API");
-
-    let chat_api: Arc<dyn ChatApi> = Arc::new(real_api);
-    let chat_api_data: web::Data<dyn ChatApi> = web::Data::from(chat_api);
-
     web::scope("/chat")
         .wrap(auth)
-        .app_data(chat_api_data)
+        .app_data(chat_api)
+        .app_data(personas)
         .route("", web::post().to(chat_endpoint))
 }
@@ -0,0 +1,20 @@
+use crate::handlers::health::health_endpoint;
+use crate::services::chat_api::ChatApi;
+use crate::services::telegram_rate_limiter::RateLimitedTelegramApi;
+use actix_web::dev;
+use actix_web::web;
+
+/// Initializes the health route.
+///
+/// `GET /health` is not authenticated; it only exposes the state of the chat backends
+/// and, when `rate_limiter` is given, the number of messages queued for Telegram.
+pub fn init_health_routes(
+    chat_api: web::Data<dyn ChatApi>,
+    rate_limiter: Option<web::Data<RateLimitedTelegramApi>>,
+) -> impl dev::HttpServiceFactory {
+    let mut scope = web::scope("/health").app_data(chat_api);
+    if let Some(rate_limiter) = rate_limiter {
+        scope = scope.app_data(rate_limiter);
+    }
+    scope.route("", web::get().to(health_endpoint))
+}
@@ -1,26 +1,26 @@
-use actix_web::{web, Scope};
+use actix_web::{dev, middleware::from_fn, web};
 use std::sync::Arc;
 
 use crate::handlers::telegram::telegram_webhook;
-use crate::services::chat_api::ChatApi;
-use crate::services::chat_api_impl::RealChatApi;
-use crate::services::telegram_api::TelegramApi;
-use crate::services::telegram_api_impl::RealTelegramApi;
+use crate::middleware::telegram_secret::{verify_secret_token, WebhookSecret};
+use crate::services::update_processor::UpdateProcessor;
 
 /// Initializes all Telegram-related routes.
-pub fn init_telegram_routes() -> Scope {
-    let real_chat_api: RealChatApi =
-        RealChatApi::new_from_env().expect("Failed to initialize Chat API");
-    let chat_api: Arc<dyn ChatApi> = Arc::new(real_chat_api);
-    let chat_api_data: web::Data<dyn ChatApi> = web::Data::from(chat_api);
-
-    let real_telegram_api: RealTelegramApi =
-        RealTelegramApi::new_from_env().expect("Failed to initialize Telegram API");
-    let telegram_api: Arc<dyn TelegramApi> = Arc::new(real_telegram_api);
-    let telegram_api_data: web::Data<dyn TelegramApi> = web::Data::from(telegram_api);
+///
+/// The update processor is created by the caller, so that every worker of the HTTP server
+/// shares the same pipeline (and the long polling runner could share it too).
+/// With a `secret`, webhook requests without the matching
+/// `X-Telegram-Bot-Api-Secret-Token` header are rejected.
+pub fn init_telegram_routes(
+    processor: web::Data<UpdateProcessor>,
+    secret: Option<WebhookSecret>,
+) -> impl dev::HttpServiceFactory {
+    let secret = secret.map(Arc::new);
 
     web::scope("/telegram")
-        .app_data(chat_api_data)
-        .app_data(telegram_api_data)
+        .wrap(from_fn(move |req, next| {
+            verify_secret_token(secret.clone(), req, next)
+        }))
+        .app_data(processor)
         .route("/webhook", web::post().to(telegram_webhook))
 }
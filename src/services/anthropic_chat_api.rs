@@ -0,0 +1,374 @@
+use async_trait::async_trait;
+use futures_util::future;
+use futures_util::stream::StreamExt;
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+use crate::models::backend::BackendConfig;
+use crate::models::chat::{ChatCompletion, ChatMessage, ChatOptions, ChatRole, ChatUsage};
+use crate::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use crate::services::chat_http::{
+    api_key_from_config, build_client, check_settings, error_from_json, read_json,
+    secs_from_config, secs_from_env, send, DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT,
+};
+use crate::services::event_stream::sse_data;
+
+/// Default base URL of the Anthropic API.
+pub const DEFAULT_ANTHROPIC_URL: &str = "https://api.anthropic.com";
+
+/// Default value of the `anthropic-version` header.
+pub const DEFAULT_ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Default upper bound for the number of generated tokens; the Messages API requires one.
+pub const DEFAULT_ANTHROPIC_MAX_TOKENS: u32 = 1024;
+
+/// `AnthropicChatApi` is an implementation of the [`ChatApi`] trait for the Anthropic
+/// Messages API (`/v1/messages`).
+///
+/// The Messages API differs from the Chat Completions API: system prompts go into a separate
+/// `system` field, the key is sent in an `x-api-key` header together with an
+/// `anthropic-version` header, replies are lists of content blocks, `max_tokens` is required
+/// and streams are typed Server-Sent Events (`content_block_delta`, `message_stop`, ...).
+///
+/// Environment variables used:
+/// - `ANTHROPIC_URL` — optional base URL, defaults to [`DEFAULT_ANTHROPIC_URL`]
+/// - `ANTHROPIC_MODEL` — model name (e.g. `claude-sonnet-4-5`)
+/// - `ANTHROPIC_API_KEY` — API key
+/// - `ANTHROPIC_VERSION` — optional API version, defaults to [`DEFAULT_ANTHROPIC_VERSION`]
+/// - `ANTHROPIC_MAX_TOKENS` — optional default for `max_tokens`
+/// - `ANTHROPIC_CONNECT_TIMEOUT_SECS` / `ANTHROPIC_READ_TIMEOUT_SECS` — optional timeouts
+pub struct AnthropicChatApi {
+    client: Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    version: String,
+    max_tokens: u32,
+}
+
+/// Request body of the Messages API.
+#[derive(Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+/// A conversation turn of the Messages API, which only knows users and the assistant.
+#[derive(Serialize)]
+struct AnthropicMessage {
+    role: &'static str,
+    content: String,
+}
+
+impl AnthropicChatApi {
+    /// Creates a new instance of [`AnthropicChatApi`] with the provided settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API (e.g. [`DEFAULT_ANTHROPIC_URL`]).
+    /// * `model` - The model name to use unless a request overrides it.
+    /// * `api_key` - The API key sent in the `x-api-key` header.
+    pub fn new(base_url: String, model: String, api_key: Option<String>) -> Self {
+        Self {
+            client: build_client(DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT),
+            base_url,
+            model,
+            api_key,
+            version: DEFAULT_ANTHROPIC_VERSION.to_string(),
+            max_tokens: DEFAULT_ANTHROPIC_MAX_TOKENS,
+        }
+    }
+
+    /// Sets the `anthropic-version` header.
+    pub fn with_version(mut self, version: String) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets `max_tokens` for requests whose options do not set it.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Replaces the client's timeouts (see [`RealChatApi::with_timeouts`](crate::services::chat_api_impl::RealChatApi::with_timeouts)).
+    pub fn with_timeouts(mut self, connect: Duration, read: Duration) -> Self {
+        self.client = build_client(connect, read);
+        self
+    }
+
+    /// Creates a new instance of [`AnthropicChatApi`] from environment variables.
+    ///
+    /// Requires `ANTHROPIC_MODEL` and `ANTHROPIC_API_KEY` to be set and non-empty; `ANTHROPIC_URL`,
+    /// `ANTHROPIC_VERSION`, `ANTHROPIC_MAX_TOKENS`, `ANTHROPIC_CONNECT_TIMEOUT_SECS` and
+    /// `ANTHROPIC_READ_TIMEOUT_SECS` are optional.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required variable is missing or empty, or a number is invalid.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let base_url = optional_env("ANTHROPIC_URL").unwrap_or(DEFAULT_ANTHROPIC_URL.to_string());
+        let model = optional_env("ANTHROPIC_MODEL")
+            .ok_or("Environment variable ANTHROPIC_MODEL is not set or empty")?;
+        let api_key = optional_env("ANTHROPIC_API_KEY")
+            .ok_or("Environment variable ANTHROPIC_API_KEY is not set or empty")?;
+        let version =
+            optional_env("ANTHROPIC_VERSION").unwrap_or(DEFAULT_ANTHROPIC_VERSION.to_string());
+        let max_tokens = match optional_env("ANTHROPIC_MAX_TOKENS") {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| "Environment variable ANTHROPIC_MAX_TOKENS must be a number")?,
+            None => DEFAULT_ANTHROPIC_MAX_TOKENS,
+        };
+
+        let connect = secs_from_env("ANTHROPIC_CONNECT_TIMEOUT_SECS", DEFAULT_CONNECT_TIMEOUT)?;
+        let read = secs_from_env("ANTHROPIC_READ_TIMEOUT_SECS", DEFAULT_READ_TIMEOUT)?;
+
+        Ok(Self::new(base_url, model, Some(api_key))
+            .with_version(version)
+            .with_max_tokens(max_tokens)
+            .with_timeouts(connect, read))
+    }
+
+    /// Creates a new instance of [`AnthropicChatApi`] for a backend of a backends file,
+    /// using its `url` (defaulting to [`DEFAULT_ANTHROPIC_URL`]), `model`, key and timeouts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model is empty, the backend's `api_key_env` variable is not set,
+    /// a timeout is not a positive number, or an OpenAI- or Ollama-only setting is set.
+    pub fn from_config(config: &BackendConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        check_settings(config)?;
+        if config.model.trim().is_empty() {
+            return Err(format!("Backend {} has no model", config.name).into());
+        }
+        let base_url = if config.url.trim().is_empty() {
+            DEFAULT_ANTHROPIC_URL.to_string()
+        } else {
+            config.url.clone()
+        };
+
+        let api_key = api_key_from_config(config)?;
+        let connect = secs_from_config(config.connect_timeout_secs, DEFAULT_CONNECT_TIMEOUT)?;
+        let read = secs_from_config(config.read_timeout_secs, DEFAULT_READ_TIMEOUT)?;
+
+        Ok(Self::new(base_url, config.model.clone(), api_key).with_timeouts(connect, read))
+    }
+
+    /// Builds the `/v1/messages` request for the given conversation.
+    ///
+    /// System messages are joined into the `system` field; the other messages keep their order,
+    /// see [`anthropic_messages`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the conversation contains tool messages.
+    fn build_request(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> Result<RequestBuilder, ChatApiError> {
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|message| message.role == ChatRole::System)
+            .map(|message| message.content.as_str())
+            .collect();
+
+        let body = MessagesRequest {
+            model: options.model.as_deref().unwrap_or(&self.model),
+            max_tokens: options.max_tokens.unwrap_or(self.max_tokens),
+            system: (!system.is_empty()).then(|| system.join("\n\n")),
+            messages: anthropic_messages(messages)?,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            stop_sequences: options.stop.as_deref(),
+            stream,
+        };
+
+        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
+
+        let mut request = self
+            .client
+            .post(&url)
+            .header("anthropic-version", &self.version)
+            .json(&body);
+
+        if let Some(key) = &self.api_key {
+            request = request.header("x-api-key", key);
+        }
+
+        Ok(request)
+    }
+}
+
+/// Converts the user and assistant messages of a conversation into Messages API turns.
+///
+/// The Messages API expects the roles to alternate, so adjacent messages of the same role
+/// are merged into one turn.
+///
+/// # Errors
+///
+/// Tool results have to answer a `tool_use` block of the previous assistant turn, which
+/// [`ChatMessage`] cannot carry, so a conversation with tool messages is rejected as a
+/// `400` [`ChatApiError::Backend`] error, which another backend may still answer.
+fn anthropic_messages(messages: &[ChatMessage]) -> Result<Vec<AnthropicMessage>, ChatApiError> {
+    let mut turns: Vec<AnthropicMessage> = Vec::new();
+
+    for message in messages {
+        let role = match message.role {
+            ChatRole::System => continue,
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+            ChatRole::Tool => {
+                return Err(ChatApiError::Backend {
+                    status: 400,
+                    message: "Tool messages are not supported by the Anthropic backend".to_string(),
+                })
+            }
+        };
+
+        match turns.last_mut() {
+            Some(last) if last.role == role => {
+                last.content.push_str("\n\n");
+                last.content.push_str(&message.content);
+            }
+            _ => turns.push(AnthropicMessage {
+                role,
+                content: message.content.clone(),
+            }),
+        }
+    }
+
+    Ok(turns)
+}
+
+/// Reads an environment variable, treating an empty value as unset.
+fn optional_env(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+#[async_trait]
+impl ChatApi for AnthropicChatApi {
+    /// Sends a non-streaming request to `/v1/messages`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChatCompletion)` — the text of all `text` content blocks, with the model,
+    ///   `stop_reason` and token usage.
+    /// * `Err(ChatApiError)` — if the request fails, the API answers with an error
+    ///   (e.g. `authentication_error` is [`ChatApiError::Auth`], "prompt is too long" is
+    ///   [`ChatApiError::ContextLengthExceeded`]), the model refused to answer
+    ///   ([`ChatApiError::ContentFiltered`]) or the reply has no content blocks.
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        let response = send(self.build_request(messages, options, false)?).await?;
+        let status = response.status().as_u16();
+        let json = read_json(response).await?;
+        if !json["error"].is_null() {
+            return Err(error_from_json(status, None, &json));
+        }
+
+        let blocks = json["content"].as_array().ok_or_else(|| {
+            ChatApiError::MalformedResponse("Missing content in the response!".to_string())
+        })?;
+        let content: String = blocks
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+
+        let stop_reason = json["stop_reason"].as_str();
+        if stop_reason == Some("refusal") && content.is_empty() {
+            return Err(ChatApiError::ContentFiltered(
+                "The model refused to answer".to_string(),
+            ));
+        }
+
+        let usage = &json["usage"];
+        let usage = match (
+            usage["input_tokens"].as_u64(),
+            usage["output_tokens"].as_u64(),
+        ) {
+            (Some(input), Some(output)) => Some(ChatUsage {
+                prompt_tokens: input as u32,
+                completion_tokens: output as u32,
+                total_tokens: (input + output) as u32,
+            }),
+            _ => None,
+        };
+
+        Ok(ChatCompletion {
+            content,
+            model: json["model"].as_str().map(str::to_string),
+            finish_reason: stop_reason.map(str::to_string),
+            usage,
+        })
+    }
+
+    /// Sends a streaming request to `/v1/messages` and yields the reply's fragments.
+    ///
+    /// The API answers with Server-Sent Events whose `data` is a JSON object with a `type`;
+    /// text arrives in `content_block_delta` events with a `text_delta`, and the stream ends
+    /// with `message_stop`. Other events (`message_start`, `ping`, ...) are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API answers with a non-success status.
+    /// The stream yields an error if an event is not valid JSON, is an `error` event
+    /// (e.g. `overloaded_error`), or the connection breaks.
+    async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatStream, ChatApiError> {
+        let response = send(self.build_request(messages, options, true)?).await?;
+        let status = response.status().as_u16();
+
+        let fragments = sse_data(response)
+            .map(move |data| {
+                let data = data.map_err(|e| ChatApiError::Transport(e.to_string()))?;
+                serde_json::from_str::<Value>(&data)
+                    .map_err(|e| ChatApiError::MalformedResponse(e.to_string()))
+            })
+            .take_while(|event| {
+                future::ready(!matches!(event, Ok(event) if event["type"] == "message_stop"))
+            })
+            .filter_map(move |event| async move {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => return Some(Err(e)),
+                };
+
+                match event["type"].as_str() {
+                    Some("error") => Some(Err(error_from_json(status, None, &event))),
+                    Some("content_block_delta") => event["delta"]["text"]
+                        .as_str()
+                        .filter(|text| !text.is_empty())
+                        .map(|text| Ok(text.to_string())),
+                    _ => None,
+                }
+            });
+
+        Ok(Box::pin(fragments))
+    }
+}
@@ -1,5 +1,131 @@
 use async_trait::async_trait;
-use std::error::Error;
+use futures_util::stream::{self, Stream};
+use std::fmt;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use crate::models::health::BackendHealth;
+
+/// A stream of text fragments (tokens) of a model's reply, in generation order.
+///
+/// Concatenating all fragments gives the whole reply. The stream ends when generation is complete,
+/// or yields an error and ends if the backend fails mid-way.
+pub type ChatStream = Pin<Box<dyn Stream<Item = Result<String, ChatApiError>> + Send>>;
+
+/// An error returned by a chat API call.
+///
+/// The variants tell apart the failures callers handle differently, e.g. a backend that is down
+/// from a conversation that no longer fits the model's context. Handlers map them to an HTTP
+/// status, and [`ChatApiError::user_message`] gives a message fit for end users.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatApiError {
+    /// The backend could not be reached or the connection broke.
+    Transport(String),
+    /// The backend did not answer in time.
+    Timeout,
+    /// The backend refused the credentials (`401` / `403`).
+    Auth(String),
+    /// The backend asked to slow down (`429`), optionally saying for how long.
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// The conversation does not fit the model's context window.
+    ContextLengthExceeded(String),
+    /// The backend's content filter blocked the prompt or the reply.
+    ContentFiltered(String),
+    /// The backend answered with something that is not a valid completion.
+    MalformedResponse(String),
+    /// The backend answered with another error.
+    Backend { status: u16, message: String },
+    /// The backend is considered down and was not called (its circuit breaker is open).
+    Unavailable(String),
+}
+
+impl ChatApiError {
+    /// Returns a short message explaining the error to the user of the bot or the API.
+    ///
+    /// Unlike the [`Display`](fmt::Display) output, it contains no backend details.
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            ChatApiError::Transport(_) | ChatApiError::MalformedResponse(_) => {
+                "The language model is not available right now. Please try again later."
+            }
+            ChatApiError::Timeout => {
+                "The language model took too long to answer. Please try again."
+            }
+            ChatApiError::Auth(_) => {
+                "The language model refused the bot's credentials. Please tell the bot's owner."
+            }
+            ChatApiError::RateLimited { .. } => {
+                "The language model is busy. Please try again in a moment."
+            }
+            ChatApiError::ContextLengthExceeded(_) => {
+                "The conversation is too long for the model. Please send a shorter message or start over with /reset."
+            }
+            ChatApiError::ContentFiltered(_) => {
+                "The language model's content filter blocked this request."
+            }
+            ChatApiError::Backend { .. } => {
+                "The language model failed to answer. Please try again later."
+            }
+            ChatApiError::Unavailable(_) => {
+                "The language model is temporarily unavailable. Please try again later."
+            }
+        }
+    }
+
+    /// Returns whether the error is a failure of the backend itself, which may go away
+    /// when the request is repeated: a broken connection, a timeout, a rate limit or a `5xx`.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ChatApiError::Transport(_)
+            | ChatApiError::Timeout
+            | ChatApiError::RateLimited { .. } => true,
+            ChatApiError::Backend { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Returns how long the backend asked to wait before the next request, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ChatApiError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ChatApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChatApiError::Transport(message) => write!(f, "Chat API unreachable: {}", message),
+            ChatApiError::Timeout => write!(f, "Chat API timed out"),
+            ChatApiError::Auth(message) => {
+                write!(f, "Chat API refused the credentials: {}", message)
+            }
+            ChatApiError::RateLimited { message, .. } => {
+                write!(f, "Chat API rate limit exceeded: {}", message)
+            }
+            ChatApiError::ContextLengthExceeded(message) => {
+                write!(f, "Chat API context length exceeded: {}", message)
+            }
+            ChatApiError::ContentFiltered(message) => {
+                write!(f, "Chat API content filter: {}", message)
+            }
+            ChatApiError::MalformedResponse(message) => {
+                write!(f, "Malformed chat API response: {}", message)
+            }
+            ChatApiError::Backend { status, message } => {
+                write!(f, "Chat API error {}: {}", status, message)
+            }
+            ChatApiError::Unavailable(message) => write!(f, "Chat API unavailable: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ChatApiError {}
 
 /// Defines the interface for a chat-based language model API (e.g., OpenAI, LocalAI).
 ///
@@ -7,11 +133,16 @@ use std::error::Error;
 /// (e.g., real HTTP clients, mocks for testing).
 ///
 /// Any implementation must be thread-safe (`Send + Sync`) and provide an asynchronous
-/// method for sending prompts and receiving model-generated responses.
+/// method for sending conversations and receiving model-generated responses.
+/// Implementations only have to provide [`ChatApi::complete`]; the single-prompt
+/// [`ChatApi::call_chat_api`] is a thin wrapper around it.
 #[async_trait]
 pub trait ChatApi: Send + Sync {
     /// Sends a prompt to a chat API and returns the assistant's response.
     ///
+    /// This is a shortcut for [`ChatApi::complete`] with a single user message
+    /// and default options.
+    ///
     /// # Arguments
     ///
     /// * `prompt` - The user's input message or question to be sent to the model.
@@ -19,35 +150,112 @@ pub trait ChatApi: Send + Sync {
     /// # Returns
     ///
     /// * `Ok(String)` — The model's response as a plain string.
-    /// * `Err(Box<dyn std::error::Error + Send + Sync>)` — If the API call or response parsing fails.
+    /// * `Err(ChatApiError)` — If the API call or response parsing fails.
     ///
     /// # Example
     ///
     /// ```no_run
     /// use async_trait::async_trait;
-    /// use std::error::Error;
-    ///
-    /// #[async_trait]
-    /// trait ChatApi: Send + Sync {
-    ///     async fn call_chat_api(&self, prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>>;
-    /// }
+    /// use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+    /// use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
     ///
     /// struct DummyApi;
     ///
     /// #[async_trait]
     /// impl ChatApi for DummyApi {
-    ///     async fn call_chat_api(&self, _prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
-    ///         Ok("Dummy response".to_string())
+    ///     async fn complete(
+    ///         &self,
+    ///         _messages: &[ChatMessage],
+    ///         _options: &ChatOptions,
+    ///     ) -> Result<ChatCompletion, ChatApiError> {
+    ///         Ok(ChatCompletion {
+    ///             content: "Dummy response".to_string(),
+    ///             ..Default::default()
+    ///         })
     ///     }
     /// }
     ///
     /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    /// async fn main() -> Result<(), ChatApiError> {
     ///     let chat_api = DummyApi;
     ///     let response = chat_api.call_chat_api("What's the weather today?").await?;
     ///     println!("Model response: {}", response);
     ///     Ok(())
     /// }
     /// ```
-    async fn call_chat_api(&self, prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>>;
+    async fn call_chat_api(&self, prompt: &str) -> Result<String, ChatApiError> {
+        let completion = self
+            .complete(&[ChatMessage::user(prompt)], &ChatOptions::default())
+            .await?;
+
+        Ok(completion.content)
+    }
+
+    /// Sends a whole conversation to a chat API and returns the structured completion.
+    ///
+    /// The messages are sent in the given order, so the last one is usually the
+    /// user's newest message and the preceding ones are the system prompt and earlier turns.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation turns, oldest first.
+    /// * `options` - Generation options; unset fields fall back to the backend's defaults.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChatCompletion)` — The model's reply together with metadata like token usage.
+    /// * `Err(ChatApiError)` — If the API call or response parsing fails.
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError>;
+
+    /// Sends a whole conversation to a chat API and streams the reply as it is generated.
+    ///
+    /// The default implementation waits for [`ChatApi::complete`] and yields its content
+    /// as a single fragment, so backends without streaming support still work.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation turns, oldest first.
+    /// * `options` - Generation options; unset fields fall back to the backend's defaults.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChatStream)` — The stream of reply fragments.
+    /// * `Err(ChatApiError)` — If the request could not be started.
+    async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatStream, ChatApiError> {
+        let completion = self.complete(messages, options).await?;
+
+        Ok(Box::pin(stream::once(
+            async move { Ok(completion.content) },
+        )))
+    }
+
+    /// Sends a minimal request to check whether the backend answers, e.g. to recover
+    /// a backend marked unhealthy.
+    ///
+    /// The default implementation asks for a single token.
+    async fn probe(&self) -> Result<(), ChatApiError> {
+        let options = ChatOptions {
+            max_tokens: Some(1),
+            ..Default::default()
+        };
+
+        self.complete(&[ChatMessage::user("ping")], &options)
+            .await
+            .map(|_| ())
+    }
+
+    /// Reports the health of the backends behind this API, e.g. for `GET /health`.
+    ///
+    /// The default implementation reports nothing, for APIs that do not track their health.
+    fn health(&self) -> Vec<BackendHealth> {
+        Vec::new()
+    }
 }
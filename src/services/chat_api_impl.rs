@@ -1,9 +1,24 @@
-use crate::services::chat_api::ChatApi;
+use crate::models::backend::BackendConfig;
+use crate::models::chat::{ChatCompletion, ChatMessage, ChatOptions, ChatUsage};
+use crate::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use crate::services::chat_http::{
+    api_key_from_config, build_client, check_settings, error_from_json, read_json,
+    secs_from_config, secs_from_env, send, DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT,
+};
+use crate::services::event_stream::sse_data;
 use async_trait::async_trait;
-use reqwest::{Client, RequestBuilder, Response};
-use serde_json::{json, Value};
+use futures_util::future;
+use futures_util::stream::StreamExt;
+use reqwest::header::HeaderName;
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use serde_json::Value;
 use std::env;
 use std::error::Error;
+use std::time::Duration;
+
+/// Default path of the Chat Completions endpoint, appended to the base URL.
+pub const DEFAULT_CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
 
 /// `RealChatApi` is a concrete implementation of the [`ChatApi`] trait
 /// that uses an OpenAI-compatible REST API (e.g., OpenAI, LocalAI).
@@ -16,25 +31,120 @@ use std::error::Error;
 /// - `OPEN_AI_URL` — base URL of the API (e.g. `http://localhost:8080` or `https://api.openai.com`)
 /// - `OPEN_AI_MODEL` — model name (e.g. `gpt-3.5-turbo`, `mistral`)
 /// - `OPEN_AI_API_KEY` — optional API key (required for OpenAI)
+/// - `OPEN_AI_CONNECT_TIMEOUT_SECS` — optional connect timeout
+/// - `OPEN_AI_READ_TIMEOUT_SECS` — optional timeout for each read of the response
+/// - `OPEN_AI_PATH` — optional endpoint path template (e.g. Azure's
+///   `/openai/deployments/{model}/chat/completions`)
+/// - `OPEN_AI_QUERY` — optional query string sent with every request (e.g. `api-version=2024-10-21`)
+/// - `OPEN_AI_AUTH_HEADER` — optional header carrying the bare API key (e.g. Azure's `api-key`)
 pub struct RealChatApi {
     client: Client,
     base_url: String,
     model: String,
     api_key: Option<String>,
+    path: String,
+    query: Vec<(String, String)>,
+    auth_header: Option<String>,
+}
+
+/// Request body of the Chat Completions API.
+///
+/// Generation options are flattened into the body and omitted when unset.
+#[derive(Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 impl RealChatApi {
+    /// Creates a new instance of [`RealChatApi`] with the provided settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API (e.g. `http://localhost:8080`).
+    /// * `model` - The model name to use unless a request overrides it.
+    /// * `api_key` - Optional API key sent as a Bearer token.
+    ///
+    /// The client uses [`DEFAULT_CONNECT_TIMEOUT`] and [`DEFAULT_READ_TIMEOUT`];
+    /// see [`RealChatApi::with_timeouts`] to change them.
+    pub fn new(base_url: String, model: String, api_key: Option<String>) -> Self {
+        Self {
+            client: build_client(DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT),
+            base_url,
+            model,
+            api_key,
+            path: DEFAULT_CHAT_COMPLETIONS_PATH.to_string(),
+            query: Vec::new(),
+            auth_header: None,
+        }
+    }
+
+    /// Replaces the endpoint path, [`DEFAULT_CHAT_COMPLETIONS_PATH`] by default.
+    ///
+    /// `{model}` in the path is replaced by the request's model, percent-encoded, as Azure
+    /// OpenAI expects the deployment there (`/openai/deployments/{model}/chat/completions`).
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sets query parameters sent with every request, such as Azure's `api-version`.
+    pub fn with_query(mut self, query: Vec<(String, String)>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Sends the API key as the value of the given header (e.g. Azure's `api-key`)
+    /// instead of `Authorization: Bearer <key>`; `None` restores the default.
+    pub fn with_auth_header(mut self, auth_header: Option<String>) -> Self {
+        self.auth_header = auth_header;
+        self
+    }
+
+    /// Replaces the client's timeouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect` - How long to wait for a connection to the API.
+    /// * `read` - How long to wait for each read of a response. Streams are not
+    ///   cut off as long as fragments keep arriving.
+    ///
+    /// A request running into either timeout fails with [`ChatApiError::Timeout`].
+    pub fn with_timeouts(mut self, connect: Duration, read: Duration) -> Self {
+        self.client = build_client(connect, read);
+        self
+    }
+
     /// Creates a new instance of [`RealChatApi`] from environment variables.
     ///
     /// Requires the following environment variables to be set and non-empty:
     /// - `OPEN_AI_URL` — the base URL of the API
     /// - `OPEN_AI_MODEL` — the model name to use
     /// - `OPEN_AI_API_KEY` — (optional) API key for authorization
+    /// - `OPEN_AI_CONNECT_TIMEOUT_SECS` — (optional) connect timeout, defaults to
+    ///   [`DEFAULT_CONNECT_TIMEOUT`]
+    /// - `OPEN_AI_READ_TIMEOUT_SECS` — (optional) read timeout, defaults to
+    ///   [`DEFAULT_READ_TIMEOUT`]
+    /// - `OPEN_AI_PATH` — (optional) endpoint path, defaults to [`DEFAULT_CHAT_COMPLETIONS_PATH`]
+    /// - `OPEN_AI_QUERY` — (optional) query parameters as `name=value&name=value`
+    /// - `OPEN_AI_AUTH_HEADER` — (optional) header carrying the API key instead of a Bearer token
     ///
     /// # Returns
     ///
     /// - `Ok(Self)` if all required variables are set correctly.
-    /// - `Err` if any required environment variable is missing or empty.
+    /// - `Err` if any required environment variable is missing or empty,
+    ///   a timeout is not a positive number, `OPEN_AI_QUERY` is not `name=value` pairs
+    ///   or `OPEN_AI_AUTH_HEADER` is not a valid header name.
     ///
     /// # Example
     ///
@@ -59,15 +169,171 @@ impl RealChatApi {
             return Err("Environment variable OPEN_AI_MODEL cannot be empty".into());
         }
 
-        let api_key = env::var("OPEN_AI_API_KEY").ok();
+        let api_key = env::var("OPEN_AI_API_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty());
 
-        Ok(Self {
-            client: Client::new(),
-            base_url,
+        let connect = secs_from_env("OPEN_AI_CONNECT_TIMEOUT_SECS", DEFAULT_CONNECT_TIMEOUT)?;
+        let read = secs_from_env("OPEN_AI_READ_TIMEOUT_SECS", DEFAULT_READ_TIMEOUT)?;
+
+        let path = env::var("OPEN_AI_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_CHAT_COMPLETIONS_PATH.to_string());
+        let query = match env::var("OPEN_AI_QUERY") {
+            Ok(query) => parse_query(&query)
+                .ok_or("Environment variable OPEN_AI_QUERY must be name=value pairs joined by &")?,
+            Err(_) => Vec::new(),
+        };
+        let auth_header = env::var("OPEN_AI_AUTH_HEADER")
+            .ok()
+            .filter(|header| !header.trim().is_empty());
+        if let Some(header) = &auth_header {
+            check_header_name(header).map_err(|_| {
+                "Environment variable OPEN_AI_AUTH_HEADER is not a valid header name"
+            })?;
+        }
+
+        Ok(Self::new(base_url, model, api_key)
+            .with_timeouts(connect, read)
+            .with_path(path)
+            .with_query(query)
+            .with_auth_header(auth_header))
+    }
+
+    /// Creates a new instance of [`RealChatApi`] for a backend of a backends file,
+    /// using its `url`, `model`, key, timeouts, `path`, `query` and `auth_header`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL or the model is empty, the backend's `api_key_env`
+    /// variable is not set, a timeout is not a positive number, `auth_header` is not
+    /// a valid header name or an Ollama-only setting is set.
+    pub fn from_config(config: &BackendConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        check_settings(config)?;
+        if config.url.trim().is_empty() {
+            return Err(format!("Backend {} has no url", config.name).into());
+        }
+        if config.model.trim().is_empty() {
+            return Err(format!("Backend {} has no model", config.name).into());
+        }
+
+        let api_key = api_key_from_config(config)?;
+        if let Some(header) = &config.auth_header {
+            check_header_name(header).map_err(|_| {
+                format!(
+                    "Backend {} has an invalid auth_header: {}",
+                    config.name, header
+                )
+            })?;
+        }
+
+        let connect = secs_from_config(config.connect_timeout_secs, DEFAULT_CONNECT_TIMEOUT)?;
+        let read = secs_from_config(config.read_timeout_secs, DEFAULT_READ_TIMEOUT)?;
+
+        let path = config
+            .path
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CHAT_COMPLETIONS_PATH.to_string());
+        let query = config
+            .query
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+
+        Ok(Self::new(config.url.clone(), config.model.clone(), api_key)
+            .with_timeouts(connect, read)
+            .with_path(path)
+            .with_query(query)
+            .with_auth_header(config.auth_header.clone()))
+    }
+
+    /// Builds the Chat Completions request for the given conversation.
+    ///
+    /// `options.model` overrides the configured model; `stream` asks the API
+    /// to answer with Server-Sent Events.
+    fn build_request(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> RequestBuilder {
+        let model = options.model.as_deref().unwrap_or(&self.model);
+        let body = ChatCompletionRequest {
             model,
-            api_key,
-        })
+            messages,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            max_tokens: options.max_tokens,
+            stop: options.stop.as_deref(),
+            stream,
+        };
+
+        let path = self.path.replace("{model}", &encode_path_segment(model));
+        let url = format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+
+        let mut request: RequestBuilder = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&body);
+
+        if !self.query.is_empty() {
+            request = request.query(&self.query);
+        }
+
+        if let Some(key) = &self.api_key {
+            request = match &self.auth_header {
+                Some(header) => request.header(header.as_str(), key),
+                None => request.header("Authorization", format!("Bearer {}", key)),
+            };
+        }
+
+        request
+    }
+}
+
+/// Percent-encodes a value for use as one segment of a URL path.
+///
+/// Only unreserved characters (RFC 3986) are kept, so a model name containing `/`, `?`,
+/// `#` or spaces cannot change the endpoint it is sent to.
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
     }
+    encoded
+}
+
+/// Checks that a configured auth header is a valid HTTP header name.
+fn check_header_name(header: &str) -> Result<(), reqwest::header::InvalidHeaderName> {
+    HeaderName::from_bytes(header.as_bytes()).map(|_| ())
+}
+
+/// Parses a query string like `api-version=2024-10-21&foo=bar` into its pairs.
+///
+/// Returns `None` if a non-empty part has no `=` or an empty name.
+fn parse_query(query: &str) -> Option<Vec<(String, String)>> {
+    query
+        .trim()
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Some((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => None,
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -76,24 +342,31 @@ impl ChatApi for RealChatApi {
     ///
     /// # Arguments
     ///
-    /// * `prompt` — user input string to be sent to the assistant.
+    /// * `messages` — conversation turns, oldest first.
+    /// * `options` — generation options; `options.model` overrides the configured model.
     ///
     /// # Returns
     ///
-    /// * `Ok(String)` — assistant’s response.
-    /// * `Err(Box<dyn Error + Send + Sync>)` — if request fails or response format is invalid.
+    /// * `Ok(ChatCompletion)` — assistant’s response with the reported model, finish reason and usage.
+    /// * `Err(ChatApiError)` — if the request fails, the API answers with an error or the
+    ///   response format is invalid.
     ///
     /// # Example
     ///
     /// ```no_run
+    /// use tg_ai_companion::models::chat::{ChatMessage, ChatOptions};
     /// use tg_ai_companion::services::chat_api_impl::RealChatApi;
     /// use tg_ai_companion::services::chat_api::ChatApi;
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     ///     let api = RealChatApi::new_from_env()?;
-    ///     let reply = api.call_chat_api("Hello!").await?;
-    ///     println!("{}", reply);
+    ///     let messages = [
+    ///         ChatMessage::system("You are a helpful assistant."),
+    ///         ChatMessage::user("Hello!"),
+    ///     ];
+    ///     let completion = api.complete(&messages, &ChatOptions::default()).await?;
+    ///     println!("{}", completion.content);
     ///     Ok(())
     /// }
     /// ```
@@ -101,40 +374,94 @@ impl ChatApi for RealChatApi {
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The HTTP request fails (e.g., timeout, connection error).
-    /// - The response does not contain expected fields.
-    /// - `"choices[0].message.content"` is missing or not a string.
-    async fn call_chat_api(&self, prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
-        let body: Value = json!({
-            "model": self.model,
-            "messages": [
-                { "role": "user", "content": prompt }
-            ]
-        });
+    /// - The HTTP request fails ([`ChatApiError::Transport`], [`ChatApiError::Timeout`]).
+    /// - The API answers with an error, classified by status and `error.code`
+    ///   (e.g. `401` is [`ChatApiError::Auth`], `context_length_exceeded` is
+    ///   [`ChatApiError::ContextLengthExceeded`]).
+    /// - `"choices[0].message.content"` is missing or not a string ([`ChatApiError::MalformedResponse`]).
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        let response = send(self.build_request(messages, options, false)).await?;
+        let status = response.status().as_u16();
+        let json = read_json(response).await?;
+        // Some OpenAI-compatible servers answer errors with `200 OK`
+        if !json["error"].is_null() {
+            return Err(error_from_json(status, None, &json));
+        }
 
-        let url = format!(
-            "{}/v1/chat/completions",
-            self.base_url.trim_end_matches('/')
-        );
+        let choice = &json["choices"][0];
+        let finish_reason = choice["finish_reason"].as_str();
+        let content = match choice["message"]["content"].as_str() {
+            Some(content) => content.to_string(),
+            None if finish_reason == Some("content_filter") => {
+                return Err(ChatApiError::ContentFiltered(
+                    "The reply was filtered".to_string(),
+                ))
+            }
+            None => {
+                return Err(ChatApiError::MalformedResponse(
+                    "Missing content in the response!".to_string(),
+                ))
+            }
+        };
 
-        let mut request: RequestBuilder = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body);
+        Ok(ChatCompletion {
+            content,
+            model: json["model"].as_str().map(str::to_string),
+            finish_reason: finish_reason.map(str::to_string),
+            usage: serde_json::from_value::<ChatUsage>(json["usage"].clone()).ok(),
+        })
+    }
 
-        if let Some(key) = &self.api_key {
-            request = request.header("Authorization", format!("Bearer {}", key));
-        }
+    /// Sends a chat completion request with `"stream": true` and yields the reply's
+    /// fragments as the API sends them.
+    ///
+    /// The API answers with Server-Sent Events whose `data` is a JSON chunk carrying
+    /// the next fragment in `choices[0].delta.content`; the stream ends with `data: [DONE]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API answers with a non-success status.
+    /// The stream yields an error if a chunk is not valid JSON, carries an `error`, was stopped
+    /// by the content filter, or the connection breaks.
+    async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatStream, ChatApiError> {
+        let response = send(self.build_request(messages, options, true)).await?;
+        let status = response.status().as_u16();
 
-        let response: Response = request.send().await?;
-        let json: Value = response.json().await?;
+        let fragments = sse_data(response)
+            .take_while(|data| future::ready(!matches!(data, Ok(data) if data.trim() == "[DONE]")))
+            .filter_map(move |data| async move {
+                let data = match data {
+                    Ok(data) => data,
+                    Err(e) => return Some(Err(ChatApiError::Transport(e.to_string()))),
+                };
+                let chunk: Value = match serde_json::from_str(&data) {
+                    Ok(chunk) => chunk,
+                    Err(e) => return Some(Err(ChatApiError::MalformedResponse(e.to_string()))),
+                };
+                if !chunk["error"].is_null() {
+                    return Some(Err(error_from_json(status, None, &chunk)));
+                }
 
-        let content = json["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or("Missing content in the response!")?
-            .to_string();
+                let choice = &chunk["choices"][0];
+                if choice["finish_reason"].as_str() == Some("content_filter") {
+                    return Some(Err(ChatApiError::ContentFiltered(
+                        "The reply was filtered".to_string(),
+                    )));
+                }
+                choice["delta"]["content"]
+                    .as_str()
+                    .filter(|content| !content.is_empty())
+                    .map(|content| Ok(content.to_string()))
+            });
 
-        Ok(content)
+        Ok(Box::pin(fragments))
     }
 }
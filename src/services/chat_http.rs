@@ -0,0 +1,202 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, RequestBuilder, Response};
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+use crate::models::backend::BackendConfig;
+use crate::services::chat_api::ChatApiError;
+
+/// Default time to wait for a connection to the API.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default time to wait for the next chunk of a response; long enough for slow local models.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Builds the HTTP client with the given timeouts.
+pub fn build_client(connect: Duration, read: Duration) -> Client {
+    Client::builder()
+        .connect_timeout(connect)
+        .read_timeout(read)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Sends a request and returns the response, or the error the API answered with.
+pub async fn send(request: RequestBuilder) -> Result<Response, ChatApiError> {
+    let response = request.send().await.map_err(transport_error)?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = retry_after(response.headers());
+    let body = response.text().await.unwrap_or_default();
+    Err(error_from_response(status.as_u16(), retry_after, &body))
+}
+
+/// Reads a response body as JSON.
+///
+/// A body cut short by a timeout or a dropped connection is a [`ChatApiError::Timeout`] or
+/// [`ChatApiError::Transport`], so it is retried like a failed request; only a body that is
+/// not JSON is a [`ChatApiError::MalformedResponse`].
+pub async fn read_json(response: Response) -> Result<Value, ChatApiError> {
+    let body = response.bytes().await.map_err(transport_error)?;
+    serde_json::from_slice(&body).map_err(|e| ChatApiError::MalformedResponse(e.to_string()))
+}
+
+/// Returns the API key of a backend: its `api_key`, or the value of its `api_key_env` variable.
+///
+/// # Errors
+///
+/// Returns an error if `api_key_env` names a variable that is not set.
+pub fn api_key_from_config(
+    config: &BackendConfig,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    let api_key = match (&config.api_key, &config.api_key_env) {
+        (Some(key), _) => Some(key.clone()),
+        (None, Some(name)) => Some(env::var(name).map_err(|_| {
+            format!(
+                "Environment variable {} of backend {} is not set",
+                name, config.name
+            )
+        })?),
+        (None, None) => None,
+    };
+
+    Ok(api_key.filter(|key| !key.trim().is_empty()))
+}
+
+/// Checks that a backend sets no settings its kind ignores, so a config that looks like
+/// it changes the endpoint or the key does not silently do nothing.
+///
+/// # Errors
+///
+/// Returns an error naming the ignored settings.
+pub fn check_settings(config: &BackendConfig) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let ignored = config.ignored_settings();
+    if ignored.is_empty() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Backend {} does not support {}",
+        config.name,
+        ignored.join(", ")
+    )
+    .into())
+}
+
+/// Reads a number of seconds from an environment variable, falling back to `default`
+/// when it is unset or empty.
+pub fn secs_from_env(
+    name: &str,
+    default: Duration,
+) -> Result<Duration, Box<dyn Error + Send + Sync>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => match value.trim().parse::<f64>() {
+            Ok(secs) if secs > 0.0 => Ok(Duration::from_secs_f64(secs)),
+            _ => Err(format!("Environment variable {} must be a positive number", name).into()),
+        },
+        _ => Ok(default),
+    }
+}
+
+/// Converts an optional number of seconds of a backend's config into a timeout.
+pub fn secs_from_config(
+    secs: Option<f64>,
+    default: Duration,
+) -> Result<Duration, Box<dyn Error + Send + Sync>> {
+    match secs {
+        Some(secs) if secs > 0.0 => Ok(Duration::from_secs_f64(secs)),
+        Some(_) => Err("Backend timeouts must be positive numbers".into()),
+        None => Ok(default),
+    }
+}
+
+/// Converts an error of the HTTP client into a [`ChatApiError`].
+pub fn transport_error(error: reqwest::Error) -> ChatApiError {
+    if error.is_timeout() {
+        ChatApiError::Timeout
+    } else {
+        ChatApiError::Transport(error.to_string())
+    }
+}
+
+/// Reads the `Retry-After` header, given in seconds.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Classifies an error answer of an OpenAI-compatible API.
+///
+/// The body is usually `{"error": {"message": "...", "type": "...", "code": "..."}}`, but some
+/// servers send `{"error": "..."}` or plain text.
+pub fn error_from_response(status: u16, retry_after: Option<Duration>, body: &str) -> ChatApiError {
+    match serde_json::from_str::<Value>(body) {
+        Ok(json) if !json["error"].is_null() => error_from_json(status, retry_after, &json),
+        _ => classify_error(status, retry_after, "", body.trim().to_string()),
+    }
+}
+
+/// Classifies a JSON body carrying an `error`.
+pub fn error_from_json(status: u16, retry_after: Option<Duration>, json: &Value) -> ChatApiError {
+    let error = &json["error"];
+    let message = error["message"]
+        .as_str()
+        .or(error.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let code = error["code"]
+        .as_str()
+        .or(error["type"].as_str())
+        .unwrap_or_default();
+
+    classify_error(status, retry_after, code, message)
+}
+
+/// Picks the [`ChatApiError`] variant for an error's status, code and message.
+pub fn classify_error(
+    status: u16,
+    retry_after: Option<Duration>,
+    code: &str,
+    message: String,
+) -> ChatApiError {
+    let lowercase = message.to_lowercase();
+    if code == "context_length_exceeded"
+        || lowercase.contains("context length")
+        || lowercase.contains("context window")
+        || lowercase.contains("prompt is too long")
+    {
+        return ChatApiError::ContextLengthExceeded(message);
+    }
+    if code == "content_filter" || code == "content_policy_violation" {
+        return ChatApiError::ContentFiltered(message);
+    }
+
+    // Error types of the Anthropic API, which may also arrive in a stream answered with `200 OK`
+    let status = match code {
+        "authentication_error" | "permission_error" => 401,
+        "rate_limit_error" => 429,
+        "overloaded_error" => 529,
+        "api_error" if status < 500 => 500,
+        _ => status,
+    };
+
+    match status {
+        401 | 403 => ChatApiError::Auth(message),
+        429 => ChatApiError::RateLimited {
+            retry_after,
+            message,
+        },
+        _ => ChatApiError::Backend { status, message },
+    }
+}
@@ -0,0 +1,114 @@
+use futures_util::stream::{self, Stream, StreamExt};
+use reqwest::Response;
+use std::collections::VecDeque;
+use std::error::Error;
+
+/// Splits the body of a streaming HTTP response into lines.
+///
+/// Lines are yielded without their trailing `\n` / `\r\n`; a last line without
+/// a line break is yielded when the body ends.
+///
+/// # Errors
+///
+/// Yields an error (and stops) if reading the body fails or a line is not valid UTF-8.
+pub fn response_lines(
+    response: Response,
+) -> impl Stream<Item = Result<String, Box<dyn Error + Send + Sync>>> + Send {
+    struct State<S> {
+        body: S,
+        buffer: Vec<u8>,
+        lines: VecDeque<String>,
+        finished: bool,
+    }
+
+    let state = State {
+        body: Box::pin(response.bytes_stream()),
+        buffer: Vec::new(),
+        lines: VecDeque::new(),
+        finished: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(line) = state.lines.pop_front() {
+                return Some((Ok(line), state));
+            }
+            if state.finished {
+                return None;
+            }
+
+            match state.body.next().await {
+                Some(Ok(chunk)) => {
+                    state.buffer.extend_from_slice(&chunk);
+                    while let Some(end) = state.buffer.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = state.buffer.drain(..=end).collect();
+                        match String::from_utf8(line) {
+                            Ok(line) => state
+                                .lines
+                                .push_back(line.trim_end_matches(['\n', '\r']).to_string()),
+                            Err(e) => {
+                                state.finished = true;
+                                return Some((Err(e.into()), state));
+                            }
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    state.finished = true;
+                    return Some((Err(e.into()), state));
+                }
+                None => {
+                    state.finished = true;
+                    if !state.buffer.is_empty() {
+                        let rest = std::mem::take(&mut state.buffer);
+                        let line = String::from_utf8(rest).map_err(|e| e.into());
+                        return Some((line, state));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Extracts the `data` payloads of a Server-Sent Events response.
+///
+/// Multi-line `data` fields of one event are joined with `\n`, as the SSE specification says.
+/// Comments, `event`, `id` and `retry` fields are ignored.
+///
+/// # Errors
+///
+/// Yields an error (and stops) if reading the body fails.
+pub fn sse_data(
+    response: Response,
+) -> impl Stream<Item = Result<String, Box<dyn Error + Send + Sync>>> + Send {
+    let lines = Box::pin(response_lines(response));
+
+    stream::unfold(Some(lines), |lines| async move {
+        let mut lines = lines?;
+        let mut data: Option<String> = None;
+
+        loop {
+            match lines.next().await {
+                Some(Ok(line)) if line.is_empty() => {
+                    if let Some(data) = data.take() {
+                        return Some((Ok(data), Some(lines)));
+                    }
+                }
+                Some(Ok(line)) => {
+                    if let Some(value) = line.strip_prefix("data:") {
+                        let value = value.strip_prefix(' ').unwrap_or(value);
+                        match data.as_mut() {
+                            Some(data) => {
+                                data.push('\n');
+                                data.push_str(value);
+                            }
+                            None => data = Some(value.to_string()),
+                        }
+                    }
+                }
+                Some(Err(e)) => return Some((Err(e), None)),
+                None => return data.map(|data| (Ok(data), None)),
+            }
+        }
+    })
+}
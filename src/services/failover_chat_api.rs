@@ -0,0 +1,434 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::models::backend::{BackendConfig, BackendKind, FailoverStrategy};
+use crate::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use crate::models::health::{BackendHealth, CircuitState};
+use crate::services::anthropic_chat_api::AnthropicChatApi;
+use crate::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use crate::services::chat_api_impl::RealChatApi;
+use crate::services::ollama_chat_api::OllamaChatApi;
+use crate::services::resilient_chat_api::{ResilienceConfig, ResilientChatApi};
+
+/// Name of the backend configured with environment variables instead of a backends file.
+pub const DEFAULT_BACKEND_NAME: &str = "default";
+
+/// Default time between two probes of the unhealthy backends.
+pub const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The backends file format.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "strategy": "priority",
+///   "backends": [
+///     { "name": "localai", "url": "http://localai:8080", "model": "mistral" },
+///     { "name": "openai", "url": "https://api.openai.com", "model": "gpt-4o-mini",
+///       "api_key_env": "OPENAI_API_KEY", "priority": 1 }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+struct BackendsFile {
+    #[serde(default)]
+    strategy: FailoverStrategy,
+    backends: Vec<BackendConfig>,
+}
+
+/// A backend of a [`FailoverChatApi`].
+///
+/// # Fields
+///
+/// * `api` – The backend, with its own retries and circuit breaker.
+/// * `priority` – Backends with a lower priority are tried first.
+/// * `weight` – Share of the requests the backend gets with [`FailoverStrategy::Weighted`].
+pub struct FailoverBackend {
+    pub api: ResilientChatApi,
+    pub priority: u32,
+    pub weight: u32,
+}
+
+/// `FailoverChatApi` is a [`ChatApi`] spreading requests over several backends.
+///
+/// Each request tries the backends one after the other until one answers: by priority, or with
+/// [`FailoverStrategy::Weighted`] starting with a healthy backend picked by weight (smooth
+/// weighted round-robin) and continuing by priority. A backend whose circuit breaker is open
+/// fails right away, so requests skip it without waiting.
+///
+/// Errors caused by the request itself — an exceeded context length or a content filter — are
+/// returned without trying the other backends. If every backend fails, the last error is
+/// returned. Streams fail over only until they started. Requests whose options name a backend
+/// (e.g. from a persona) are sent to that backend only, without failover. A model override
+/// (`ChatOptions::model`) only applies to the first backend tried; the backends failed over to
+/// use their own model.
+///
+/// Unhealthy backends are recovered by [`FailoverChatApi::run_probes`], which probes them
+/// periodically even when no requests arrive.
+pub struct FailoverChatApi {
+    backends: Vec<FailoverBackend>,
+    strategy: FailoverStrategy,
+    probe_interval: Duration,
+    current_weights: Mutex<Vec<i64>>,
+}
+
+impl FailoverChatApi {
+    /// Creates a new composite API.
+    ///
+    /// # Arguments
+    ///
+    /// * `backends` - The backends to try.
+    /// * `strategy` - The order in which a request tries the backends.
+    pub fn new(backends: Vec<FailoverBackend>, strategy: FailoverStrategy) -> Self {
+        Self {
+            current_weights: Mutex::new(vec![0; backends.len()]),
+            backends,
+            strategy,
+            probe_interval: DEFAULT_PROBE_INTERVAL,
+        }
+    }
+
+    /// Sets the time between two probes of the unhealthy backends.
+    pub fn with_probe_interval(mut self, probe_interval: Duration) -> Self {
+        self.probe_interval = probe_interval;
+        self
+    }
+
+    /// Creates a composite API from backend configurations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the list is empty, contains duplicate or empty names,
+    /// or a backend is invalid (see [`chat_api_from_config`]).
+    pub fn from_config(
+        configs: Vec<BackendConfig>,
+        strategy: FailoverStrategy,
+        resilience: ResilienceConfig,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if configs.is_empty() {
+            return Err("At least one chat backend must be configured".into());
+        }
+
+        let mut backends = Vec::with_capacity(configs.len());
+        for (index, config) in configs.iter().enumerate() {
+            if config.name.trim().is_empty() {
+                return Err("Backend names cannot be empty".into());
+            }
+            if configs[..index].iter().any(|c| c.name == config.name) {
+                return Err(format!("Duplicate backend name: {}", config.name).into());
+            }
+
+            backends.push(FailoverBackend {
+                api: ResilientChatApi::new(
+                    config.name.clone(),
+                    chat_api_from_config(config)?,
+                    resilience,
+                ),
+                priority: config.priority,
+                weight: config.weight,
+            });
+        }
+
+        Ok(Self::new(backends, strategy))
+    }
+
+    /// Parses a composite API from the JSON backends file format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is invalid or describes invalid backends (see [`FailoverChatApi::from_config`]).
+    pub fn from_json(
+        json: &str,
+        resilience: ResilienceConfig,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let file: BackendsFile = serde_json::from_str(json)?;
+        Self::from_config(file.backends, file.strategy, resilience)
+    }
+
+    /// Creates a composite API using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `CHAT_BACKENDS_FILE`: (optional) path of a JSON backends file; if unset or empty,
+    ///   a single backend named [`DEFAULT_BACKEND_NAME`] is used (see [`chat_api_from_env`])
+    /// - `CHAT_API_PROBE_INTERVAL_SECS`: (optional) seconds between two probes of the unhealthy
+    ///   backends, defaults to [`DEFAULT_PROBE_INTERVAL`]
+    /// - The retry and circuit breaker settings of [`ResilienceConfig::new_from_env`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is invalid, or a variable is invalid.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let resilience = ResilienceConfig::new_from_env()?;
+
+        let api = match env::var("CHAT_BACKENDS_FILE") {
+            Ok(path) if !path.trim().is_empty() => {
+                let json = fs::read_to_string(&path)
+                    .map_err(|e| format!("Cannot read backends file {}: {}", path, e))?;
+                Self::from_json(&json, resilience)?
+            }
+            _ => Self::new(
+                vec![FailoverBackend {
+                    api: ResilientChatApi::new(
+                        DEFAULT_BACKEND_NAME,
+                        chat_api_from_env()?,
+                        resilience,
+                    ),
+                    priority: 0,
+                    weight: 1,
+                }],
+                FailoverStrategy::Priority,
+            ),
+        };
+
+        let probe_interval = match env::var("CHAT_API_PROBE_INTERVAL_SECS") {
+            Ok(value) if !value.trim().is_empty() => match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => return Err(
+                    "Environment variable CHAT_API_PROBE_INTERVAL_SECS must be a positive number"
+                        .into(),
+                ),
+            },
+            _ => DEFAULT_PROBE_INTERVAL,
+        };
+
+        Ok(api.with_probe_interval(probe_interval))
+    }
+
+    /// Probes every backend whose circuit breaker is open and due for a trial.
+    pub async fn probe_unhealthy(&self) {
+        for backend in &self.backends {
+            if backend.api.state() != CircuitState::Closed && backend.api.recover().await {
+                println!("💚 Chat backend {} passed its probe", backend.api.name());
+            }
+        }
+    }
+
+    /// Probes the unhealthy backends every probe interval. This method never returns.
+    pub async fn run_probes(&self) {
+        let mut interval = tokio::time::interval(self.probe_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            self.probe_unhealthy().await;
+        }
+    }
+
+    /// Returns the indexes of the backends in the order a request tries them.
+    ///
+    /// A request naming a backend is sent to that backend only.
+    fn order(&self, backend: Option<&str>) -> Vec<usize> {
+        if let Some(name) = backend {
+            match self.backends.iter().position(|b| b.api.name() == name) {
+                Some(index) => return vec![index],
+                None => eprintln!("Unknown chat backend {}, using all backends", name),
+            }
+        }
+
+        let mut order: Vec<usize> = (0..self.backends.len()).collect();
+        order.sort_by_key(|&index| self.backends[index].priority);
+
+        let first = match self.strategy {
+            FailoverStrategy::Priority => None,
+            FailoverStrategy::Weighted => self.pick_weighted(),
+        };
+        if let Some(first) = first {
+            order.retain(|&index| index != first);
+            order.insert(0, first);
+        }
+
+        order
+    }
+
+    /// Returns the index of the backend a model override is meant for: the backend the
+    /// request named, or else the one with the lowest priority.
+    ///
+    /// The weighted strategy may start with any backend, but the persona's or the chat's
+    /// model usually only exists on the primary one.
+    fn model_backend(&self, backend: Option<&str>) -> Option<usize> {
+        let named =
+            backend.and_then(|name| self.backends.iter().position(|b| b.api.name() == name));
+
+        named
+            .or_else(|| (0..self.backends.len()).min_by_key(|&index| self.backends[index].priority))
+    }
+
+    /// Picks a healthy backend with smooth weighted round-robin: every pick adds each
+    /// candidate's weight to its current weight, and the highest one is picked and
+    /// lowered by the total weight.
+    fn pick_weighted(&self) -> Option<usize> {
+        let candidates: Vec<usize> = (0..self.backends.len())
+            .filter(|&index| {
+                let backend = &self.backends[index];
+                backend.weight > 0 && backend.api.state() != CircuitState::Open
+            })
+            .collect();
+        let total: i64 = candidates
+            .iter()
+            .map(|&index| i64::from(self.backends[index].weight))
+            .sum();
+
+        let mut current = self.current_weights.lock().unwrap();
+        let mut best: Option<usize> = None;
+        for &index in &candidates {
+            current[index] += i64::from(self.backends[index].weight);
+            if best.is_none_or(|best| current[index] > current[best]) {
+                best = Some(index);
+            }
+        }
+        if let Some(best) = best {
+            current[best] -= total;
+        }
+
+        best
+    }
+
+    /// Runs a request against the backends in order until one answers.
+    ///
+    /// `request` is told whether the backend may get the model override,
+    /// see [`FailoverChatApi::model_backend`].
+    async fn call<'a, T, F, Fut>(
+        &'a self,
+        backend: Option<&str>,
+        request: F,
+    ) -> Result<T, ChatApiError>
+    where
+        F: Fn(&'a ResilientChatApi, bool) -> Fut,
+        Fut: Future<Output = Result<T, ChatApiError>>,
+    {
+        let mut error: Option<ChatApiError> = None;
+        let model_backend = self.model_backend(backend);
+
+        for index in self.order(backend) {
+            let backend = &self.backends[index].api;
+            let e = match request(backend, Some(index) == model_backend).await {
+                Err(e) if fails_over(&e) => e,
+                result => return result,
+            };
+
+            if !matches!(e, ChatApiError::Unavailable(_)) {
+                eprintln!(
+                    "Chat backend {} failed, trying the next one: {}",
+                    backend.name(),
+                    e
+                );
+            }
+            // An actual failure tells more than a skipped backend
+            error = match (error, e) {
+                (Some(previous), ChatApiError::Unavailable(_)) => Some(previous),
+                (_, e) => Some(e),
+            };
+        }
+
+        Err(error.unwrap_or_else(|| {
+            ChatApiError::Unavailable("no chat backend is configured".to_string())
+        }))
+    }
+}
+
+/// Creates the API of a backend of a backends file, by the backend's `kind`.
+///
+/// # Errors
+///
+/// Returns an error if the backend is invalid (see [`RealChatApi::from_config`],
+/// [`OllamaChatApi::from_config`] and [`AnthropicChatApi::from_config`]).
+pub fn chat_api_from_config(
+    config: &BackendConfig,
+) -> Result<Arc<dyn ChatApi>, Box<dyn Error + Send + Sync>> {
+    Ok(match config.kind {
+        BackendKind::OpenAi => Arc::new(RealChatApi::from_config(config)?),
+        BackendKind::Ollama => Arc::new(OllamaChatApi::from_config(config)?),
+        BackendKind::Anthropic => Arc::new(AnthropicChatApi::from_config(config)?),
+    })
+}
+
+/// Creates the API of the backend configured with environment variables.
+///
+/// # Environment Variables
+///
+/// - `CHAT_BACKEND_KIND`: (optional) `openai` (the default) reads the backend with
+///   [`RealChatApi::new_from_env`], `ollama` with [`OllamaChatApi::new_from_env`] and
+///   `anthropic` with [`AnthropicChatApi::new_from_env`]
+///
+/// # Errors
+///
+/// Returns an error if the kind is unknown or the backend's variables are invalid.
+pub fn chat_api_from_env() -> Result<Arc<dyn ChatApi>, Box<dyn Error + Send + Sync>> {
+    let kind = env::var("CHAT_BACKEND_KIND").unwrap_or_default();
+
+    Ok(match kind.trim() {
+        "" | "openai" => Arc::new(RealChatApi::new_from_env()?),
+        "ollama" => Arc::new(OllamaChatApi::new_from_env()?),
+        "anthropic" => Arc::new(AnthropicChatApi::new_from_env()?),
+        other => return Err(format!("Unknown CHAT_BACKEND_KIND: {}", other).into()),
+    })
+}
+
+/// Returns whether another backend may answer a request that failed with this error.
+fn fails_over(error: &ChatApiError) -> bool {
+    !matches!(
+        error,
+        ChatApiError::ContextLengthExceeded(_) | ChatApiError::ContentFiltered(_)
+    )
+}
+
+/// Returns the options sent to the other backends: the model override is meant for one
+/// backend and would likely not exist on the others.
+fn fallback_options(options: &ChatOptions) -> ChatOptions {
+    ChatOptions {
+        model: None,
+        ..options.clone()
+    }
+}
+
+#[async_trait]
+impl ChatApi for FailoverChatApi {
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        let fallback_options = fallback_options(options);
+        self.call(options.backend.as_deref(), |backend, uses_model| {
+            let options = if uses_model {
+                options
+            } else {
+                &fallback_options
+            };
+            backend.complete(messages, options)
+        })
+        .await
+    }
+
+    async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatStream, ChatApiError> {
+        let fallback_options = fallback_options(options);
+        self.call(options.backend.as_deref(), |backend, uses_model| {
+            let options = if uses_model {
+                options
+            } else {
+                &fallback_options
+            };
+            backend.complete_stream(messages, options)
+        })
+        .await
+    }
+
+    fn health(&self) -> Vec<BackendHealth> {
+        self.backends
+            .iter()
+            .flat_map(|backend| backend.api.health())
+            .collect()
+    }
+}
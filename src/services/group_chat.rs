@@ -0,0 +1,121 @@
+use crate::handlers::commands::parse_command;
+use crate::models::telegram::{MessageEntity, TelegramMessage};
+
+/// Returns the prompt the bot should answer for a message, or `None` if it should stay silent.
+///
+/// In private chats every text message is answered. In groups the bot only answers messages
+/// addressed to it: bot commands (the command router ignores those meant for other bots),
+/// messages mentioning `@bot_username`, and replies to one of the bot's own messages. Mentions of the bot are removed from the prompt.
+///
+/// # Arguments
+///
+/// * `message` - The received message.
+/// * `bot_username` - The bot's username without the leading `@`, as returned by `getMe`;
+///   without it, only commands are answered in groups.
+///
+/// # Returns
+///
+/// The trimmed prompt, or `None` if the message has no text, is not addressed to the bot,
+/// or is nothing but a mention.
+pub fn addressed_prompt(message: &TelegramMessage, bot_username: Option<&str>) -> Option<String> {
+    let text = message.text.as_deref()?;
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    if !message.chat.is_group() || parse_command(text).is_some() {
+        return Some(text.trim().to_string());
+    }
+
+    let bot_username = bot_username?;
+    let mentions: Vec<&MessageEntity> = message
+        .entities
+        .iter()
+        .filter(|entity| is_mention_of(text, entity, bot_username))
+        .collect();
+
+    if mentions.is_empty() && !is_reply_to(message, bot_username) {
+        return None;
+    }
+
+    let prompt = strip_entities(text, &mentions);
+    let prompt = prompt.trim().trim_start_matches([',', ':']).trim();
+    (!prompt.is_empty()).then(|| prompt.to_string())
+}
+
+/// Returns whether an entity is a `@mention` of the bot.
+fn is_mention_of(text: &str, entity: &MessageEntity, bot_username: &str) -> bool {
+    entity.entity_type == "mention"
+        && entity_text(text, entity).is_some_and(|mention| {
+            mention
+                .strip_prefix('@')
+                .is_some_and(|username| username.eq_ignore_ascii_case(bot_username))
+        })
+}
+
+/// Returns whether a message replies to one of the bot's messages.
+fn is_reply_to(message: &TelegramMessage, bot_username: &str) -> bool {
+    message
+        .reply_to_message
+        .as_ref()
+        .and_then(|replied| replied.from.as_ref())
+        .and_then(|author| author.username.as_deref())
+        .is_some_and(|username| username.eq_ignore_ascii_case(bot_username))
+}
+
+/// Removes the given entities from a text, joining the remaining pieces with a single space
+/// (or none before punctuation, as in `Hey @bot, ...`).
+fn strip_entities(text: &str, entities: &[&MessageEntity]) -> String {
+    let mut ranges: Vec<(usize, usize)> = entities
+        .iter()
+        .filter_map(|entity| byte_range(text, entity))
+        .collect();
+    ranges.sort_unstable();
+
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for (from, to) in ranges {
+        if from >= start {
+            pieces.push(text[start..from].trim());
+            start = to;
+        }
+    }
+    pieces.push(text[start..].trim());
+
+    let mut stripped = String::with_capacity(text.len());
+    for piece in pieces.into_iter().filter(|piece| !piece.is_empty()) {
+        if !stripped.is_empty() && !piece.starts_with([',', '.', ':', ';', '!', '?']) {
+            stripped.push(' ');
+        }
+        stripped.push_str(piece);
+    }
+    stripped
+}
+
+/// Returns the text covered by an entity.
+fn entity_text<'a>(text: &'a str, entity: &MessageEntity) -> Option<&'a str> {
+    byte_range(text, entity).map(|(from, to)| &text[from..to])
+}
+
+/// Converts an entity's UTF-16 `offset` and `length` into a byte range of the text.
+fn byte_range(text: &str, entity: &MessageEntity) -> Option<(usize, usize)> {
+    let from = byte_index(text, entity.offset)?;
+    let to = byte_index(text, entity.offset + entity.length)?;
+    Some((from, to))
+}
+
+/// Returns the byte index of a position counted in UTF-16 code units, if it falls on
+/// a character boundary of the text.
+fn byte_index(text: &str, utf16_offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if units == utf16_offset {
+            return Some(index);
+        }
+        if units > utf16_offset {
+            return None;
+        }
+        units += c.len_utf16();
+    }
+    (units == utf16_offset).then_some(text.len())
+}
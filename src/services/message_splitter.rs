@@ -0,0 +1,117 @@
+/// Maximum length of a Telegram message text, in characters.
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
+
+/// Line closing a code block that had to be split.
+const CLOSING_FENCE: &str = "\n```";
+
+/// Splits a text into parts of at most `limit` characters each.
+///
+/// Parts end at the last paragraph break that fits, otherwise at a line break, the end of
+/// a sentence, a space, or — for a single overlong word — in the middle. A code block cut in
+/// two is closed at the end of one part and reopened (with its language) at the start of the
+/// next, so every part renders on its own.
+///
+/// # Arguments
+///
+/// * `text` - The text to split.
+/// * `limit` - Maximum characters per part, e.g. [`TELEGRAM_MESSAGE_LIMIT`]; must leave room
+///   for a reopened code fence, so values below a few dozen characters are not useful.
+///
+/// # Returns
+///
+/// The parts in order, trimmed; empty if the text is blank.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim().to_string();
+
+    while rest.chars().count() > limit {
+        let mut cut = find_cut(&rest[..byte_index(&rest, limit)]);
+        if open_fence(&rest[..cut]).is_some() {
+            // Leave room for the fence closing the code block
+            let budget = limit.saturating_sub(CLOSING_FENCE.len()).max(1);
+            cut = find_cut(&rest[..byte_index(&rest, budget)]);
+
+            // A cut right after the fence line would reopen the block with nothing consumed,
+            // so a line too long for a part is cut in the middle
+            let stuck = open_fence(&rest[..cut]).is_some_and(|(_, fence_end)| cut <= fence_end);
+            if stuck {
+                cut = byte_index(&rest, budget);
+            }
+        }
+        let (head, tail) = rest.split_at(cut);
+
+        let mut part = head.trim_end().to_string();
+        let next = match open_fence(head) {
+            Some((fence, _)) => {
+                part.push_str(CLOSING_FENCE);
+                format!("{}\n{}", fence, tail.trim_start_matches(['\n', '\r']))
+            }
+            None => tail.trim_start().to_string(),
+        };
+
+        parts.push(part);
+        rest = next;
+    }
+
+    if !rest.is_empty() {
+        parts.push(rest);
+    }
+    parts
+}
+
+/// Returns the byte index of the `chars`-th character, or the length if the text is shorter.
+fn byte_index(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(index, _)| index)
+}
+
+/// Returns where to cut a window that is too long: the byte index the part ends at.
+///
+/// Paragraph, line and sentence boundaries are only used in the second half of the window,
+/// so parts do not get needlessly short.
+fn find_cut(window: &str) -> usize {
+    let min = window.len() / 2;
+
+    for separator in ["\n\n", "\n"] {
+        if let Some(index) = window.rfind(separator).filter(|index| *index >= min) {
+            return index;
+        }
+    }
+
+    let sentence_end = [". ", "! ", "? ", "; "]
+        .iter()
+        .filter_map(|separator| window.rfind(separator))
+        .max()
+        .filter(|index| *index >= min);
+    if let Some(index) = sentence_end {
+        return index + 1;
+    }
+
+    match window.rfind(char::is_whitespace) {
+        Some(index) if index > 0 => index,
+        _ => window.len(),
+    }
+}
+
+/// Returns the opening fence line (e.g. ` ```rust `) if `text` ends inside a code block,
+/// together with the byte index the fence line ends at.
+fn open_fence(text: &str) -> Option<(String, usize)> {
+    let mut fence = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let end = offset + line.trim_end().len();
+        offset += line.len();
+
+        let line = line.trim();
+        if line.starts_with("```") {
+            fence = match fence {
+                Some(_) => None,
+                None => Some((line.to_string(), end)),
+            };
+        }
+    }
+
+    fence
+}
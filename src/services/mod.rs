@@ -1,4 +1,18 @@
+pub mod anthropic_chat_api;
 pub mod chat_api;
 pub mod chat_api_impl;
+pub mod chat_http;
+pub mod event_stream;
+pub mod failover_chat_api;
+pub mod group_chat;
+pub mod message_splitter;
+pub mod ollama_chat_api;
+pub mod persona_registry;
+pub mod reply_streamer;
+pub mod resilient_chat_api;
 pub mod telegram_api;
 pub mod telegram_api_impl;
+pub mod telegram_format;
+pub mod telegram_polling;
+pub mod telegram_rate_limiter;
+pub mod update_processor;
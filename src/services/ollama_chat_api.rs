@@ -0,0 +1,309 @@
+use async_trait::async_trait;
+use futures_util::stream::StreamExt;
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+use crate::models::backend::BackendConfig;
+use crate::models::chat::{ChatCompletion, ChatMessage, ChatOptions, ChatUsage};
+use crate::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use crate::services::chat_http::{
+    build_client, check_settings, error_from_json, read_json, secs_from_config, secs_from_env,
+    send, DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT,
+};
+use crate::services::event_stream::response_lines;
+
+/// `OllamaChatApi` is an implementation of the [`ChatApi`] trait for Ollama's native
+/// `/api/chat` endpoint.
+///
+/// Unlike Ollama's OpenAI-compatible endpoint, the native one lets the model stay loaded
+/// for a configurable time (`keep_alive`) and sets the context window size (`num_ctx`).
+/// Replies are streamed as newline-delimited JSON.
+///
+/// Environment variables used:
+/// - `OLLAMA_URL` — base URL of the Ollama server (e.g. `http://localhost:11434`)
+/// - `OLLAMA_MODEL` — model name (e.g. `llama3.2`)
+/// - `OLLAMA_KEEP_ALIVE` — optional time the model stays loaded (e.g. `10m`, or `-1` for ever)
+/// - `OLLAMA_NUM_CTX` — optional context window size in tokens
+/// - `OLLAMA_CONNECT_TIMEOUT_SECS` / `OLLAMA_READ_TIMEOUT_SECS` — optional timeouts
+pub struct OllamaChatApi {
+    client: Client,
+    base_url: String,
+    model: String,
+    keep_alive: Option<String>,
+    num_ctx: Option<u32>,
+}
+
+/// Request body of Ollama's `/api/chat`.
+#[derive(Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<Value>,
+    #[serde(skip_serializing_if = "OllamaOptions::is_empty")]
+    options: OllamaOptions<'a>,
+}
+
+/// Model parameters of an Ollama request; unset ones are omitted.
+#[derive(Serialize, Default)]
+struct OllamaOptions<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+}
+
+impl OllamaOptions<'_> {
+    fn is_empty(&self) -> bool {
+        self.temperature.is_none()
+            && self.top_p.is_none()
+            && self.num_predict.is_none()
+            && self.stop.is_none()
+            && self.num_ctx.is_none()
+    }
+}
+
+impl OllamaChatApi {
+    /// Creates a new instance of [`OllamaChatApi`] with the provided settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the Ollama server (e.g. `http://localhost:11434`).
+    /// * `model` - The model name to use unless a request overrides it.
+    pub fn new(base_url: String, model: String) -> Self {
+        Self {
+            client: build_client(DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT),
+            base_url,
+            model,
+            keep_alive: None,
+            num_ctx: None,
+        }
+    }
+
+    /// Sets how long Ollama keeps the model loaded after a request.
+    ///
+    /// Either a duration like `10m` or a number of seconds; a negative number keeps it loaded
+    /// for ever and `0` unloads it right away.
+    pub fn with_keep_alive(mut self, keep_alive: Option<String>) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Sets the size of the context window in tokens.
+    pub fn with_num_ctx(mut self, num_ctx: Option<u32>) -> Self {
+        self.num_ctx = num_ctx;
+        self
+    }
+
+    /// Replaces the client's timeouts (see [`RealChatApi::with_timeouts`](crate::services::chat_api_impl::RealChatApi::with_timeouts)).
+    pub fn with_timeouts(mut self, connect: Duration, read: Duration) -> Self {
+        self.client = build_client(connect, read);
+        self
+    }
+
+    /// Creates a new instance of [`OllamaChatApi`] from environment variables.
+    ///
+    /// Requires `OLLAMA_URL` and `OLLAMA_MODEL` to be set and non-empty; `OLLAMA_KEEP_ALIVE`,
+    /// `OLLAMA_NUM_CTX`, `OLLAMA_CONNECT_TIMEOUT_SECS` and `OLLAMA_READ_TIMEOUT_SECS` are optional.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required variable is missing or empty, or a number is invalid.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let base_url = required_env("OLLAMA_URL")?;
+        let model = required_env("OLLAMA_MODEL")?;
+
+        let keep_alive = env::var("OLLAMA_KEEP_ALIVE")
+            .ok()
+            .filter(|value| !value.trim().is_empty());
+        let num_ctx = match env::var("OLLAMA_NUM_CTX") {
+            Ok(value) if !value.trim().is_empty() => Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Environment variable OLLAMA_NUM_CTX must be a number")?,
+            ),
+            _ => None,
+        };
+
+        let connect = secs_from_env("OLLAMA_CONNECT_TIMEOUT_SECS", DEFAULT_CONNECT_TIMEOUT)?;
+        let read = secs_from_env("OLLAMA_READ_TIMEOUT_SECS", DEFAULT_READ_TIMEOUT)?;
+
+        Ok(Self::new(base_url, model)
+            .with_keep_alive(keep_alive)
+            .with_num_ctx(num_ctx)
+            .with_timeouts(connect, read))
+    }
+
+    /// Creates a new instance of [`OllamaChatApi`] for a backend of a backends file,
+    /// using its `url`, `model`, `keep_alive`, `num_ctx` and timeouts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL or the model is empty, a timeout is not a positive number,
+    /// or an API key or an OpenAI-only setting is set, as Ollama uses neither.
+    pub fn from_config(config: &BackendConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        check_settings(config)?;
+        if config.url.trim().is_empty() {
+            return Err(format!("Backend {} has no url", config.name).into());
+        }
+        if config.model.trim().is_empty() {
+            return Err(format!("Backend {} has no model", config.name).into());
+        }
+
+        let connect = secs_from_config(config.connect_timeout_secs, DEFAULT_CONNECT_TIMEOUT)?;
+        let read = secs_from_config(config.read_timeout_secs, DEFAULT_READ_TIMEOUT)?;
+
+        Ok(Self::new(config.url.clone(), config.model.clone())
+            .with_keep_alive(config.keep_alive.clone())
+            .with_num_ctx(config.num_ctx)
+            .with_timeouts(connect, read))
+    }
+
+    /// Builds the `/api/chat` request for the given conversation.
+    fn build_request(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> RequestBuilder {
+        // Ollama reads numbers as seconds and strings as durations like "10m"
+        let keep_alive =
+            self.keep_alive
+                .as_deref()
+                .map(|keep_alive| match keep_alive.trim().parse::<i64>() {
+                    Ok(secs) => Value::from(secs),
+                    Err(_) => Value::from(keep_alive.trim()),
+                });
+
+        let body = OllamaChatRequest {
+            model: options.model.as_deref().unwrap_or(&self.model),
+            messages,
+            stream,
+            keep_alive,
+            options: OllamaOptions {
+                temperature: options.temperature,
+                top_p: options.top_p,
+                num_predict: options.max_tokens,
+                stop: options.stop.as_deref(),
+                num_ctx: self.num_ctx,
+            },
+        };
+
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+
+        self.client.post(&url).json(&body)
+    }
+}
+
+/// Reads an environment variable that must be set and non-empty.
+fn required_env(name: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => Ok(value),
+        Ok(_) => Err(format!("Environment variable {} cannot be empty", name).into()),
+        Err(_) => Err(format!("Environment variable {} is not set or empty", name).into()),
+    }
+}
+
+#[async_trait]
+impl ChatApi for OllamaChatApi {
+    /// Sends a non-streaming request to `/api/chat`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChatCompletion)` — the reply, with the model, `done_reason` and token counts
+    ///   (`prompt_eval_count`, `eval_count`).
+    /// * `Err(ChatApiError)` — if the request fails, Ollama answers with an `error`
+    ///   (e.g. an unknown model is a `404` [`ChatApiError::Backend`]) or `message.content` is missing.
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        let response = send(self.build_request(messages, options, false)).await?;
+        let status = response.status().as_u16();
+        let json = read_json(response).await?;
+        if !json["error"].is_null() {
+            return Err(error_from_json(status, None, &json));
+        }
+
+        let content = json["message"]["content"].as_str().ok_or_else(|| {
+            ChatApiError::MalformedResponse("Missing content in the response!".to_string())
+        })?;
+
+        let usage = match (
+            json["prompt_eval_count"].as_u64(),
+            json["eval_count"].as_u64(),
+        ) {
+            (None, None) => None,
+            (prompt, completion) => {
+                let prompt_tokens = prompt.unwrap_or_default() as u32;
+                let completion_tokens = completion.unwrap_or_default() as u32;
+                Some(ChatUsage {
+                    prompt_tokens,
+                    completion_tokens,
+                    total_tokens: prompt_tokens + completion_tokens,
+                })
+            }
+        };
+
+        Ok(ChatCompletion {
+            content: content.to_string(),
+            model: json["model"].as_str().map(str::to_string),
+            finish_reason: json["done_reason"].as_str().map(str::to_string),
+            usage,
+        })
+    }
+
+    /// Sends a streaming request to `/api/chat` and yields the reply's fragments.
+    ///
+    /// Ollama answers with one JSON object per line carrying the next fragment in
+    /// `message.content`; the last one has `"done": true`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or Ollama answers with a non-success status.
+    /// The stream yields an error if a line is not valid JSON, carries an `error`, or the
+    /// connection breaks.
+    async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatStream, ChatApiError> {
+        let response = send(self.build_request(messages, options, true)).await?;
+        let status = response.status().as_u16();
+
+        let fragments = response_lines(response).filter_map(move |line| async move {
+            let line = match line {
+                Ok(line) if line.trim().is_empty() => return None,
+                Ok(line) => line,
+                Err(e) => return Some(Err(ChatApiError::Transport(e.to_string()))),
+            };
+            let chunk: Value = match serde_json::from_str(&line) {
+                Ok(chunk) => chunk,
+                Err(e) => return Some(Err(ChatApiError::MalformedResponse(e.to_string()))),
+            };
+            if !chunk["error"].is_null() {
+                return Some(Err(error_from_json(status, None, &chunk)));
+            }
+
+            chunk["message"]["content"]
+                .as_str()
+                .filter(|content| !content.is_empty())
+                .map(|content| Ok(content.to_string()))
+        });
+
+        Ok(Box::pin(fragments))
+    }
+}
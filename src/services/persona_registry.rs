@@ -0,0 +1,149 @@
+use serde::Deserialize;
+use std::env;
+use std::error::Error;
+use std::fs;
+
+use crate::models::persona::Persona;
+
+/// Name of the persona used when no personas file is configured.
+pub const DEFAULT_PERSONA_NAME: &str = "default";
+
+/// The personas file format.
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "default": "assistant",
+///   "personas": [
+///     { "name": "assistant", "system_prompt": "You are a helpful assistant." },
+///     { "name": "pirate", "system_prompt": "You are a pirate.", "temperature": 0.9 }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+struct PersonasFile {
+    default: Option<String>,
+    personas: Vec<Persona>,
+}
+
+/// `PersonaRegistry` holds the personas available to chats and REST requests.
+///
+/// Personas keep the order in which they were configured, and one of them is the default,
+/// used by chats that have not picked a persona and requests without a `persona` field.
+#[derive(Debug, Clone)]
+pub struct PersonaRegistry {
+    personas: Vec<Persona>,
+    default_index: usize,
+}
+
+impl Default for PersonaRegistry {
+    /// Creates a registry with a single persona named [`DEFAULT_PERSONA_NAME`]
+    /// that has no system prompt, so the model's own defaults apply.
+    fn default() -> Self {
+        Self {
+            personas: vec![Persona {
+                name: DEFAULT_PERSONA_NAME.to_string(),
+                ..Default::default()
+            }],
+            default_index: 0,
+        }
+    }
+}
+
+impl PersonaRegistry {
+    /// Creates a registry from a list of personas.
+    ///
+    /// # Arguments
+    ///
+    /// * `personas` - The available personas; names must be unique.
+    /// * `default` - Name of the default persona; the first persona is used if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the list is empty, contains duplicate or empty names,
+    /// or the default persona does not exist.
+    pub fn new(
+        personas: Vec<Persona>,
+        default: Option<&str>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if personas.is_empty() {
+            return Err("At least one persona must be configured".into());
+        }
+
+        for (index, persona) in personas.iter().enumerate() {
+            if persona.name.trim().is_empty() {
+                return Err("Persona names cannot be empty".into());
+            }
+            if personas[..index].iter().any(|p| p.name == persona.name) {
+                return Err(format!("Duplicate persona name: {}", persona.name).into());
+            }
+        }
+
+        let default_index = match default {
+            Some(name) => personas
+                .iter()
+                .position(|p| p.name == name)
+                .ok_or_else(|| format!("Default persona {} is not configured", name))?,
+            None => 0,
+        };
+
+        Ok(Self {
+            personas,
+            default_index,
+        })
+    }
+
+    /// Parses a registry from the JSON personas file format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is invalid or describes an invalid registry (see [`PersonaRegistry::new`]).
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let file: PersonasFile = serde_json::from_str(json)?;
+        Self::new(file.personas, file.default.as_deref())
+    }
+
+    /// Creates a registry using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `PERSONAS_FILE`: (optional) path of a JSON personas file; if unset or empty,
+    ///   the [`Default`] registry is used
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is invalid.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match env::var("PERSONAS_FILE") {
+            Ok(path) if !path.trim().is_empty() => {
+                let json = fs::read_to_string(&path)
+                    .map_err(|e| format!("Cannot read personas file {}: {}", path, e))?;
+                Self::from_json(&json)
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the persona with the given name, if it exists.
+    pub fn get(&self, name: &str) -> Option<&Persona> {
+        self.personas.iter().find(|p| p.name == name)
+    }
+
+    /// Returns the default persona.
+    pub fn default_persona(&self) -> &Persona {
+        &self.personas[self.default_index]
+    }
+
+    /// Returns the persona with the given name, falling back to the default one
+    /// if `name` is `None` or no longer configured.
+    pub fn resolve(&self, name: Option<&str>) -> &Persona {
+        name.and_then(|name| self.get(name))
+            .unwrap_or_else(|| self.default_persona())
+    }
+
+    /// Returns all personas in their configured order.
+    pub fn personas(&self) -> &[Persona] {
+        &self.personas
+    }
+}
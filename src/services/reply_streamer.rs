@@ -0,0 +1,267 @@
+use futures_util::StreamExt;
+use std::env;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use crate::services::chat_api::ChatStream;
+use crate::services::message_splitter::{split_message, TELEGRAM_MESSAGE_LIMIT};
+use crate::services::telegram_api::TelegramApi;
+
+/// Default minimum time between two edits of the same message.
+///
+/// Telegram allows roughly one message update per second per chat; editing more often
+/// gets the bot throttled with `429 Too Many Requests`.
+pub const DEFAULT_EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Text of the message sent while the model has not produced anything yet.
+pub const PLACEHOLDER_TEXT: &str = "…";
+
+/// Suffix shown after the partial reply while the model is still generating.
+const TYPING_SUFFIX: &str = " …";
+
+/// Name of the file very long replies are sent as.
+pub const DOCUMENT_FILE_NAME: &str = "answer.md";
+
+/// Text the placeholder is replaced with when the reply is sent as a file.
+pub const DOCUMENT_NOTICE: &str =
+    "📄 The answer is too long for a message, so here it is as a file.";
+
+/// `ReplyStreamer` delivers a streamed model reply to a Telegram chat progressively.
+///
+/// It sends a placeholder message right away, then edits it with the text received so far
+/// at most once per `edit_interval`, and finally edits it with the complete reply.
+///
+/// Replies longer than one Telegram message are split with [`split_message`]: the placeholder
+/// shows the first part and the other parts follow as new messages. With a document threshold,
+/// replies longer than it are sent as a Markdown file instead.
+///
+/// The model's Markdown is shown with Telegram's formatting, see
+/// [`TelegramApi::send_markdown_message`].
+#[derive(Debug, Clone)]
+pub struct ReplyStreamer {
+    edit_interval: Duration,
+    message_limit: usize,
+    document_threshold: Option<usize>,
+}
+
+impl Default for ReplyStreamer {
+    fn default() -> Self {
+        Self::new(DEFAULT_EDIT_INTERVAL)
+    }
+}
+
+impl ReplyStreamer {
+    /// Creates a new streamer.
+    ///
+    /// # Arguments
+    ///
+    /// * `edit_interval` - Minimum time between two intermediate edits of the reply.
+    pub fn new(edit_interval: Duration) -> Self {
+        Self {
+            edit_interval,
+            message_limit: TELEGRAM_MESSAGE_LIMIT,
+            document_threshold: None,
+        }
+    }
+
+    /// Sets the maximum characters per message, [`TELEGRAM_MESSAGE_LIMIT`] by default.
+    pub fn with_message_limit(mut self, message_limit: usize) -> Self {
+        self.message_limit = message_limit;
+        self
+    }
+
+    /// Sets the length (in characters) above which a reply is sent as a file;
+    /// `None` (the default) always sends messages.
+    pub fn with_document_threshold(mut self, document_threshold: Option<usize>) -> Self {
+        self.document_threshold = document_threshold;
+        self
+    }
+
+    /// Creates a new streamer using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `TELEGRAM_EDIT_INTERVAL_MS`: (optional) minimum milliseconds between two edits,
+    ///   defaults to [`DEFAULT_EDIT_INTERVAL`]
+    /// - `TELEGRAM_DOCUMENT_THRESHOLD`: (optional) replies longer than this many characters are
+    ///   sent as a Markdown file; unset, empty or `0` disables it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is set but is not a valid number.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let edit_interval = match env::var("TELEGRAM_EDIT_INTERVAL_MS") {
+            Ok(value) if !value.trim().is_empty() => {
+                let millis: u64 = value.trim().parse().map_err(|_| {
+                    "Environment variable TELEGRAM_EDIT_INTERVAL_MS must be a number"
+                })?;
+                Duration::from_millis(millis)
+            }
+            _ => DEFAULT_EDIT_INTERVAL,
+        };
+
+        let document_threshold = match env::var("TELEGRAM_DOCUMENT_THRESHOLD") {
+            Ok(value) if !value.trim().is_empty() => {
+                let threshold: usize = value.trim().parse().map_err(|_| {
+                    "Environment variable TELEGRAM_DOCUMENT_THRESHOLD must be a number"
+                })?;
+                (threshold > 0).then_some(threshold)
+            }
+            _ => None,
+        };
+
+        Ok(Self::new(edit_interval).with_document_threshold(document_threshold))
+    }
+
+    /// Sends the reply fragments to a chat, editing a single message as they arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `telegram_api` - The API used to send and edit the message.
+    /// * `chat_id` - The chat to reply to.
+    /// * `fragments` - The model's reply, as streamed by `ChatApi::complete_stream`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` — The complete reply, after the chat shows all of it.
+    /// * `Err(Box<dyn Error + Send + Sync>)` — If the placeholder or a part of the final reply
+    ///   could not be sent, the model produced nothing, or the stream failed. When the stream
+    ///   fails, the reply so far is delivered with a notice that it was interrupted, or the
+    ///   error's [`ChatApiError::user_message`](crate::services::chat_api::ChatApiError::user_message)
+    ///   if there is none.
+    pub async fn deliver(
+        &self,
+        telegram_api: &dyn TelegramApi,
+        chat_id: i64,
+        fragments: ChatStream,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.deliver_reply(telegram_api, chat_id, None, fragments)
+            .await
+    }
+
+    /// Sends the reply fragments to a chat like [`ReplyStreamer::deliver`], with the placeholder
+    /// answering the message `reply_to_message_id` if given.
+    pub async fn deliver_reply(
+        &self,
+        telegram_api: &dyn TelegramApi,
+        chat_id: i64,
+        reply_to_message_id: Option<i64>,
+        mut fragments: ChatStream,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let placeholder = PLACEHOLDER_TEXT.to_string();
+        let placeholder = match reply_to_message_id {
+            Some(reply_to_message_id) => {
+                telegram_api
+                    .send_reply_message(chat_id, reply_to_message_id, placeholder)
+                    .await?
+            }
+            None => {
+                telegram_api
+                    .send_telegram_message(chat_id, placeholder)
+                    .await?
+            }
+        };
+        let message_id = placeholder.message_id;
+
+        let mut text = String::new();
+        let mut shown = String::new();
+        let mut last_edit = Instant::now();
+
+        while let Some(fragment) = fragments.next().await {
+            let fragment = match fragment {
+                Ok(fragment) => fragment,
+                Err(e) => {
+                    let notice = if text.trim().is_empty() {
+                        e.user_message().to_string()
+                    } else {
+                        format!("{}\n\n⚠️ The answer was interrupted.", text)
+                    };
+                    if let Err(finish_error) = self
+                        .finish(telegram_api, chat_id, message_id, &notice)
+                        .await
+                    {
+                        eprintln!("Error editing Telegram message: {}", finish_error);
+                    }
+                    return Err(e.into());
+                }
+            };
+
+            text.push_str(&fragment);
+
+            if last_edit.elapsed() >= self.edit_interval && !text.trim().is_empty() {
+                // Only the first part is shown while generating; it stops changing
+                // once the reply outgrows a single message.
+                let limit = self.message_limit.saturating_sub(TYPING_SUFFIX.len());
+                let first = split_message(&text, limit).into_iter().next();
+                let partial = format!("{}{}", first.unwrap_or_default(), TYPING_SUFFIX);
+                if partial != shown {
+                    if let Err(e) = telegram_api
+                        .edit_markdown_message(chat_id, message_id, partial.clone())
+                        .await
+                    {
+                        eprintln!("Error editing Telegram message: {}", e);
+                    }
+                    shown = partial;
+                }
+                last_edit = Instant::now();
+            }
+        }
+
+        if text.trim().is_empty() {
+            telegram_api
+                .edit_telegram_message(
+                    chat_id,
+                    message_id,
+                    "Sorry, I have no answer to that.".to_string(),
+                )
+                .await?;
+            return Err("The model returned an empty reply".into());
+        }
+
+        self.finish(telegram_api, chat_id, message_id, &text)
+            .await?;
+
+        Ok(text)
+    }
+
+    /// Shows the complete `text`: in the placeholder and as many further messages as needed,
+    /// or as a file if it is longer than the document threshold.
+    async fn finish(
+        &self,
+        telegram_api: &dyn TelegramApi,
+        chat_id: i64,
+        message_id: i64,
+        text: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let as_document = self
+            .document_threshold
+            .is_some_and(|threshold| text.chars().count() > threshold);
+        if as_document {
+            telegram_api
+                .edit_telegram_message(chat_id, message_id, DOCUMENT_NOTICE.to_string())
+                .await?;
+            telegram_api
+                .send_document(
+                    chat_id,
+                    DOCUMENT_FILE_NAME.to_string(),
+                    text.as_bytes().to_vec(),
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let mut parts = split_message(text, self.message_limit).into_iter();
+
+        let first = parts.next().unwrap_or_default();
+        telegram_api
+            .edit_markdown_message(chat_id, message_id, first)
+            .await?;
+
+        for part in parts {
+            telegram_api.send_markdown_message(chat_id, part).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+use async_trait::async_trait;
+use std::env;
+use std::error::Error;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use crate::models::health::{BackendHealth, CircuitState};
+use crate::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+
+/// Default number of times a failed request is repeated.
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// Default wait before the first retry; it doubles with every further retry.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Default upper bound of the wait between retries.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// Default number of consecutive failures that open the circuit.
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time the circuit stays open before a request may test the backend again.
+pub const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// How [`ResilientChatApi`] retries requests and when it gives up on a backend.
+///
+/// # Fields
+///
+/// * `max_retries` – How often a failed request is repeated; `0` disables retries.
+/// * `initial_backoff` – The wait before the first retry, doubled for every further one.
+/// * `max_backoff` – The longest wait between retries.
+/// * `failure_threshold` – Consecutive failures after which the circuit opens.
+/// * `open_duration` – How long the circuit stays open before the backend is tested again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResilienceConfig {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub failure_threshold: u32,
+    pub open_duration: Duration,
+}
+
+impl Default for ResilienceConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            open_duration: DEFAULT_OPEN_DURATION,
+        }
+    }
+}
+
+impl ResilienceConfig {
+    /// Reads the configuration from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `CHAT_API_MAX_RETRIES`: (optional) retries of a failed request,
+    ///   defaults to [`DEFAULT_MAX_RETRIES`]
+    /// - `CHAT_API_RETRY_BACKOFF_MS`: (optional) wait before the first retry in milliseconds,
+    ///   defaults to [`DEFAULT_INITIAL_BACKOFF`]
+    /// - `CHAT_API_FAILURE_THRESHOLD`: (optional) consecutive failures that open the circuit,
+    ///   defaults to [`DEFAULT_FAILURE_THRESHOLD`]
+    /// - `CHAT_API_OPEN_SECS`: (optional) seconds the circuit stays open,
+    ///   defaults to [`DEFAULT_OPEN_DURATION`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is set but is not a valid number.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let defaults = Self::default();
+
+        Ok(Self {
+            max_retries: number_from_env("CHAT_API_MAX_RETRIES", defaults.max_retries)?,
+            initial_backoff: Duration::from_millis(number_from_env(
+                "CHAT_API_RETRY_BACKOFF_MS",
+                defaults.initial_backoff.as_millis() as u64,
+            )?),
+            max_backoff: defaults.max_backoff,
+            failure_threshold: number_from_env(
+                "CHAT_API_FAILURE_THRESHOLD",
+                defaults.failure_threshold,
+            )?
+            .max(1),
+            open_duration: Duration::from_secs(number_from_env(
+                "CHAT_API_OPEN_SECS",
+                defaults.open_duration.as_secs(),
+            )?),
+        })
+    }
+
+    /// Returns the wait before a retry, doubling from `initial_backoff` up to `max_backoff`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+/// Reads a number from an environment variable, falling back to `default` when it is unset or empty.
+fn number_from_env<T: std::str::FromStr>(
+    name: &str,
+    default: T,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map_err(|_| format!("Environment variable {} must be a number", name).into()),
+        _ => Ok(default),
+    }
+}
+
+/// The circuit breaker of one backend.
+#[derive(Debug, Default)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_started: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// `ResilientChatApi` wraps a [`ChatApi`] with retries and a circuit breaker.
+///
+/// Chat completions have no side effects, so a request that failed because of the backend —
+/// a broken connection, a timeout, a rate limit or a `5xx` answer — is repeated with
+/// exponential backoff. A rate limit's `Retry-After` is honored when it is not longer than
+/// the maximal backoff. Errors caused by the request itself (authentication, context length,
+/// content filters) are returned right away.
+///
+/// After `failure_threshold` consecutive failures the circuit opens: requests fail with
+/// [`ChatApiError::Unavailable`] without calling the backend. Once `open_duration` passed,
+/// the circuit is half-open and lets a single request through; its success closes the
+/// circuit, its failure opens it again.
+///
+/// Streams are retried only until they started; fragments already sent cannot be taken back.
+pub struct ResilientChatApi {
+    name: String,
+    inner: Arc<dyn ChatApi>,
+    config: ResilienceConfig,
+    circuit: Mutex<Circuit>,
+}
+
+impl ResilientChatApi {
+    /// Creates a new wrapper around an API.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The backend's name, reported by [`ChatApi::health`] and in logs.
+    /// * `inner` - The API that answers the requests.
+    /// * `config` - How to retry requests and when to open the circuit.
+    pub fn new(name: impl Into<String>, inner: Arc<dyn ChatApi>, config: ResilienceConfig) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+            config,
+            circuit: Mutex::new(Circuit::default()),
+        }
+    }
+
+    /// Returns the backend's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Probes the backend if its circuit is open and due for a trial, closing the circuit
+    /// when the backend answers. Without traffic, an open circuit would otherwise stay open.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the circuit is closed afterwards.
+    pub async fn recover(&self) -> bool {
+        if self.circuit.lock().unwrap().state == CircuitState::Closed {
+            return true;
+        }
+        if self.acquire().is_err() {
+            return false;
+        }
+
+        let result = self.inner.probe().await;
+        if let Err(e) = &result {
+            eprintln!("Probe of chat backend {} failed: {}", self.name, e);
+        }
+        self.record(result.as_ref().err());
+
+        self.circuit.lock().unwrap().state == CircuitState::Closed
+    }
+
+    /// Returns the current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        self.health()[0].state
+    }
+
+    /// Checks whether the circuit lets a request through, moving it from open to half-open
+    /// once `open_duration` passed.
+    fn acquire(&self) -> Result<(), ChatApiError> {
+        let mut circuit = self.circuit.lock().unwrap();
+        let now = Instant::now();
+
+        match circuit.state {
+            CircuitState::Closed => return Ok(()),
+            CircuitState::Open => {
+                let opened_at = circuit.opened_at.unwrap_or(now);
+                if now.duration_since(opened_at) >= self.config.open_duration {
+                    circuit.state = CircuitState::HalfOpen;
+                    circuit.trial_started = Some(now);
+                    return Ok(());
+                }
+            }
+            CircuitState::HalfOpen => {
+                // A trial whose caller gave up must not block the backend forever
+                let trial_running = circuit
+                    .trial_started
+                    .is_some_and(|started| now.duration_since(started) < self.config.open_duration);
+                if !trial_running {
+                    circuit.trial_started = Some(now);
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(ChatApiError::Unavailable(format!(
+            "backend {} is unhealthy",
+            self.name
+        )))
+    }
+
+    /// Updates the circuit with the outcome of a request.
+    ///
+    /// Only a success closes the circuit. Errors caused by the request (e.g. a rejected key)
+    /// say nothing about the backend's health and leave the counters as they are, but they
+    /// still fail a half-open trial, as the backend did not answer it.
+    fn record(&self, error: Option<&ChatApiError>) {
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.trial_started = None;
+
+        match error {
+            Some(e) if e.is_transient() => {
+                circuit.consecutive_failures += 1;
+                circuit.last_error = Some(e.to_string());
+
+                let should_open = circuit.state == CircuitState::HalfOpen
+                    || circuit.consecutive_failures >= self.config.failure_threshold;
+                if should_open {
+                    if circuit.state != CircuitState::Open {
+                        eprintln!(
+                            "Chat backend {} is unhealthy after {} failures: {}",
+                            self.name, circuit.consecutive_failures, e
+                        );
+                    }
+                    circuit.state = CircuitState::Open;
+                    circuit.opened_at = Some(Instant::now());
+                }
+            }
+            Some(e) => {
+                if circuit.state == CircuitState::HalfOpen {
+                    eprintln!(
+                        "Chat backend {} is still unhealthy, trial request failed: {}",
+                        self.name, e
+                    );
+                    circuit.last_error = Some(e.to_string());
+                    circuit.state = CircuitState::Open;
+                    circuit.opened_at = Some(Instant::now());
+                }
+            }
+            None => {
+                if circuit.state != CircuitState::Closed {
+                    println!("💚 Chat backend {} recovered", self.name);
+                }
+                circuit.state = CircuitState::Closed;
+                circuit.consecutive_failures = 0;
+                circuit.opened_at = None;
+            }
+        }
+    }
+
+    /// Runs a request through the circuit breaker, retrying transient failures.
+    async fn call<T, F, Fut>(&self, request: F) -> Result<T, ChatApiError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ChatApiError>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            self.acquire()?;
+            let result = request().await;
+            self.record(result.as_ref().err());
+
+            let error = match result {
+                Err(e) if e.is_transient() && attempt < self.config.max_retries => e,
+                result => return result,
+            };
+
+            let delay = match error.retry_after() {
+                Some(delay) if delay <= self.config.max_backoff => delay,
+                Some(_) => return Err(error),
+                None => self.config.backoff(attempt),
+            };
+            attempt += 1;
+            eprintln!(
+                "Chat backend {} failed ({}), retry {}/{} in {:?}",
+                self.name, error, attempt, self.config.max_retries, delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[async_trait]
+impl ChatApi for ResilientChatApi {
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        self.call(|| self.inner.complete(messages, options)).await
+    }
+
+    async fn complete_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatStream, ChatApiError> {
+        self.call(|| self.inner.complete_stream(messages, options))
+            .await
+    }
+
+    fn health(&self) -> Vec<BackendHealth> {
+        let circuit = self.circuit.lock().unwrap();
+
+        // An open circuit whose time is up lets the next request through
+        let state = match circuit.opened_at {
+            Some(opened_at)
+                if circuit.state == CircuitState::Open
+                    && opened_at.elapsed() >= self.config.open_duration =>
+            {
+                CircuitState::HalfOpen
+            }
+            _ => circuit.state,
+        };
+
+        vec![BackendHealth {
+            name: self.name.clone(),
+            state,
+            consecutive_failures: circuit.consecutive_failures,
+            last_error: circuit.last_error.clone(),
+        }]
+    }
+}
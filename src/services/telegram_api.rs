@@ -1,22 +1,520 @@
 use async_trait::async_trait;
+use std::fmt;
+use std::time::Duration;
+
+use crate::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    EditMessageTextRequest, InputFile, SendMediaOptions, SendMessageRequest, TelegramFile,
+    TelegramMessage, TelegramUpdate, TelegramUser,
+};
+
+/// An error returned by a Telegram Bot API call.
+///
+/// API errors keep the `error_code`, `description` and `parameters` Telegram answered with,
+/// so callers can react to flood control (`retry_after`) and group migrations
+/// (`migrate_to_chat_id`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TelegramApiError {
+    /// The request could not be built or sent, or its response could not be read.
+    Http(String),
+    /// Telegram refused the request (`"ok": false`).
+    Api {
+        error_code: i64,
+        description: String,
+        retry_after: Option<u64>,
+        migrate_to_chat_id: Option<i64>,
+    },
+    /// Telegram answered with a result that does not match the expected type.
+    InvalidResponse(String),
+}
+
+impl TelegramApiError {
+    /// Returns how long Telegram asked to wait before repeating the request, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            TelegramApiError::Api {
+                retry_after: Some(seconds),
+                ..
+            } => Some(Duration::from_secs(*seconds)),
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of the supergroup the target group was migrated to, if it was.
+    pub fn migrate_to_chat_id(&self) -> Option<i64> {
+        match self {
+            TelegramApiError::Api {
+                migrate_to_chat_id, ..
+            } => *migrate_to_chat_id,
+            _ => None,
+        }
+    }
+
+    /// Returns whether Telegram refused the request with `409 Conflict`, e.g. `getUpdates`
+    /// while a webhook is set or another instance of the bot is polling.
+    pub fn is_conflict(&self) -> bool {
+        matches!(
+            self,
+            TelegramApiError::Api {
+                error_code: 409,
+                ..
+            }
+        )
+    }
+
+    /// Returns whether Telegram rejected the formatting of a message
+    /// (`Bad Request: can't parse entities: ...`).
+    pub fn is_parse_entities_error(&self) -> bool {
+        match self {
+            TelegramApiError::Api { description, .. } => {
+                description.to_lowercase().contains("can't parse entities")
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TelegramApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TelegramApiError::Http(message) => write!(f, "HTTP error: {}", message),
+            TelegramApiError::Api {
+                error_code,
+                description,
+                ..
+            } => write!(f, "Telegram API error {}: {}", error_code, description),
+            TelegramApiError::InvalidResponse(message) => {
+                write!(f, "Invalid Telegram response: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TelegramApiError {}
 
 /// `TelegramApi` defines an interface for sending messages via the Telegram Bot API.
 ///
 /// This trait allows different implementations, including mock implementations for testing
 /// and real ones that send actual HTTP requests.
+///
+/// Implementations provide the Bot API methods the bot uses, returning Telegram's typed
+/// results; the convenience methods (`send_telegram_message`, `send_document`, ...) build on them.
 #[async_trait]
 pub trait TelegramApi: Send + Sync {
-    /// Sends a text message to a specified Telegram chat.
+    /// Sends a message using the `sendMessage` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The message and its options (formatting, reply, inline keyboard, ...).
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError>;
+
+    /// Edits the text of a message using the `editMessageText` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The message to edit, its new text and options.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the edited message.
+    /// - `Err(TelegramApiError)` describing the error if editing failed.
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError>;
+
+    /// Deletes a message using the `deleteMessage` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier of the chat the message belongs to.
+    /// * `message_id` - The ID of the message to delete.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(true)` if the message was deleted.
+    /// - `Err(TelegramApiError)` describing the error if deleting failed.
+    async fn delete_message(&self, chat_id: i64, message_id: i64)
+        -> Result<bool, TelegramApiError>;
+
+    /// Sends a file using the `sendDocument` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `document` - The file: an uploaded one, a `file_id` or a URL.
+    /// * `options` - Caption and reply options.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_document_file(
+        &self,
+        chat_id: i64,
+        document: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError>;
+
+    /// Shows a chat action, such as "typing…", to the members of a chat.
+    ///
+    /// The action disappears after about 5 seconds or when the bot sends a message,
+    /// so it has to be repeated for as long as the bot is busy.
     ///
     /// # Arguments
     ///
     /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `message_thread_id` - The forum topic to show the action in; `None` for the whole chat.
+    /// * `action` - The activity to show.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(true)` if the action was sent successfully.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_chat_action(
+        &self,
+        chat_id: i64,
+        message_thread_id: Option<i64>,
+        action: ChatAction,
+    ) -> Result<bool, TelegramApiError>;
+
+    /// Receives incoming updates using long polling.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The `update_id` of the first update to return, usually the last
+    ///   processed `update_id` plus one; `None` returns the oldest unconfirmed updates.
+    /// * `timeout_secs` - How long Telegram waits for an update before answering with an empty list.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(Vec<TelegramUpdate>)` with the received updates, oldest first. An update that
+    ///   cannot be parsed is returned with only its `update_id`, so the caller still moves past it.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError>;
+
+    /// Returns a user's membership in a chat, e.g. to check whether the user is an administrator.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The chat to look in.
+    /// * `user_id` - The user to look up.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(ChatMember)` with the user's status in the chat.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn get_chat_member(
+        &self,
+        chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError>;
+
+    /// Answers a callback query using the `answerCallbackQuery` method.
+    ///
+    /// Telegram clients show a progress indicator on the pressed button until the query is
+    /// answered, so every query should be answered, even without a notification.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The query to answer and the notification to show.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(true)` if the query was answered.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn answer_callback_query(
+        &self,
+        request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError>;
+
+    /// Answers an inline query using the `answerInlineQuery` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The query to answer and its results.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(true)` if the query was answered.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn answer_inline_query(
+        &self,
+        request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError>;
+
+    /// Sends a photo using the `sendPhoto` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `photo` - The photo: an uploaded file, a `file_id` or a URL.
+    /// * `options` - Caption and reply options.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_photo(
+        &self,
+        chat_id: i64,
+        photo: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError>;
+
+    /// Sends a voice note using the `sendVoice` method.
+    ///
+    /// The audio must be OGG encoded with OPUS (or MP3 / M4A) to show as a voice note.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `voice` - The audio: an uploaded file, a `file_id` or a URL.
+    /// * `options` - Caption and reply options.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_voice(
+        &self,
+        chat_id: i64,
+        voice: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError>;
+
+    /// Prepares a file for downloading using the `getFile` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Identifier of the file, e.g. of a received voice note.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramFile)` with the file and its download path.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError>;
+
+    /// Downloads the content of a file returned by [`TelegramApi::get_file`].
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to download.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(Vec<u8>)` with the file content.
+    /// - `Err(TelegramApiError)` if the file has no download path (files over 20 MB)
+    ///   or the download failed.
+    async fn download_file(&self, file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError>;
+
+    /// Returns the bot's own user using the `getMe` method.
+    ///
+    /// Useful to check that the bot token is valid and to learn the bot's username.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramUser)` with the bot user.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError>;
+
+    /// Publishes the bot's command list using the `setMyCommands` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The commands shown in the Telegram command menu.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(true)` if the commands were published.
+    /// - `Err(TelegramApiError)` describing the error if the request failed.
+    async fn set_my_commands(&self, commands: &[BotCommand]) -> Result<bool, TelegramApiError>;
+
+    /// Sends a plain text message to a specified Telegram chat.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `text` - The message content to be sent.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_telegram_message(
+        &self,
+        chat_id: i64,
+        text: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        let request = SendMessageRequest {
+            chat_id,
+            text,
+            ..Default::default()
+        };
+        self.send_message(&request).await
+    }
+
+    /// Sends a text message as a reply to another message of the chat.
+    ///
+    /// In groups this keeps the conversation readable, as the reply shows the message it answers.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `reply_to_message_id` - The ID of the message being answered.
     /// * `text` - The message content to be sent.
     ///
     /// # Returns
     ///
     /// A `Result`:
-    /// - `Ok(())` if the message was sent successfully.
-    /// - `Err(String)` with a description of the error if sending failed.
-    async fn send_telegram_message(&self, chat_id: i64, text: String) -> Result<(), String>;
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_reply_message(
+        &self,
+        chat_id: i64,
+        reply_to_message_id: i64,
+        text: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        let request = SendMessageRequest {
+            chat_id,
+            text,
+            reply_to_message_id: Some(reply_to_message_id),
+            ..Default::default()
+        };
+        self.send_message(&request).await
+    }
+
+    /// Replaces the text of a message previously sent by the bot with plain text.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier of the chat the message belongs to.
+    /// * `message_id` - The ID of the message to edit.
+    /// * `text` - The new message content.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the edited message.
+    /// - `Err(TelegramApiError)` describing the error if editing failed.
+    async fn edit_telegram_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        let request = EditMessageTextRequest {
+            chat_id,
+            message_id,
+            text,
+            ..Default::default()
+        };
+        self.edit_message_text(&request).await
+    }
+
+    /// Sends a message written in Markdown, rendered with Telegram's formatting.
+    ///
+    /// Implementations that cannot format text send it as it is; the default implementation
+    /// does exactly that via [`TelegramApi::send_telegram_message`].
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `markdown` - The message content, as CommonMark (e.g. a model's reply).
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_markdown_message(
+        &self,
+        chat_id: i64,
+        markdown: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.send_telegram_message(chat_id, markdown).await
+    }
+
+    /// Replaces the text of a message with Markdown, rendered with Telegram's formatting.
+    ///
+    /// The default implementation edits the message with the text as it is via
+    /// [`TelegramApi::edit_telegram_message`].
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier of the chat the message belongs to.
+    /// * `message_id` - The ID of the message to edit.
+    /// * `markdown` - The new message content, as CommonMark.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the edited message.
+    /// - `Err(TelegramApiError)` describing the error if editing failed.
+    async fn edit_markdown_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        markdown: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.edit_telegram_message(chat_id, message_id, markdown)
+            .await
+    }
+
+    /// Uploads content as a file to a specified Telegram chat.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The unique identifier for the target Telegram chat.
+    /// * `file_name` - The name the file is shown with, e.g. `answer.md`.
+    /// * `content` - The file content.
+    /// * `caption` - Optional text shown below the file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result`:
+    /// - `Ok(TelegramMessage)` with the sent message.
+    /// - `Err(TelegramApiError)` describing the error if sending failed.
+    async fn send_document(
+        &self,
+        chat_id: i64,
+        file_name: String,
+        content: Vec<u8>,
+        caption: Option<String>,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        let document = InputFile::Upload { file_name, content };
+        let options = SendMediaOptions {
+            caption,
+            ..Default::default()
+        };
+        self.send_document_file(chat_id, document, &options).await
+    }
 }
@@ -1,15 +1,42 @@
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
 
-use crate::models::telegram::SendMessageRequest;
-use crate::services::telegram_api::TelegramApi;
+use crate::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    DeleteMessageRequest, DeleteWebhookRequest, EditMessageTextRequest, GetChatMemberRequest,
+    GetFileRequest, GetUpdatesRequest, InputFile, ParseMode, SendChatActionRequest,
+    SendMediaOptions, SendMessageRequest, SetMyCommandsRequest, SetWebhookRequest, TelegramFile,
+    TelegramMessage, TelegramResponse, TelegramUpdate, TelegramUser, WebhookInfo,
+};
+use crate::services::telegram_api::{TelegramApi, TelegramApiError};
+use crate::services::telegram_format::markdown_to_telegram_html;
+
+/// How many times a request refused by Telegram's flood control is repeated.
+pub const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// The longest `retry_after` waited for; requests asked to wait longer fail right away.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How much longer than its long polling timeout a `getUpdates` request may take before it is abandoned.
+pub const GET_UPDATES_TIMEOUT_MARGIN: Duration = Duration::from_secs(10);
 
 /// A real implementation of the `TelegramApi` trait that sends HTTP requests to the Telegram Bot API.
+///
+/// When Telegram reports that a group was upgraded to a supergroup, the new chat ID is
+/// remembered, so later requests to the group go straight to the supergroup.
 pub struct RealTelegramApi {
     pub client: Client,
     pub base_url: String,
     pub token: String,
+    migrated_chats: Mutex<HashMap<i64, i64>>,
 }
 
 impl RealTelegramApi {
@@ -28,6 +55,7 @@ impl RealTelegramApi {
             client: Client::new(),
             base_url,
             token,
+            migrated_chats: Mutex::new(HashMap::new()),
         }
     }
 
@@ -54,47 +82,667 @@ impl RealTelegramApi {
             return Err("Environment variable TELEGRAM_BOT_TOKEN cannot be empty".into());
         }
 
-        Ok(Self {
-            client: Client::new(),
-            base_url,
-            token,
+        Ok(Self::new(base_url, token))
+    }
+
+    /// Registers the bot's webhook using the Telegram Bot API `setWebhook` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - HTTPS URL of the webhook route, e.g. `https://example.com/telegram/webhook`.
+    /// * `secret_token` - Secret Telegram sends back in the `X-Telegram-Bot-Api-Secret-Token`
+    ///   header, see `TELEGRAM_WEBHOOK_SECRET`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or a [`TelegramApiError`] on failure.
+    pub async fn set_webhook(
+        &self,
+        url: &str,
+        secret_token: Option<&str>,
+    ) -> Result<(), TelegramApiError> {
+        let request = SetWebhookRequest {
+            url: url.to_string(),
+            secret_token: secret_token.map(str::to_string),
+        };
+        let _: bool = self.call_method("setWebhook", &request).await?;
+
+        Ok(())
+    }
+
+    /// Removes the bot's webhook using the Telegram Bot API `deleteWebhook` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `drop_pending_updates` - Whether Telegram should discard the updates not delivered yet.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or a [`TelegramApiError`] on failure.
+    pub async fn delete_webhook(&self, drop_pending_updates: bool) -> Result<(), TelegramApiError> {
+        let request = DeleteWebhookRequest {
+            drop_pending_updates,
+        };
+        let _: bool = self.call_method("deleteWebhook", &request).await?;
+
+        Ok(())
+    }
+
+    /// Returns the current webhook status using the Telegram Bot API `getWebhookInfo` method.
+    ///
+    /// # Returns
+    ///
+    /// The webhook status on success, or a [`TelegramApiError`] on failure.
+    pub async fn get_webhook_info(&self) -> Result<WebhookInfo, TelegramApiError> {
+        self.call_method("getWebhookInfo", &serde_json::json!({}))
+            .await
+    }
+
+    /// Sends a file with one of the `sendDocument`-like methods as `multipart/form-data`.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The Bot API method name, e.g. `sendPhoto`.
+    /// * `field` - The form field holding the file, e.g. `photo`.
+    /// * `chat_id` - Telegram chat ID to send the file to.
+    /// * `file` - The file to send.
+    /// * `options` - Caption and reply options.
+    async fn send_media(
+        &self,
+        method: &str,
+        field: &'static str,
+        chat_id: i64,
+        file: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        // A form cannot be cloned, so it is built again for every attempt
+        self.execute(method, Some(chat_id), |chat_id| {
+            let chat_id = chat_id.unwrap_or_default();
+            let mut form = Form::new().text("chat_id", chat_id.to_string());
+            form = match &file {
+                InputFile::FileId(value) | InputFile::Url(value) => form.text(field, value.clone()),
+                InputFile::Upload { file_name, content } => form.part(
+                    field,
+                    Part::bytes(content.clone()).file_name(file_name.clone()),
+                ),
+            };
+            if let Some(caption) = &options.caption {
+                form = form.text("caption", caption.clone());
+            }
+            if let Some(parse_mode) = options.parse_mode {
+                form = form.text("parse_mode", parse_mode.as_str());
+            }
+            if let Some(reply_to_message_id) = options.reply_to_message_id {
+                form = form.text("reply_to_message_id", reply_to_message_id.to_string());
+            }
+
+            self.client.post(self.method_url(method)).multipart(form)
         })
+        .await
+    }
+
+    /// Calls a Bot API method with a JSON body and returns the parsed `result` of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The Bot API method name (e.g. `sendMessage`).
+    /// * `body` - The method parameters, serialized as JSON.
+    ///
+    /// # Returns
+    ///
+    /// The `result` on success, or a [`TelegramApiError`] describing the HTTP or API error.
+    async fn call_method<R, B>(&self, method: &str, body: &B) -> Result<R, TelegramApiError>
+    where
+        R: DeserializeOwned,
+        B: Serialize + ?Sized,
+    {
+        let body = serde_json::to_value(body).map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        let chat_id = body.get("chat_id").and_then(Value::as_i64);
+
+        self.execute(method, chat_id, |chat_id| {
+            let mut body = body.clone();
+            if let Some(chat_id) = chat_id {
+                body["chat_id"] = chat_id.into();
+            }
+            self.client.post(self.method_url(method)).json(&body)
+        })
+        .await
+    }
+
+    /// Sends a Bot API request, repeating it when Telegram asks to.
+    ///
+    /// Requests refused by flood control are repeated after the `retry_after` Telegram
+    /// answered with, at most [`MAX_RATE_LIMIT_RETRIES`] times and only for waits up to
+    /// [`MAX_RETRY_AFTER`]. Requests to a group that was upgraded to a supergroup are sent
+    /// again to the supergroup's `migrate_to_chat_id`, which is used for the chat from then on.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The Bot API method name, used in logs and errors.
+    /// * `chat_id` - The chat the request targets, if any.
+    /// * `build` - Builds the request for the given chat.
+    async fn execute<R, F>(
+        &self,
+        method: &str,
+        chat_id: Option<i64>,
+        build: F,
+    ) -> Result<R, TelegramApiError>
+    where
+        R: DeserializeOwned,
+        F: Fn(Option<i64>) -> RequestBuilder,
+    {
+        let mut chat_id = chat_id.map(|chat_id| self.current_chat_id(chat_id));
+        let mut retries = 0;
+        loop {
+            let error = match self.send_request(method, build(chat_id)).await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+
+            let migrated_to = error.migrate_to_chat_id().filter(|new_chat_id| {
+                chat_id.is_some_and(|old_chat_id| old_chat_id != *new_chat_id)
+            });
+            if let Some(new_chat_id) = migrated_to {
+                println!(
+                    "🔀 Chat {} was migrated to {}, repeating {}",
+                    chat_id.unwrap_or_default(),
+                    new_chat_id,
+                    method
+                );
+                if let Some(old_chat_id) = chat_id {
+                    self.migrated_chats
+                        .lock()
+                        .unwrap()
+                        .insert(old_chat_id, new_chat_id);
+                }
+                chat_id = Some(new_chat_id);
+                continue;
+            }
+
+            match error.retry_after() {
+                Some(wait) if retries < MAX_RATE_LIMIT_RETRIES && wait <= MAX_RETRY_AFTER => {
+                    retries += 1;
+                    println!(
+                        "⏳ Telegram rate limit on {}, retrying in {}s",
+                        method,
+                        wait.as_secs()
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// Returns the ID of the supergroup a chat was migrated to, or the chat ID itself.
+    pub fn current_chat_id(&self, chat_id: i64) -> i64 {
+        self.migrated_chats
+            .lock()
+            .unwrap()
+            .get(&chat_id)
+            .copied()
+            .unwrap_or(chat_id)
+    }
+
+    /// Returns the URL of a Bot API method.
+    fn method_url(&self, method: &str) -> String {
+        format!("{}/bot{}/{}", self.base_url, self.token, method)
+    }
+
+    /// Sends a prepared Bot API request and returns the parsed `result` of the response.
+    async fn send_request<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        request: RequestBuilder,
+    ) -> Result<R, TelegramApiError> {
+        let response = request.send().await.map_err(|e| {
+            eprintln!("HTTP error calling Telegram {}: {}", method, e);
+            TelegramApiError::Http(e.to_string())
+        })?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+
+        let response: TelegramResponse<Value> = match serde_json::from_str(&body) {
+            Ok(response) => response,
+            Err(_) if !status.is_success() => {
+                return Err(TelegramApiError::Api {
+                    error_code: i64::from(status.as_u16()),
+                    description: body,
+                    retry_after: None,
+                    migrate_to_chat_id: None,
+                })
+            }
+            Err(e) => return Err(TelegramApiError::InvalidResponse(e.to_string())),
+        };
+
+        if status.is_success() && response.ok {
+            let result = response.result.unwrap_or_default();
+            serde_json::from_value(result)
+                .map_err(|e| TelegramApiError::InvalidResponse(format!("{} result: {}", method, e)))
+        } else {
+            let parameters = response.parameters.unwrap_or_default();
+            Err(TelegramApiError::Api {
+                error_code: response
+                    .error_code
+                    .unwrap_or_else(|| i64::from(status.as_u16())),
+                description: response.description.unwrap_or_default(),
+                retry_after: parameters.retry_after,
+                migrate_to_chat_id: parameters.migrate_to_chat_id,
+            })
+        }
     }
 }
 
 #[async_trait]
 impl TelegramApi for RealTelegramApi {
-    /// Sends a message to a Telegram chat using the Telegram Bot API.
+    /// Sends a message using the Telegram Bot API `sendMessage` method.
     ///
     /// # Arguments
     ///
-    /// * `chat_id` - Telegram chat ID to send the message to.
-    /// * `text` - Message text to send.
+    /// * `request` - The message and its options (formatting, reply, inline keyboard, ...).
     ///
     /// # Returns
     ///
-    /// `Ok(())` on success, or `Err(String)` with an error message on failure.
-    async fn send_telegram_message(&self, chat_id: i64, text: String) -> Result<(), String> {
-        let url = format!("{}/bot{}/sendMessage", self.base_url, self.token);
-        let message = SendMessageRequest { chat_id, text };
+    /// The sent message on success, or a [`TelegramApiError`] on failure.
+    async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.call_method("sendMessage", request).await
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&message)
-            .send()
+    /// Edits the text of a message using the Telegram Bot API `editMessageText` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The message to edit, its new text and options.
+    ///
+    /// # Returns
+    ///
+    /// The edited message on success, or a [`TelegramApiError`] on failure.
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.call_method("editMessageText", request).await
+    }
+
+    /// Deletes a message using the Telegram Bot API `deleteMessage` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - Telegram chat ID the message belongs to.
+    /// * `message_id` - ID of the message to delete.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` on success, or a [`TelegramApiError`] on failure.
+    async fn delete_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        let request = DeleteMessageRequest {
+            chat_id,
+            message_id,
+        };
+        self.call_method("deleteMessage", &request).await
+    }
+
+    /// Sends a file using the Telegram Bot API `sendDocument` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - Telegram chat ID to send the file to.
+    /// * `document` - The file: an uploaded one, a `file_id` or a URL.
+    /// * `options` - Caption and reply options.
+    ///
+    /// # Returns
+    ///
+    /// The sent message on success, or a [`TelegramApiError`] on failure.
+    async fn send_document_file(
+        &self,
+        chat_id: i64,
+        document: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.send_media("sendDocument", "document", chat_id, document, options)
             .await
-            .map_err(|e| {
-                eprintln!("HTTP error sending Telegram message: {}", e);
-                format!("HTTP error: {}", e)
-            })?;
+    }
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            let status = response.status();
+    /// Shows a chat action using the Telegram Bot API `sendChatAction` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - Telegram chat ID to show the action in.
+    /// * `message_thread_id` - The forum topic to show the action in, if any.
+    /// * `action` - The activity to show.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` on success, or a [`TelegramApiError`] on failure.
+    async fn send_chat_action(
+        &self,
+        chat_id: i64,
+        message_thread_id: Option<i64>,
+        action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        let request = SendChatActionRequest {
+            chat_id,
+            message_thread_id,
+            action,
+        };
+        self.call_method("sendChatAction", &request).await
+    }
+
+    /// Receives incoming updates using the Telegram Bot API `getUpdates` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The first `update_id` to return.
+    /// * `timeout_secs` - Long polling timeout in seconds.
+    ///
+    /// # Returns
+    ///
+    /// The received updates on success, or a [`TelegramApiError`] on failure.
+    async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        let request = GetUpdatesRequest {
+            offset,
+            timeout: timeout_secs,
+        };
+        let timeout = Duration::from_secs(timeout_secs) + GET_UPDATES_TIMEOUT_MARGIN;
+
+        let updates: Vec<Value> = self
+            .execute("getUpdates", None, |_| {
+                self.client
+                    .post(self.method_url("getUpdates"))
+                    .json(&request)
+                    .timeout(timeout)
+            })
+            .await?;
+
+        Ok(updates.into_iter().filter_map(parse_update).collect())
+    }
+
+    async fn get_chat_member(
+        &self,
+        chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        let request = GetChatMemberRequest { chat_id, user_id };
+        self.call_method("getChatMember", &request).await
+    }
+
+    async fn answer_callback_query(
+        &self,
+        request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        self.call_method("answerCallbackQuery", request).await
+    }
+
+    /// Sends a photo using the Telegram Bot API `sendPhoto` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - Telegram chat ID to send the photo to.
+    /// * `photo` - The photo: an uploaded file, a `file_id` or a URL.
+    /// * `options` - Caption and reply options.
+    ///
+    /// # Returns
+    ///
+    /// The sent message on success, or a [`TelegramApiError`] on failure.
+    async fn send_photo(
+        &self,
+        chat_id: i64,
+        photo: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.send_media("sendPhoto", "photo", chat_id, photo, options)
+            .await
+    }
+
+    /// Sends a voice note using the Telegram Bot API `sendVoice` method.
+    ///
+    /// The audio must be OGG encoded with OPUS (or MP3 / M4A) to show as a voice note.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - Telegram chat ID to send the voice note to.
+    /// * `voice` - The audio: an uploaded file, a `file_id` or a URL.
+    /// * `options` - Caption and reply options.
+    ///
+    /// # Returns
+    ///
+    /// The sent message on success, or a [`TelegramApiError`] on failure.
+    async fn send_voice(
+        &self,
+        chat_id: i64,
+        voice: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.send_media("sendVoice", "voice", chat_id, voice, options)
+            .await
+    }
+
+    /// Prepares a file for downloading using the Telegram Bot API `getFile` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Identifier of the file, e.g. of a received voice note.
+    ///
+    /// # Returns
+    ///
+    /// The file with its download path on success, or a [`TelegramApiError`] on failure.
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        let request = GetFileRequest {
+            file_id: file_id.to_string(),
+        };
+        self.call_method("getFile", &request).await
+    }
+
+    /// Downloads the content of a file returned by [`TelegramApi::get_file`].
+    ///
+    /// # Returns
+    ///
+    /// The file content on success, or a [`TelegramApiError`] if the file has no download path
+    /// (files over 20 MB) or the download failed.
+    async fn download_file(&self, file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        let file_path = file.file_path.as_deref().ok_or_else(|| {
+            TelegramApiError::InvalidResponse(format!("File {} cannot be downloaded", file.file_id))
+        })?;
+        let url = format!("{}/file/bot{}/{}", self.base_url, self.token, file_path);
+
+        let response = self.client.get(url).send().await.map_err(|e| {
+            eprintln!("HTTP error downloading Telegram file: {}", e);
+            TelegramApiError::Http(e.to_string())
+        })?;
+        let status = response.status();
+        if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
-            Err(format!("Telegram API error {}: {}", status, body))
+            return Err(TelegramApiError::Api {
+                error_code: i64::from(status.as_u16()),
+                description: body,
+                retry_after: None,
+                migrate_to_chat_id: None,
+            });
+        }
+
+        let content = response
+            .bytes()
+            .await
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        Ok(content.to_vec())
+    }
+
+    /// Answers an inline query using the Telegram Bot API `answerInlineQuery` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The query to answer and its results.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` on success, or a [`TelegramApiError`] on failure.
+    async fn answer_inline_query(
+        &self,
+        request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        self.call_method("answerInlineQuery", request).await
+    }
+
+    /// Returns the bot's own user using the Telegram Bot API `getMe` method.
+    ///
+    /// Useful to check that the bot token is valid.
+    ///
+    /// # Returns
+    ///
+    /// The bot user on success, or a [`TelegramApiError`] on failure.
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        self.call_method("getMe", &serde_json::json!({})).await
+    }
+
+    /// Publishes the bot's command list using the Telegram Bot API `setMyCommands` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The commands shown in the Telegram command menu.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` on success, or a [`TelegramApiError`] on failure.
+    async fn set_my_commands(&self, commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        let request = SetMyCommandsRequest {
+            commands: commands.to_vec(),
+        };
+        self.call_method("setMyCommands", &request).await
+    }
+
+    /// Sends a Markdown message, converted to Telegram HTML, see [`send_markdown_as_html`].
+    async fn send_markdown_message(
+        &self,
+        chat_id: i64,
+        markdown: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        send_markdown_as_html(self, chat_id, markdown).await
+    }
+
+    /// Edits a message with Markdown, converted to Telegram HTML, see [`edit_markdown_as_html`].
+    async fn edit_markdown_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        markdown: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        edit_markdown_as_html(self, chat_id, message_id, markdown).await
+    }
+}
+
+/// Sends a Markdown message through an API, converted to Telegram HTML.
+///
+/// If Telegram cannot parse the converted text, the Markdown is sent again as plain text.
+/// Both requests go through `api`, so a scheduler wrapping the API paces each of them.
+///
+/// # Arguments
+///
+/// * `api` - The API sending the message.
+/// * `chat_id` - Telegram chat ID to send the message to.
+/// * `markdown` - Message text in CommonMark.
+///
+/// # Returns
+///
+/// The sent message on success, or a [`TelegramApiError`] on failure.
+pub async fn send_markdown_as_html<A: TelegramApi + ?Sized>(
+    api: &A,
+    chat_id: i64,
+    markdown: String,
+) -> Result<TelegramMessage, TelegramApiError> {
+    let request = SendMessageRequest {
+        chat_id,
+        text: markdown_to_telegram_html(&markdown),
+        parse_mode: Some(ParseMode::Html),
+        ..Default::default()
+    };
+
+    match api.send_message(&request).await {
+        Err(e) if e.is_parse_entities_error() => {
+            eprintln!(
+                "Telegram rejected the formatting, sending plain text: {}",
+                e
+            );
+            api.send_telegram_message(chat_id, markdown).await
+        }
+        result => result,
+    }
+}
+
+/// Edits a message through an API with Markdown, converted to Telegram HTML.
+///
+/// If Telegram cannot parse the converted text, the message is edited again with
+/// the Markdown as plain text. Both requests go through `api`.
+///
+/// # Arguments
+///
+/// * `api` - The API editing the message.
+/// * `chat_id` - Telegram chat ID the message belongs to.
+/// * `message_id` - ID of the message to edit.
+/// * `markdown` - New message text in CommonMark.
+///
+/// # Returns
+///
+/// The edited message on success, or a [`TelegramApiError`] on failure.
+pub async fn edit_markdown_as_html<A: TelegramApi + ?Sized>(
+    api: &A,
+    chat_id: i64,
+    message_id: i64,
+    markdown: String,
+) -> Result<TelegramMessage, TelegramApiError> {
+    let request = EditMessageTextRequest {
+        chat_id,
+        message_id,
+        text: markdown_to_telegram_html(&markdown),
+        parse_mode: Some(ParseMode::Html),
+        ..Default::default()
+    };
+
+    match api.edit_message_text(&request).await {
+        Err(e) if e.is_parse_entities_error() => {
+            eprintln!(
+                "Telegram rejected the formatting, sending plain text: {}",
+                e
+            );
+            api.edit_telegram_message(chat_id, message_id, markdown)
+                .await
+        }
+        result => result,
+    }
+}
+
+/// Parses one update of a `getUpdates` batch.
+///
+/// An update the model cannot parse is logged and replaced by an empty update with the same
+/// `update_id`, so one malformed update does not stop long polling. Updates without an
+/// `update_id` are dropped.
+fn parse_update(update: Value) -> Option<TelegramUpdate> {
+    let update_id = update.get("update_id").and_then(Value::as_i64);
+
+    match serde_json::from_value(update) {
+        Ok(update) => Some(update),
+        Err(e) => {
+            eprintln!(
+                "Skipping Telegram update {:?} that could not be parsed: {}",
+                update_id, e
+            );
+            update_id.map(|update_id| TelegramUpdate {
+                update_id,
+                ..Default::default()
+            })
         }
     }
 }
@@ -0,0 +1,145 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+/// Converts the CommonMark a model writes into the HTML subset Telegram understands.
+///
+/// Telegram only supports a few inline tags (`<b>`, `<i>`, `<s>`, `<code>`, `<pre>`, `<a>`,
+/// `<blockquote>`), so headings become bold lines, list items get `•` or `1.` markers and rules
+/// become a line of dashes. Everything else — including raw HTML from the model — is escaped
+/// and shown as text. The result is meant to be sent with `parse_mode` set to `HTML`.
+///
+/// Incomplete Markdown, as seen while a reply is still being generated, is converted as well:
+/// an unclosed code block runs to the end of the text and unmatched markers stay as they are.
+pub fn markdown_to_telegram_html(markdown: &str) -> String {
+    let mut html = String::with_capacity(markdown.len());
+    // Item counters of the open lists; `None` for bullet lists.
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Paragraph => {}
+                Tag::Heading { .. } => html.push_str("<b>"),
+                Tag::BlockQuote(_) => html.push_str("<blockquote>"),
+                Tag::CodeBlock(kind) => {
+                    in_code_block = true;
+                    match kind {
+                        CodeBlockKind::Fenced(language) if !language.trim().is_empty() => {
+                            let language = language.split_whitespace().next().unwrap_or_default();
+                            html.push_str(&format!(
+                                "<pre><code class=\"language-{}\">",
+                                escape_html(language)
+                            ));
+                        }
+                        _ => html.push_str("<pre><code>"),
+                    }
+                }
+                Tag::List(start) => {
+                    if !lists.is_empty() && !html.ends_with('\n') {
+                        html.push('\n');
+                    }
+                    lists.push(start);
+                }
+                Tag::Item => {
+                    let depth = lists.len().saturating_sub(1);
+                    html.push_str(&"  ".repeat(depth));
+                    match lists.last_mut() {
+                        Some(Some(number)) => {
+                            html.push_str(&format!("{}. ", number));
+                            *number += 1;
+                        }
+                        _ => html.push_str("• "),
+                    }
+                }
+                Tag::Emphasis => html.push_str("<i>"),
+                Tag::Strong => html.push_str("<b>"),
+                Tag::Strikethrough => html.push_str("<s>"),
+                Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                    html.push_str(&format!("<a href=\"{}\">", escape_html(&dest_url)));
+                }
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Paragraph => end_block(&mut html, &lists),
+                TagEnd::Heading(_) => {
+                    html.push_str("</b>");
+                    end_block(&mut html, &lists);
+                }
+                TagEnd::BlockQuote(_) => {
+                    trim_trailing_newlines(&mut html);
+                    html.push_str("</blockquote>");
+                    end_block(&mut html, &lists);
+                }
+                TagEnd::CodeBlock => {
+                    in_code_block = false;
+                    trim_trailing_newlines(&mut html);
+                    html.push_str("</code></pre>");
+                    end_block(&mut html, &lists);
+                }
+                TagEnd::List(_) => {
+                    lists.pop();
+                    end_block(&mut html, &lists);
+                }
+                TagEnd::Item => {
+                    trim_trailing_newlines(&mut html);
+                    html.push('\n');
+                }
+                TagEnd::Emphasis => html.push_str("</i>"),
+                TagEnd::Strong => html.push_str("</b>"),
+                TagEnd::Strikethrough => html.push_str("</s>"),
+                TagEnd::Link | TagEnd::Image => html.push_str("</a>"),
+                _ => {}
+            },
+            Event::Text(text) => html.push_str(&escape_html(&text)),
+            Event::Code(code) => {
+                html.push_str("<code>");
+                html.push_str(&escape_html(&code));
+                html.push_str("</code>");
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => html.push_str(&escape_html(&raw)),
+            Event::SoftBreak | Event::HardBreak => html.push('\n'),
+            Event::Rule => {
+                html.push_str("———");
+                end_block(&mut html, &lists);
+            }
+            Event::TaskListMarker(done) => html.push_str(if done { "☑ " } else { "☐ " }),
+            _ => {}
+        }
+    }
+
+    if in_code_block {
+        trim_trailing_newlines(&mut html);
+        html.push_str("</code></pre>");
+    }
+
+    html.trim_end().to_string()
+}
+
+/// Escapes the characters Telegram's HTML parser treats specially.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Separates a finished block from the next one: a blank line at the top level,
+/// a single line break inside list items.
+fn end_block(html: &mut String, lists: &[Option<u64>]) {
+    trim_trailing_newlines(html);
+    html.push_str(if lists.is_empty() { "\n\n" } else { "\n" });
+}
+
+/// Removes line breaks at the end of the output.
+fn trim_trailing_newlines(html: &mut String) {
+    while html.ends_with('\n') {
+        html.pop();
+    }
+}
@@ -0,0 +1,179 @@
+use std::env;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::services::telegram_api::{TelegramApi, TelegramApiError};
+use crate::services::update_processor::UpdateProcessor;
+
+/// Default long polling timeout when `TELEGRAM_POLLING_TIMEOUT_SECS` is not set.
+pub const DEFAULT_POLLING_TIMEOUT_SECS: u64 = 30;
+
+/// Longest long polling timeout accepted in `TELEGRAM_POLLING_TIMEOUT_SECS`.
+pub const MAX_POLLING_TIMEOUT_SECS: u64 = 50;
+
+/// How long the poller waits before retrying after a failed `getUpdates` call.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How the bot receives updates from Telegram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMode {
+    /// Telegram posts updates to `POST /telegram/webhook`; needs a public HTTPS URL.
+    Webhook,
+    /// The bot fetches updates with `getUpdates`; works without a public URL.
+    Polling,
+}
+
+impl UpdateMode {
+    /// Reads the update mode from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `TELEGRAM_UPDATE_MODE`: (optional) `webhook` (default) or `polling`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable holds any other value.
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match env::var("TELEGRAM_UPDATE_MODE") {
+            Ok(value) => match value.trim() {
+                "" | "webhook" => Ok(UpdateMode::Webhook),
+                "polling" => Ok(UpdateMode::Polling),
+                other => Err(format!(
+                    "Environment variable TELEGRAM_UPDATE_MODE must be `webhook` or `polling`, got `{}`",
+                    other
+                )
+                .into()),
+            },
+            Err(_) => Ok(UpdateMode::Webhook),
+        }
+    }
+}
+
+/// `TelegramPoller` receives updates with `getUpdates` long polling, as an alternative
+/// to the webhook for bots running without a public URL (e.g. behind NAT).
+///
+/// Every update is handed to the same [`UpdateProcessor`] the webhook uses with
+/// [`UpdateProcessor::spawn_update`]: a slow reply does not hold back other chats,
+/// while the updates of one chat are processed in order.
+pub struct TelegramPoller {
+    telegram_api: Arc<dyn TelegramApi>,
+    processor: Arc<UpdateProcessor>,
+    timeout_secs: u64,
+}
+
+impl TelegramPoller {
+    /// Creates a new poller.
+    ///
+    /// # Arguments
+    ///
+    /// * `telegram_api` - The API used to call `getUpdates`.
+    /// * `processor` - The pipeline every received update is handed to.
+    /// * `timeout_secs` - Long polling timeout passed to `getUpdates`.
+    pub fn new(
+        telegram_api: Arc<dyn TelegramApi>,
+        processor: Arc<UpdateProcessor>,
+        timeout_secs: u64,
+    ) -> Self {
+        Self {
+            telegram_api,
+            processor,
+            timeout_secs,
+        }
+    }
+
+    /// Creates a new poller using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `TELEGRAM_POLLING_TIMEOUT_SECS`: (optional) long polling timeout in seconds, from 1 to
+    ///   [`MAX_POLLING_TIMEOUT_SECS`], defaults to [`DEFAULT_POLLING_TIMEOUT_SECS`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable is set but is not a valid timeout
+    /// (see [`parse_polling_timeout`]).
+    pub fn new_from_env(
+        telegram_api: Arc<dyn TelegramApi>,
+        processor: Arc<UpdateProcessor>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let timeout_secs = match env::var("TELEGRAM_POLLING_TIMEOUT_SECS") {
+            Ok(value) if !value.trim().is_empty() => parse_polling_timeout(&value)?,
+            _ => DEFAULT_POLLING_TIMEOUT_SECS,
+        };
+
+        Ok(Self::new(telegram_api, processor, timeout_secs))
+    }
+
+    /// Fetches one batch of updates and hands each of them to the processor.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The first `update_id` to fetch (the previous batch's last ID plus one).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Option<i64>)` — The offset for the next call; unchanged if there were no updates.
+    /// * `Err(TelegramApiError)` — If `getUpdates` failed.
+    pub async fn poll_once(&self, offset: Option<i64>) -> Result<Option<i64>, TelegramApiError> {
+        let updates = self
+            .telegram_api
+            .get_updates(offset, self.timeout_secs)
+            .await?;
+
+        let mut next_offset = offset;
+        for update in updates {
+            next_offset = Some(next_offset.unwrap_or(0).max(update.update_id + 1));
+            self.processor.spawn_update(update);
+        }
+
+        Ok(next_offset)
+    }
+
+    /// Polls for updates until Telegram refuses long polling.
+    ///
+    /// Failed `getUpdates` calls are logged and retried after a short delay. Telegram answers
+    /// `409 Conflict` while a webhook is registered or another instance of the bot is polling;
+    /// retrying cannot fix that, so the conflict is returned instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`TelegramApiError`] of a `getUpdates` call refused with `409 Conflict`.
+    pub async fn run(&self) -> Result<(), TelegramApiError> {
+        println!("📡 Polling Telegram for updates");
+
+        let mut offset = None;
+        loop {
+            match self.poll_once(offset).await {
+                Ok(next_offset) => offset = next_offset,
+                Err(e) if e.is_conflict() => return Err(e),
+                Err(e) => {
+                    eprintln!("Error polling Telegram updates: {}", e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+}
+
+/// Parses a `TELEGRAM_POLLING_TIMEOUT_SECS` value.
+///
+/// A timeout of 0 would turn long polling into a tight loop of short polls, so it is rejected
+/// along with timeouts above [`MAX_POLLING_TIMEOUT_SECS`].
+///
+/// # Errors
+///
+/// Returns an error if the value is not a number from 1 to [`MAX_POLLING_TIMEOUT_SECS`].
+pub fn parse_polling_timeout(value: &str) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    match value.trim().parse() {
+        Ok(timeout_secs) if (1..=MAX_POLLING_TIMEOUT_SECS).contains(&timeout_secs) => {
+            Ok(timeout_secs)
+        }
+        _ => Err(format!(
+            "Environment variable TELEGRAM_POLLING_TIMEOUT_SECS must be a number from 1 to {}, got `{}`",
+            MAX_POLLING_TIMEOUT_SECS,
+            value.trim()
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,427 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    EditMessageTextRequest, InputFile, SendMediaOptions, SendMessageRequest, TelegramFile,
+    TelegramMessage, TelegramUpdate, TelegramUser,
+};
+use crate::services::telegram_api::{TelegramApi, TelegramApiError};
+use crate::services::telegram_api_impl::{edit_markdown_as_html, send_markdown_as_html};
+
+/// Default number of messages per second the bot may send across all chats.
+pub const DEFAULT_GLOBAL_RATE: f64 = 30.0;
+
+/// Default number of messages per second the bot may send to one chat.
+pub const DEFAULT_CHAT_RATE: f64 = 1.0;
+
+/// Default number of messages per minute the bot may send to one group.
+pub const DEFAULT_GROUP_RATE_PER_MINUTE: f64 = 20.0;
+
+/// The rates Telegram allows bots to send messages at.
+///
+/// # Fields
+///
+/// * `global_per_second` – Messages per second across all chats.
+/// * `chat_per_second` – Messages per second to one chat.
+/// * `group_per_minute` – Messages per minute to one group, supergroup or channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    pub global_per_second: f64,
+    pub chat_per_second: f64,
+    pub group_per_minute: f64,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            global_per_second: DEFAULT_GLOBAL_RATE,
+            chat_per_second: DEFAULT_CHAT_RATE,
+            group_per_minute: DEFAULT_GROUP_RATE_PER_MINUTE,
+        }
+    }
+}
+
+impl RateLimits {
+    /// Reads the rate limits from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `TELEGRAM_GLOBAL_RATE_LIMIT`: (optional) messages per second across all chats,
+    ///   defaults to [`DEFAULT_GLOBAL_RATE`]
+    /// - `TELEGRAM_CHAT_RATE_LIMIT`: (optional) messages per second to one chat,
+    ///   defaults to [`DEFAULT_CHAT_RATE`]
+    /// - `TELEGRAM_GROUP_RATE_LIMIT`: (optional) messages per minute to one group,
+    ///   defaults to [`DEFAULT_GROUP_RATE_PER_MINUTE`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is set but is not a positive number.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            global_per_second: rate_from_env("TELEGRAM_GLOBAL_RATE_LIMIT", DEFAULT_GLOBAL_RATE)?,
+            chat_per_second: rate_from_env("TELEGRAM_CHAT_RATE_LIMIT", DEFAULT_CHAT_RATE)?,
+            group_per_minute: rate_from_env(
+                "TELEGRAM_GROUP_RATE_LIMIT",
+                DEFAULT_GROUP_RATE_PER_MINUTE,
+            )?,
+        })
+    }
+}
+
+/// Reads a rate from an environment variable, falling back to `default` when it is unset or empty.
+fn rate_from_env(name: &str, default: f64) -> Result<f64, Box<dyn Error + Send + Sync>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => match value.trim().parse::<f64>() {
+            Ok(rate) if rate > 0.0 => Ok(rate),
+            _ => Err(format!("Environment variable {} must be a positive number", name).into()),
+        },
+        _ => Ok(default),
+    }
+}
+
+/// A token bucket refilled continuously at a fixed rate.
+#[derive(Debug)]
+struct TokenBucket {
+    per_second: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    fn new(per_second: f64, capacity: f64) -> Self {
+        Self {
+            per_second,
+            capacity,
+            tokens: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Takes a token and returns how long to wait before it may be used.
+    ///
+    /// Tokens are reserved right away, so callers are served in the order they asked.
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let refilled = now.duration_since(self.updated).as_secs_f64() * self.per_second;
+        self.tokens = (self.tokens + refilled).min(self.capacity) - 1.0;
+        self.updated = now;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.per_second)
+        }
+    }
+
+    /// Returns whether the bucket has refilled completely, i.e. it remembers no recent use.
+    fn is_full(&self) -> bool {
+        let refilled = self.updated.elapsed().as_secs_f64() * self.per_second;
+        self.tokens + refilled >= self.capacity
+    }
+}
+
+/// The outgoing messages of one chat.
+///
+/// The bucket's lock is held from the moment a message gets its turn until it was sent,
+/// so messages reach the chat in the order they were queued (Tokio's mutex is fair).
+struct ChatQueue {
+    bucket: tokio::sync::Mutex<TokenBucket>,
+    depth: AtomicUsize,
+}
+
+impl ChatQueue {
+    /// Returns whether nothing is queued and the bucket has refilled, so the queue can be
+    /// dropped and created again later without changing the pacing.
+    fn is_idle(&self) -> bool {
+        self.depth.load(Ordering::SeqCst) == 0
+            && self.bucket.try_lock().is_ok_and(|bucket| bucket.is_full())
+    }
+}
+
+/// A message in a chat's queue, counted in the queue's depth until it was sent
+/// or its sending was cancelled.
+struct Queued(Arc<ChatQueue>);
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.0.depth.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// `RateLimitedTelegramApi` wraps a [`TelegramApi`] and paces the messages sent through it.
+///
+/// Telegram throttles bots sending more than about 30 messages per second overall, one message
+/// per second to a chat or 20 messages per minute to a group, answering `429 Too Many Requests`.
+/// This scheduler enforces these limits with a global token bucket and one bucket per chat:
+/// messages wait in a per-chat queue and are sent in order once both buckets allow it.
+///
+/// Sending, replying, editing, documents, photos and voice notes are paced per chat; requests
+/// not sent to a chat (query answers, files, `getMe`, `setMyCommands`) only take a global token.
+/// Chat actions, deletions, `getUpdates` and `getChatMember` are passed through right away.
+/// Chats with a negative ID (groups, supergroups and channels) use the group rate. The queues
+/// of idle chats are dropped when a new chat is queued.
+pub struct RateLimitedTelegramApi {
+    inner: Arc<dyn TelegramApi>,
+    limits: RateLimits,
+    global: Mutex<TokenBucket>,
+    chats: Mutex<HashMap<i64, Arc<ChatQueue>>>,
+}
+
+impl RateLimitedTelegramApi {
+    /// Creates a new scheduler around an API.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The API that sends the messages.
+    /// * `limits` - The rates to send messages at.
+    pub fn new(inner: Arc<dyn TelegramApi>, limits: RateLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            global: Mutex::new(TokenBucket::new(
+                limits.global_per_second,
+                limits.global_per_second.max(1.0),
+            )),
+            chats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of messages queued or being sent for a chat.
+    pub fn queue_depth(&self, chat_id: i64) -> usize {
+        self.chats
+            .lock()
+            .unwrap()
+            .get(&chat_id)
+            .map_or(0, |queue| queue.depth.load(Ordering::SeqCst))
+    }
+
+    /// Returns the number of messages queued or being sent across all chats.
+    pub fn total_queue_depth(&self) -> usize {
+        self.chats
+            .lock()
+            .unwrap()
+            .values()
+            .map(|queue| queue.depth.load(Ordering::SeqCst))
+            .sum()
+    }
+
+    /// Returns the number of chats with a queue, i.e. chats messaged recently.
+    pub fn queued_chats(&self) -> usize {
+        self.chats.lock().unwrap().len()
+    }
+
+    /// Adds a message to the queue of a chat, creating the queue on first use.
+    ///
+    /// Creating a queue first drops the queues of idle chats, so the map does not grow
+    /// with every chat the bot ever talked to.
+    fn enqueue(&self, chat_id: i64) -> Queued {
+        let per_second = if chat_id < 0 {
+            self.limits
+                .chat_per_second
+                .min(self.limits.group_per_minute / 60.0)
+        } else {
+            self.limits.chat_per_second
+        };
+
+        let mut chats = self.chats.lock().unwrap();
+        if !chats.contains_key(&chat_id) {
+            chats.retain(|_, queue| !queue.is_idle());
+        }
+        let queue = chats
+            .entry(chat_id)
+            .or_insert_with(|| {
+                Arc::new(ChatQueue {
+                    bucket: tokio::sync::Mutex::new(TokenBucket::new(per_second, 1.0)),
+                    depth: AtomicUsize::new(0),
+                })
+            })
+            .clone();
+        // Counted while the map is locked, so the queue cannot be dropped as idle meanwhile
+        queue.depth.fetch_add(1, Ordering::SeqCst);
+
+        Queued(queue)
+    }
+
+    /// Queues a call to the inner API for a chat and runs it once the rate limits allow.
+    async fn schedule<T, F, Fut>(&self, chat_id: i64, send: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let queued = self.enqueue(chat_id);
+
+        let mut bucket = queued.0.bucket.lock().await;
+        wait(bucket.reserve()).await;
+        let global_wait = self.global.lock().unwrap().reserve();
+        wait(global_wait).await;
+
+        let result = send().await;
+        drop(bucket);
+        result
+    }
+
+    /// Runs a call to the inner API that is not sent to a chat once the global rate allows.
+    async fn throttle<T, F, Fut>(&self, send: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let global_wait = self.global.lock().unwrap().reserve();
+        wait(global_wait).await;
+
+        send().await
+    }
+}
+
+/// Sleeps for a duration, returning right away when it is zero.
+async fn wait(duration: Duration) {
+    if !duration.is_zero() {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+#[async_trait]
+impl TelegramApi for RateLimitedTelegramApi {
+    async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.schedule(request.chat_id, || self.inner.send_message(request))
+            .await
+    }
+
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.schedule(request.chat_id, || self.inner.edit_message_text(request))
+            .await
+    }
+
+    async fn delete_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        self.inner.delete_message(chat_id, message_id).await
+    }
+
+    async fn send_document_file(
+        &self,
+        chat_id: i64,
+        document: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.schedule(chat_id, || {
+            self.inner.send_document_file(chat_id, document, options)
+        })
+        .await
+    }
+
+    async fn send_chat_action(
+        &self,
+        chat_id: i64,
+        message_thread_id: Option<i64>,
+        action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        self.inner
+            .send_chat_action(chat_id, message_thread_id, action)
+            .await
+    }
+
+    async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        self.inner.get_updates(offset, timeout_secs).await
+    }
+
+    async fn get_chat_member(
+        &self,
+        chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        self.inner.get_chat_member(chat_id, user_id).await
+    }
+
+    async fn answer_callback_query(
+        &self,
+        request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        self.throttle(|| self.inner.answer_callback_query(request))
+            .await
+    }
+
+    async fn answer_inline_query(
+        &self,
+        request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        self.throttle(|| self.inner.answer_inline_query(request))
+            .await
+    }
+
+    async fn send_photo(
+        &self,
+        chat_id: i64,
+        photo: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.schedule(chat_id, || self.inner.send_photo(chat_id, photo, options))
+            .await
+    }
+
+    async fn send_voice(
+        &self,
+        chat_id: i64,
+        voice: InputFile,
+        options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.schedule(chat_id, || self.inner.send_voice(chat_id, voice, options))
+            .await
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        self.throttle(|| self.inner.get_file(file_id)).await
+    }
+
+    async fn download_file(&self, file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        self.throttle(|| self.inner.download_file(file)).await
+    }
+
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        self.throttle(|| self.inner.get_me()).await
+    }
+
+    async fn set_my_commands(&self, commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        self.throttle(|| self.inner.set_my_commands(commands)).await
+    }
+
+    // Converted here rather than by the inner API, so the plain-text fallback takes its own token
+
+    async fn send_markdown_message(
+        &self,
+        chat_id: i64,
+        markdown: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        send_markdown_as_html(self, chat_id, markdown).await
+    }
+
+    async fn edit_markdown_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        markdown: String,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        edit_markdown_as_html(self, chat_id, message_id, markdown).await
+    }
+}
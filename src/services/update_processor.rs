@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::handlers::commands::{parse_command, CommandRouter};
+use crate::models::chat::ChatMessage;
+use crate::models::storage::{StoredChat, StoredUser};
+use crate::models::telegram::{
+    AnswerCallbackQueryRequest, CallbackQuery, ChatAction, ChatMemberUpdated, InlineQuery,
+    TelegramMessage, TelegramUpdate,
+};
+use crate::services::chat_api::ChatApi;
+use crate::services::group_chat::addressed_prompt;
+use crate::services::persona_registry::PersonaRegistry;
+use crate::services::reply_streamer::ReplyStreamer;
+use crate::services::telegram_api::TelegramApi;
+use crate::storage::storage_api::Storage;
+
+/// Default time between two `typing` chat actions while a reply is being generated.
+///
+/// Telegram shows a chat action for about 5 seconds, so it is repeated a bit more often.
+pub const DEFAULT_TYPING_INTERVAL: Duration = Duration::from_secs(4);
+
+/// `UpdateProcessor` is the update-processing pipeline shared by every way the bot
+/// receives Telegram updates (the webhook and long polling).
+///
+/// For a text message it records the chat and its sender, dispatches bot commands to the [`CommandRouter`],
+/// or sends the message together with the chat's persona and recent history to the AI chat API
+/// and streams the reply back via the Telegram Bot API. The chat shows "typing…" until the
+/// reply is delivered. In groups only messages addressed to the bot are answered, as replies.
+pub struct UpdateProcessor {
+    chat_api: Arc<dyn ChatApi>,
+    telegram_api: Arc<dyn TelegramApi>,
+    storage: Arc<dyn Storage>,
+    personas: Arc<PersonaRegistry>,
+    reply_streamer: ReplyStreamer,
+    commands: CommandRouter,
+    typing_interval: Duration,
+    bot_username: Option<String>,
+    chat_tasks: Mutex<HashMap<i64, JoinHandle<()>>>,
+}
+
+impl UpdateProcessor {
+    /// Creates a new processor answering the built-in bot commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_api` - An implementation of the `ChatApi` trait used to get the AI-generated response.
+    /// * `telegram_api` - An implementation of the `TelegramApi` trait used to reply to the chat.
+    /// * `storage` - An implementation of the `Storage` trait holding chats and their recent turns.
+    /// * `personas` - The registry of personas the chats can pick from.
+    /// * `reply_streamer` - Delivers the streamed reply by editing a placeholder message.
+    pub fn new(
+        chat_api: Arc<dyn ChatApi>,
+        telegram_api: Arc<dyn TelegramApi>,
+        storage: Arc<dyn Storage>,
+        personas: Arc<PersonaRegistry>,
+        reply_streamer: ReplyStreamer,
+    ) -> Self {
+        Self {
+            chat_api,
+            telegram_api,
+            storage,
+            personas,
+            reply_streamer,
+            commands: CommandRouter::default(),
+            typing_interval: DEFAULT_TYPING_INTERVAL,
+            bot_username: None,
+            chat_tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the bot commands the processor answers.
+    pub fn with_commands(mut self, commands: CommandRouter) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// Sets the time between two `typing` chat actions, [`DEFAULT_TYPING_INTERVAL`] by default.
+    pub fn with_typing_interval(mut self, typing_interval: Duration) -> Self {
+        self.typing_interval = typing_interval;
+        self
+    }
+
+    /// Sets the bot's username (without the leading `@`), as returned by `getMe`.
+    ///
+    /// In groups the bot answers messages mentioning or replying to it only if it knows
+    /// its username; otherwise it only answers commands there.
+    pub fn with_bot_username(mut self, bot_username: Option<String>) -> Self {
+        self.bot_username = bot_username;
+        self
+    }
+
+    /// Returns the bot commands the processor answers.
+    pub fn commands(&self) -> &CommandRouter {
+        &self.commands
+    }
+
+    /// Processes an update in a background task.
+    ///
+    /// Updates of the same chat are processed one after another, in the order they were
+    /// handed over, so a chat's messages are answered in order; different chats are
+    /// processed concurrently.
+    pub fn spawn_update(self: &Arc<Self>, update: TelegramUpdate) {
+        let processor = self.clone();
+        let Some(chat_id) = update.chat_id() else {
+            tokio::spawn(async move { processor.process_update(update).await });
+            return;
+        };
+
+        let mut chat_tasks = self.chat_tasks.lock().unwrap();
+        chat_tasks.retain(|_, task| !task.is_finished());
+        let previous = chat_tasks.remove(&chat_id);
+        let task = tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            processor.process_update(update).await;
+        });
+        chat_tasks.insert(chat_id, task);
+    }
+
+    /// Processes an update, dispatching it to the handler of its type.
+    ///
+    /// Messages are answered; edited messages, callback queries, inline queries and changes
+    /// of the bot's membership have their own handlers. Other update types are logged and
+    /// ignored. Errors are logged, as there is nobody to report them to.
+    pub async fn process_update(&self, update: TelegramUpdate) {
+        let kind = update.kind();
+        let TelegramUpdate {
+            update_id,
+            message,
+            edited_message,
+            callback_query,
+            inline_query,
+            my_chat_member,
+            ..
+        } = update;
+
+        if let Some(message) = message {
+            self.handle_message(message).await;
+        } else if let Some(message) = edited_message {
+            self.handle_edited_message(message).await;
+        } else if let Some(query) = callback_query {
+            self.handle_callback_query(query).await;
+        } else if let Some(query) = inline_query {
+            self.handle_inline_query(query).await;
+        } else if let Some(update) = my_chat_member {
+            self.handle_my_chat_member(update).await;
+        } else {
+            println!("Ignoring update {} of unsupported type {}", update_id, kind);
+        }
+    }
+
+    /// Replies to a message if it is addressed to the bot, see [`addressed_prompt`].
+    ///
+    /// The service messages announcing a group's upgrade to a supergroup are passed to
+    /// [`UpdateProcessor::handle_chat_migration`].
+    pub async fn handle_message(&self, message: TelegramMessage) {
+        if let Some(to_chat_id) = message.migrate_to_chat_id {
+            self.handle_chat_migration(message.chat.id, to_chat_id)
+                .await;
+            return;
+        }
+        if let Some(from_chat_id) = message.migrate_from_chat_id {
+            self.handle_chat_migration(from_chat_id, message.chat.id)
+                .await;
+            return;
+        }
+
+        let Some(prompt) = addressed_prompt(&message, self.bot_username.as_deref()) else {
+            return;
+        };
+
+        self.record_sender(&message).await;
+        self.handle_text_message(&message, prompt).await;
+    }
+
+    /// Stores the chat a message was sent in and its sender.
+    async fn record_sender(&self, message: &TelegramMessage) {
+        let chat = StoredChat::from_telegram(&message.chat);
+        if let Err(e) = self.storage.upsert_chat(&chat).await {
+            eprintln!("Error storing chat {}: {}", chat.id, e);
+        }
+
+        let Some(user) = message.from.as_ref().map(StoredUser::from_telegram) else {
+            return;
+        };
+        if let Err(e) = self.storage.upsert_user(&user).await {
+            eprintln!("Error storing user {}: {}", user.id, e);
+        }
+    }
+
+    /// Moves the stored history and settings of a group upgraded to a supergroup.
+    ///
+    /// Telegram announces the upgrade with a service message in both chats,
+    /// so this usually runs twice; the second run finds nothing left to move.
+    pub async fn handle_chat_migration(&self, from_chat_id: i64, to_chat_id: i64) {
+        println!("🔀 Chat {} was migrated to {}", from_chat_id, to_chat_id);
+
+        if let Err(e) = self.storage.migrate_chat(from_chat_id, to_chat_id).await {
+            eprintln!(
+                "Failed to migrate chat {} to {}: {}",
+                from_chat_id, to_chat_id, e
+            );
+        }
+    }
+
+    /// Handles an edited message.
+    ///
+    /// The original message was already answered, so edits are not answered again.
+    pub async fn handle_edited_message(&self, message: TelegramMessage) {
+        println!(
+            "Ignoring edit of message {} in chat {}",
+            message.message_id, message.chat.id
+        );
+    }
+
+    /// Handles a press of an inline keyboard button.
+    ///
+    /// The bot does not send inline keyboards yet, so the query is answered without a
+    /// notification, which stops the client's progress indicator on the button.
+    pub async fn handle_callback_query(&self, query: CallbackQuery) {
+        println!(
+            "Answering callback query {} from user {}",
+            query.id, query.from.id
+        );
+
+        let request = AnswerCallbackQueryRequest {
+            callback_query_id: query.id,
+            ..Default::default()
+        };
+        if let Err(e) = self.telegram_api.answer_callback_query(&request).await {
+            eprintln!(
+                "Failed to answer callback query {}: {}",
+                request.callback_query_id, e
+            );
+        }
+    }
+
+    /// Handles an inline query (`@my_bot query` typed in any chat).
+    ///
+    /// Inline mode is not supported yet, so the query is only logged.
+    pub async fn handle_inline_query(&self, query: InlineQuery) {
+        println!(
+            "Ignoring inline query {} from user {}",
+            query.id, query.from.id
+        );
+    }
+
+    /// Handles a change of the bot's own membership in a chat.
+    ///
+    /// When the bot is added to a chat, the chat is recorded; when it is removed or blocked,
+    /// the chat's conversation history is forgotten.
+    pub async fn handle_my_chat_member(&self, update: ChatMemberUpdated) {
+        let chat_id = update.chat.id;
+        let status = &update.new_chat_member.status;
+        println!("🤖 Bot status in chat {} changed to {}", chat_id, status);
+
+        let result = if update.new_chat_member.is_present() {
+            self.storage
+                .upsert_chat(&StoredChat::from_telegram(&update.chat))
+                .await
+        } else {
+            self.storage.clear_messages(chat_id).await
+        };
+        if let Err(e) = result {
+            eprintln!("Error updating chat {}: {}", chat_id, e);
+        }
+    }
+
+    /// Replies to a text message received in a chat.
+    ///
+    /// The chat shows "typing…" from the start until the reply is delivered or generation fails.
+    /// The user's message and the model's reply are appended to the chat's history
+    /// only after the reply was delivered successfully.
+    ///
+    /// In groups the reply answers the message with `reply_to_message_id`, so it is clear
+    /// which message the bot is responding to.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message, for its chat, sender and ID.
+    /// * `text` - The text addressed to the bot, i.e. the prompt or a bot command.
+    pub async fn handle_text_message(&self, message: &TelegramMessage, text: String) {
+        let chat_id = message.chat.id;
+        let reply_to_message_id = message.chat.is_group().then_some(message.message_id);
+
+        if let Some(command) = parse_command(&text) {
+            let reply = self
+                .commands
+                .dispatch(
+                    &command,
+                    message,
+                    self.storage.as_ref(),
+                    &self.personas,
+                    self.telegram_api.as_ref(),
+                )
+                .await;
+            if let Some(reply) = reply {
+                self.send_text(chat_id, reply_to_message_id, reply).await;
+            }
+            return;
+        }
+
+        // Stopped when dropped, i.e. on every way out of this function
+        let _typing = TypingIndicator::start(
+            self.telegram_api.clone(),
+            chat_id,
+            topic_id(message),
+            self.typing_interval,
+        );
+
+        let settings = self
+            .storage
+            .chat_settings(chat_id)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Error loading settings of chat {}: {}", chat_id, e);
+                Default::default()
+            });
+        let persona = self.personas.resolve(settings.persona.as_deref());
+
+        let mut conversation = self
+            .storage
+            .recent_messages(chat_id)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Error loading history of chat {}: {}", chat_id, e);
+                Vec::new()
+            });
+        let user_message = ChatMessage::user(text);
+        conversation.push(user_message.clone());
+
+        let messages = persona.build_messages(conversation);
+        let mut options = persona.chat_options();
+        if settings.model.is_some() {
+            options.model = settings.model;
+        }
+
+        let fragments = match self.chat_api.complete_stream(&messages, &options).await {
+            Ok(fragments) => fragments,
+            Err(e) => {
+                eprintln!("Error calling chat API: {}", e);
+                self.send_text(chat_id, reply_to_message_id, e.user_message().to_string())
+                    .await;
+                return;
+            }
+        };
+
+        match self
+            .reply_streamer
+            .deliver_reply(
+                self.telegram_api.as_ref(),
+                chat_id,
+                reply_to_message_id,
+                fragments,
+            )
+            .await
+        {
+            Ok(reply) => {
+                let reply = ChatMessage::assistant(reply);
+                if let Err(e) = self
+                    .storage
+                    .append_messages(chat_id, &[user_message, reply])
+                    .await
+                {
+                    eprintln!("Error storing history of chat {}: {}", chat_id, e);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error delivering reply to Telegram: {}", e);
+            }
+        }
+    }
+
+    /// Sends a plain text message to a chat, as a reply to `reply_to_message_id` if given.
+    /// Errors are logged.
+    async fn send_text(&self, chat_id: i64, reply_to_message_id: Option<i64>, text: String) {
+        let sent = match reply_to_message_id {
+            Some(message_id) => {
+                self.telegram_api
+                    .send_reply_message(chat_id, message_id, text)
+                    .await
+            }
+            None => self.telegram_api.send_telegram_message(chat_id, text).await,
+        };
+        if let Err(e) = sent {
+            eprintln!("Error sending to Telegram: {}", e);
+        }
+    }
+}
+
+/// Keeps showing the `typing` chat action in a chat until it is dropped.
+struct TypingIndicator {
+    task: JoinHandle<()>,
+}
+
+impl TypingIndicator {
+    /// Sends `typing` right away and repeats it every `interval` in a background task.
+    ///
+    /// In a forum, `message_thread_id` shows the action in the topic instead of "General".
+    fn start(
+        telegram_api: Arc<dyn TelegramApi>,
+        chat_id: i64,
+        message_thread_id: Option<i64>,
+        interval: Duration,
+    ) -> Self {
+        let task = tokio::spawn(async move {
+            loop {
+                if let Err(e) = telegram_api
+                    .send_chat_action(chat_id, message_thread_id, ChatAction::Typing)
+                    .await
+                {
+                    eprintln!("Error sending chat action to chat {}: {}", chat_id, e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+
+        Self { task }
+    }
+}
+
+impl Drop for TypingIndicator {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Returns the forum topic a message was sent to, or `None` outside of forum topics.
+///
+/// Only topic IDs are kept: the thread ID of a plain reply thread is not a valid target.
+fn topic_id(message: &TelegramMessage) -> Option<i64> {
+    message
+        .message_thread_id
+        .filter(|_| message.is_topic_message == Some(true))
+}
@@ -0,0 +1,83 @@
+use rusqlite::Connection;
+use std::error::Error;
+
+/// Schema migrations of the SQLite database, oldest first.
+///
+/// The number of applied migrations is tracked in SQLite's `user_version` pragma,
+/// so a migration must never be edited once released: append a new one instead.
+pub const MIGRATIONS: &[&str] = &[
+    // 1: users, chats, messages and per-chat settings.
+    r#"
+    CREATE TABLE users (
+        id          INTEGER PRIMARY KEY,
+        username    TEXT,
+        first_name  TEXT,
+        last_name   TEXT,
+        created_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE TABLE chats (
+        id          INTEGER PRIMARY KEY,
+        chat_type   TEXT,
+        title       TEXT,
+        created_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        updated_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE TABLE messages (
+        id            INTEGER PRIMARY KEY AUTOINCREMENT,
+        chat_id       INTEGER NOT NULL REFERENCES chats (id) ON DELETE CASCADE,
+        role          TEXT NOT NULL,
+        content       TEXT NOT NULL,
+        name          TEXT,
+        tool_call_id  TEXT,
+        created_at    TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+
+    CREATE INDEX idx_messages_chat_id ON messages (chat_id, id);
+
+    CREATE TABLE chat_settings (
+        chat_id     INTEGER PRIMARY KEY REFERENCES chats (id) ON DELETE CASCADE,
+        persona     TEXT,
+        model       TEXT,
+        updated_at  TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    "#,
+];
+
+/// Applies every migration that has not been applied to the database yet.
+///
+/// Each migration runs in its own transaction together with the `user_version` bump,
+/// so a failing migration leaves the database at the previous version.
+///
+/// # Returns
+///
+/// The schema version after migrating.
+///
+/// # Errors
+///
+/// Returns an error if a migration fails, or if the database was created by a newer
+/// version of the application (its `user_version` is higher than the known migrations).
+pub fn run_migrations(connection: &mut Connection) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let current: usize =
+        connection.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+
+    if current > MIGRATIONS.len() {
+        return Err(format!(
+            "Database schema version {} is newer than the supported version {}",
+            current,
+            MIGRATIONS.len()
+        )
+        .into());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", (index + 1) as i64)?;
+        transaction.commit()?;
+    }
+
+    Ok(MIGRATIONS.len())
+}
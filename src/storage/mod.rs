@@ -0,0 +1,6 @@
+#[cfg(feature = "sqlite")]
+pub mod migrations;
+pub mod storage_api;
+pub mod storage_memory_impl;
+#[cfg(feature = "sqlite")]
+pub mod storage_sqlite_impl;
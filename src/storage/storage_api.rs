@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use std::env;
+use std::error::Error;
+
+use crate::models::chat::ChatMessage;
+use crate::models::storage::{ChatSettings, StoredChat, StoredUser};
+
+/// Default number of messages returned per chat when `CONVERSATION_HISTORY_LIMIT` is not set.
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// Reads the conversation history limit shared by all [`Storage`] implementations.
+///
+/// # Environment Variables
+///
+/// - `CONVERSATION_HISTORY_LIMIT`: (optional) messages kept per chat, defaults to
+///   [`DEFAULT_HISTORY_LIMIT`]
+///
+/// # Errors
+///
+/// Returns an error if the variable is set but is not a valid number.
+pub fn history_limit_from_env() -> Result<usize, Box<dyn Error + Send + Sync>> {
+    match env::var("CONVERSATION_HISTORY_LIMIT") {
+        Ok(value) if !value.trim().is_empty() => Ok(value
+            .trim()
+            .parse()
+            .map_err(|_| "Environment variable CONVERSATION_HISTORY_LIMIT must be a number")?),
+        _ => Ok(DEFAULT_HISTORY_LIMIT),
+    }
+}
+
+/// `Storage` persists what the bot knows about its users and chats:
+/// user and chat records, the conversation history and per-chat settings.
+///
+/// This trait allows handlers to be tested with the in-memory implementation,
+/// while production deployments use the SQLite one (`sqlite` cargo feature).
+///
+/// Chats are keyed by the Telegram chat ID (`TelegramChat::id`).
+/// Implementations decide how many messages [`Storage::recent_messages`] returns.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Inserts a user or updates the stored one with the same ID.
+    async fn upsert_user(&self, user: &StoredUser) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Inserts a chat or updates the stored one with the same ID.
+    ///
+    /// Unset fields keep their stored value.
+    async fn upsert_chat(&self, chat: &StoredChat) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Appends new turns to a chat's conversation history.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_id` - The Telegram chat ID.
+    /// * `messages` - The messages to append, oldest first.
+    async fn append_messages(
+        &self,
+        chat_id: i64,
+        messages: &[ChatMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Returns the most recent turns of a chat, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// The stored messages (at most the implementation's history limit),
+    /// or an empty vector if the chat has no history yet.
+    async fn recent_messages(
+        &self,
+        chat_id: i64,
+    ) -> Result<Vec<ChatMessage>, Box<dyn Error + Send + Sync>>;
+
+    /// Forgets the whole conversation history of a chat.
+    async fn clear_messages(&self, chat_id: i64) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Returns the settings of a chat, or the default settings if none were saved.
+    async fn chat_settings(
+        &self,
+        chat_id: i64,
+    ) -> Result<ChatSettings, Box<dyn Error + Send + Sync>>;
+
+    /// Saves the settings of a chat, replacing the previous ones.
+    async fn save_chat_settings(
+        &self,
+        chat_id: i64,
+        settings: &ChatSettings,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Moves a chat's conversation history and settings to a new chat ID.
+    ///
+    /// Telegram gives a group a new ID when it is upgraded to a supergroup.
+    /// Settings already saved for the new chat are kept. Moving a chat that has
+    /// nothing stored does nothing, so the migration can be recorded more than once.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_chat_id` - The old chat ID (the group).
+    /// * `to_chat_id` - The new chat ID (the supergroup).
+    async fn migrate_chat(
+        &self,
+        from_chat_id: i64,
+        to_chat_id: i64,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
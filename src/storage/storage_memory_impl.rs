@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::sync::Mutex;
+
+use crate::models::chat::ChatMessage;
+use crate::models::storage::{ChatSettings, StoredChat, StoredUser};
+use crate::storage::storage_api::{history_limit_from_env, Storage};
+
+/// Everything kept by [`InMemoryStorage`], behind a single lock.
+#[derive(Default)]
+struct State {
+    users: HashMap<i64, StoredUser>,
+    chats: HashMap<i64, StoredChat>,
+    histories: HashMap<i64, VecDeque<ChatMessage>>,
+    settings: HashMap<i64, ChatSettings>,
+}
+
+/// An in-memory implementation of the [`Storage`] trait.
+///
+/// Each chat keeps at most `history_limit` messages; older ones are dropped first.
+/// Everything is lost when the process restarts, which makes it a good fit for tests
+/// and for deployments that do not need persistence.
+pub struct InMemoryStorage {
+    state: Mutex<State>,
+    history_limit: usize,
+}
+
+impl InMemoryStorage {
+    /// Creates a new, empty storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `history_limit` - How many messages (user and assistant turns together) are kept per chat.
+    pub fn new(history_limit: usize) -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            history_limit,
+        }
+    }
+
+    /// Creates a new storage using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `CONVERSATION_HISTORY_LIMIT`: (optional) messages kept per chat
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable is set but is not a valid number.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self::new(history_limit_from_env()?))
+    }
+
+    /// Returns the stored user with the given ID, if any.
+    pub fn user(&self, user_id: i64) -> Option<StoredUser> {
+        self.state.lock().unwrap().users.get(&user_id).cloned()
+    }
+
+    /// Returns the stored chat with the given ID, if any.
+    pub fn chat(&self, chat_id: i64) -> Option<StoredChat> {
+        self.state.lock().unwrap().chats.get(&chat_id).cloned()
+    }
+}
+
+#[async_trait]
+impl Storage for InMemoryStorage {
+    async fn upsert_user(&self, user: &StoredUser) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        state.users.insert(user.id, user.clone());
+        Ok(())
+    }
+
+    async fn upsert_chat(&self, chat: &StoredChat) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        let stored = state.chats.entry(chat.id).or_insert_with(|| StoredChat {
+            id: chat.id,
+            ..Default::default()
+        });
+        if chat.chat_type.is_some() {
+            stored.chat_type = chat.chat_type.clone();
+        }
+        if chat.title.is_some() {
+            stored.title = chat.title.clone();
+        }
+        Ok(())
+    }
+
+    async fn append_messages(
+        &self,
+        chat_id: i64,
+        messages: &[ChatMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        let history = state.histories.entry(chat_id).or_default();
+
+        history.extend(messages.iter().cloned());
+        while history.len() > self.history_limit {
+            history.pop_front();
+        }
+
+        Ok(())
+    }
+
+    async fn recent_messages(
+        &self,
+        chat_id: i64,
+    ) -> Result<Vec<ChatMessage>, Box<dyn Error + Send + Sync>> {
+        let state = self.state.lock().unwrap();
+
+        Ok(state
+            .histories
+            .get(&chat_id)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn clear_messages(&self, chat_id: i64) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.state.lock().unwrap().histories.remove(&chat_id);
+        Ok(())
+    }
+
+    async fn chat_settings(
+        &self,
+        chat_id: i64,
+    ) -> Result<ChatSettings, Box<dyn Error + Send + Sync>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.settings.get(&chat_id).cloned().unwrap_or_default())
+    }
+
+    async fn save_chat_settings(
+        &self,
+        chat_id: i64,
+        settings: &ChatSettings,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+        state.settings.insert(chat_id, settings.clone());
+        Ok(())
+    }
+
+    async fn migrate_chat(
+        &self,
+        from_chat_id: i64,
+        to_chat_id: i64,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(mut history) = state.histories.remove(&from_chat_id) {
+            history.extend(state.histories.remove(&to_chat_id).unwrap_or_default());
+            while history.len() > self.history_limit {
+                history.pop_front();
+            }
+            state.histories.insert(to_chat_id, history);
+        }
+        if let Some(settings) = state.settings.remove(&from_chat_id) {
+            state.settings.entry(to_chat_id).or_insert(settings);
+        }
+        state.chats.remove(&from_chat_id);
+
+        Ok(())
+    }
+}
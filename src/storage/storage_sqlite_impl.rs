@@ -0,0 +1,305 @@
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::env;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use crate::models::chat::ChatMessage;
+use crate::models::storage::{ChatSettings, StoredChat, StoredUser};
+use crate::storage::migrations::run_migrations;
+use crate::storage::storage_api::{history_limit_from_env, Storage};
+
+/// A SQLite-backed implementation of the [`Storage`] trait.
+///
+/// The schema is created and upgraded by [`run_migrations`] when the database is opened.
+/// The whole conversation log is kept; [`Storage::recent_messages`] only returns
+/// the newest `history_limit` messages of a chat.
+///
+/// `rusqlite` is synchronous, so every query runs on Tokio's blocking thread pool.
+///
+/// Environment variables used:
+/// - `DATABASE_PATH` — path of the SQLite database file (created if missing)
+/// - `CONVERSATION_HISTORY_LIMIT` — optional, messages returned per chat
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+    history_limit: usize,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database file at `path` and applies pending migrations.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the SQLite database file.
+    /// * `history_limit` - How many messages [`Storage::recent_messages`] returns per chat.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or migrated.
+    pub fn open(path: &str, history_limit: usize) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_connection(Connection::open(path)?, history_limit)
+    }
+
+    /// Opens a private in-memory database, mostly useful for tests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be migrated.
+    pub fn open_in_memory(history_limit: usize) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_connection(Connection::open_in_memory()?, history_limit)
+    }
+
+    /// Creates a new `SqliteStorage` using environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// - `DATABASE_PATH`: path of the SQLite database file
+    /// - `CONVERSATION_HISTORY_LIMIT`: (optional) messages returned per chat
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `DATABASE_PATH` is missing or empty, or the database
+    /// cannot be opened or migrated.
+    pub fn new_from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path = env::var("DATABASE_PATH")
+            .map_err(|_| "Environment variable DATABASE_PATH is not set or empty")?;
+        if path.trim().is_empty() {
+            return Err("Environment variable DATABASE_PATH cannot be empty".into());
+        }
+
+        Self::open(&path, history_limit_from_env()?)
+    }
+
+    fn from_connection(
+        mut connection: Connection,
+        history_limit: usize,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        run_migrations(&mut connection)?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            history_limit,
+        })
+    }
+
+    /// Runs `query` with exclusive access to the connection on the blocking thread pool.
+    async fn run<T, F>(&self, query: F) -> Result<T, Box<dyn Error + Send + Sync>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().map_err(|e| e.to_string())?;
+            query(&mut connection).map_err(|e| e.to_string())
+        })
+        .await?;
+
+        Ok(result?)
+    }
+}
+
+/// Makes sure a chat row exists, so rows referencing it satisfy the foreign keys.
+fn ensure_chat(connection: &Connection, chat_id: i64) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT OR IGNORE INTO chats (id) VALUES (?1)",
+        params![chat_id],
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn upsert_user(&self, user: &StoredUser) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let user = user.clone();
+
+        self.run(move |connection| {
+            connection.execute(
+                "INSERT INTO users (id, username, first_name, last_name) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (id) DO UPDATE SET
+                     username = excluded.username,
+                     first_name = excluded.first_name,
+                     last_name = excluded.last_name,
+                     updated_at = CURRENT_TIMESTAMP",
+                params![user.id, user.username, user.first_name, user.last_name],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn upsert_chat(&self, chat: &StoredChat) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let chat = chat.clone();
+
+        self.run(move |connection| {
+            connection.execute(
+                "INSERT INTO chats (id, chat_type, title) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET
+                     chat_type = COALESCE(excluded.chat_type, chats.chat_type),
+                     title = COALESCE(excluded.title, chats.title),
+                     updated_at = CURRENT_TIMESTAMP",
+                params![chat.id, chat.chat_type, chat.title],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn append_messages(
+        &self,
+        chat_id: i64,
+        messages: &[ChatMessage],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let messages = messages.to_vec();
+
+        self.run(move |connection| {
+            let transaction = connection.transaction()?;
+            ensure_chat(&transaction, chat_id)?;
+
+            {
+                let mut statement = transaction.prepare(
+                    "INSERT INTO messages (chat_id, role, content, name, tool_call_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for message in &messages {
+                    statement.execute(params![
+                        chat_id,
+                        message.role.as_str(),
+                        message.content,
+                        message.name,
+                        message.tool_call_id,
+                    ])?;
+                }
+            }
+
+            transaction.commit()
+        })
+        .await
+    }
+
+    async fn recent_messages(
+        &self,
+        chat_id: i64,
+    ) -> Result<Vec<ChatMessage>, Box<dyn Error + Send + Sync>> {
+        let limit = self.history_limit as i64;
+
+        let rows = self
+            .run(move |connection| {
+                let mut statement = connection.prepare(
+                    "SELECT role, content, name, tool_call_id FROM (
+                         SELECT id, role, content, name, tool_call_id FROM messages
+                         WHERE chat_id = ?1 ORDER BY id DESC LIMIT ?2
+                     ) ORDER BY id ASC",
+                )?;
+                let rows = statement.query_map(params![chat_id, limit], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                })?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await?;
+
+        rows.into_iter()
+            .map(|(role, content, name, tool_call_id)| {
+                Ok(ChatMessage {
+                    role: role.parse()?,
+                    content,
+                    name,
+                    tool_call_id,
+                })
+            })
+            .collect()
+    }
+
+    async fn clear_messages(&self, chat_id: i64) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.run(move |connection| {
+            connection.execute("DELETE FROM messages WHERE chat_id = ?1", params![chat_id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn chat_settings(
+        &self,
+        chat_id: i64,
+    ) -> Result<ChatSettings, Box<dyn Error + Send + Sync>> {
+        let settings = self
+            .run(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT persona, model FROM chat_settings WHERE chat_id = ?1",
+                        params![chat_id],
+                        |row| {
+                            Ok(ChatSettings {
+                                persona: row.get(0)?,
+                                model: row.get(1)?,
+                            })
+                        },
+                    )
+                    .optional()
+            })
+            .await?;
+
+        Ok(settings.unwrap_or_default())
+    }
+
+    async fn save_chat_settings(
+        &self,
+        chat_id: i64,
+        settings: &ChatSettings,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let settings = settings.clone();
+
+        self.run(move |connection| {
+            let transaction = connection.transaction()?;
+            ensure_chat(&transaction, chat_id)?;
+            transaction.execute(
+                "INSERT INTO chat_settings (chat_id, persona, model) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (chat_id) DO UPDATE SET
+                     persona = excluded.persona,
+                     model = excluded.model,
+                     updated_at = CURRENT_TIMESTAMP",
+                params![chat_id, settings.persona, settings.model],
+            )?;
+            transaction.commit()
+        })
+        .await
+    }
+
+    async fn migrate_chat(
+        &self,
+        from_chat_id: i64,
+        to_chat_id: i64,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.run(move |connection| {
+            let transaction = connection.transaction()?;
+            let has_data: bool = transaction.query_row(
+                "SELECT EXISTS (SELECT 1 FROM messages WHERE chat_id = ?1)
+                     OR EXISTS (SELECT 1 FROM chat_settings WHERE chat_id = ?1)",
+                params![from_chat_id],
+                |row| row.get(0),
+            )?;
+            if has_data {
+                ensure_chat(&transaction, to_chat_id)?;
+                transaction.execute(
+                    "UPDATE messages SET chat_id = ?2 WHERE chat_id = ?1",
+                    params![from_chat_id, to_chat_id],
+                )?;
+                transaction.execute(
+                    "INSERT OR IGNORE INTO chat_settings (chat_id, persona, model)
+                     SELECT ?2, persona, model FROM chat_settings WHERE chat_id = ?1",
+                    params![from_chat_id, to_chat_id],
+                )?;
+            }
+            transaction.execute("DELETE FROM chats WHERE id = ?1", params![from_chat_id])?;
+            transaction.commit()
+        })
+        .await
+    }
+}
@@ -0,0 +1,179 @@
+use httpmock::{Method::POST, MockServer};
+
+use tg_ai_companion::cli::{run_command, Command};
+use tg_ai_companion::models::telegram::{DeleteWebhookRequest, SetWebhookRequest};
+use tg_ai_companion::services::telegram_api_impl::RealTelegramApi;
+
+/// A fake token used for mocking Telegram Bot API requests in tests.
+const FAKE_TOKEN: &str = "FAKE_TOKEN";
+
+/// Builds a client talking to the mock server.
+fn telegram_api(server: &MockServer) -> RealTelegramApi {
+    RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string())
+}
+
+/// Tests parsing of every subcommand, with and without arguments.
+#[test]
+fn test_parse_commands() {
+    let no_args: [&str; 0] = [];
+    assert_eq!(Command::parse(no_args), Ok(Command::Serve));
+    assert_eq!(Command::parse(["serve"]), Ok(Command::Serve));
+    assert_eq!(
+        Command::parse(["set-webhook"]),
+        Ok(Command::SetWebhook { url: None })
+    );
+    assert_eq!(
+        Command::parse(["set-webhook", "https://example.com/telegram/webhook"]),
+        Ok(Command::SetWebhook {
+            url: Some("https://example.com/telegram/webhook".to_string())
+        })
+    );
+    assert_eq!(
+        Command::parse(["delete-webhook"]),
+        Ok(Command::DeleteWebhook {
+            drop_pending_updates: false
+        })
+    );
+    assert_eq!(
+        Command::parse(["delete-webhook", "--drop-pending-updates"]),
+        Ok(Command::DeleteWebhook {
+            drop_pending_updates: true
+        })
+    );
+    assert_eq!(Command::parse(["webhook-info"]), Ok(Command::WebhookInfo));
+    assert_eq!(Command::parse(["get-me"]), Ok(Command::GetMe));
+    assert_eq!(Command::parse(["--help"]), Ok(Command::Help));
+}
+
+/// Tests that unknown commands and unexpected arguments are rejected.
+#[test]
+fn test_parse_invalid_commands() {
+    assert!(Command::parse(["unknown"]).is_err());
+    assert!(Command::parse(["get-me", "extra"]).is_err());
+    assert!(Command::parse(["delete-webhook", "--force"]).is_err());
+    assert!(Command::parse(["set-webhook", "a", "b"]).is_err());
+}
+
+/// Tests that `set-webhook` with an explicit URL calls `setWebhook`.
+#[tokio::test]
+async fn test_run_set_webhook() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/setWebhook", FAKE_TOKEN))
+            .json_body_obj(&SetWebhookRequest {
+                url: "https://example.com/telegram/webhook".to_string(),
+                secret_token: None,
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+
+    let output = run_command(
+        Command::SetWebhook {
+            url: Some("https://example.com/telegram/webhook".to_string()),
+        },
+        &telegram_api(&server),
+    )
+    .await;
+
+    assert_eq!(
+        output,
+        Ok("Webhook set to https://example.com/telegram/webhook".to_string())
+    );
+    mock.assert();
+}
+
+/// Tests that `delete-webhook --drop-pending-updates` calls `deleteWebhook`.
+#[tokio::test]
+async fn test_run_delete_webhook() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/deleteWebhook", FAKE_TOKEN))
+            .json_body_obj(&DeleteWebhookRequest {
+                drop_pending_updates: true,
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+
+    let output = run_command(
+        Command::DeleteWebhook {
+            drop_pending_updates: true,
+        },
+        &telegram_api(&server),
+    )
+    .await;
+
+    assert_eq!(output, Ok("Webhook deleted".to_string()));
+    mock.assert();
+}
+
+/// Tests that `webhook-info` prints the parsed webhook status.
+#[tokio::test]
+async fn test_run_webhook_info() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/getWebhookInfo", FAKE_TOKEN));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":{"url":"https://example.com/telegram/webhook",
+                    "has_custom_certificate":false,"pending_update_count":3,
+                    "last_error_date":1700000000,"last_error_message":"Connection refused"}}"#,
+            );
+    });
+
+    let output = run_command(Command::WebhookInfo, &telegram_api(&server))
+        .await
+        .unwrap();
+
+    assert!(output.contains(r#""url": "https://example.com/telegram/webhook""#));
+    assert!(output.contains(r#""pending_update_count": 3"#));
+    assert!(output.contains(r#""last_error_message": "Connection refused""#));
+    mock.assert();
+}
+
+/// Tests that `get-me` prints the bot user and reports an invalid token as an error.
+#[tokio::test]
+async fn test_run_get_me() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST).path(format!("/bot{}/getMe", FAKE_TOKEN));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":{"id":42,"is_bot":true,"first_name":"Companion",
+                    "username":"companion_bot","can_join_groups":true}}"#,
+            );
+    });
+
+    let output = run_command(Command::GetMe, &telegram_api(&server))
+        .await
+        .unwrap();
+
+    assert!(output.contains(r#""username": "companion_bot""#));
+    mock.assert();
+
+    let unauthorized = MockServer::start();
+    unauthorized.mock(|when, then| {
+        when.method(POST);
+        then.status(401)
+            .body(r#"{"ok":false,"error_code":401,"description":"Unauthorized"}"#);
+    });
+
+    let result = run_command(Command::GetMe, &telegram_api(&unauthorized)).await;
+    assert!(result.unwrap_err().contains("401"));
+}
@@ -5,39 +5,97 @@
 
 use actix_web::{http::StatusCode, test, web, App};
 use async_trait::async_trait;
-use mockall::predicate::*;
 use mockall::*;
 use serde_json::json;
-use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tg_ai_companion::handlers::chat::chat_endpoint;
-use tg_ai_companion::services::chat_api::ChatApi;
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use tg_ai_companion::models::health::BackendHealth;
+use tg_ai_companion::models::persona::Persona;
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use tg_ai_companion::services::persona_registry::PersonaRegistry;
 
 mock! {
     /// A mock implementation of the `ChatApi` trait for testing.
     ///
-    /// This mock simulates `call_chat_api` without real HTTP calls.
+    /// This mock simulates `call_chat_api`, `complete`, `complete_stream` and `health` without real HTTP calls.
     pub ChatApi {}
 
     #[async_trait]
     impl ChatApi for ChatApi {
-        async fn call_chat_api(&self, prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>>;
+        async fn call_chat_api(&self, prompt: &str) -> Result<String, ChatApiError>;
+        async fn complete(
+            &self,
+            messages: &[ChatMessage],
+            options: &ChatOptions,
+        ) -> Result<ChatCompletion, ChatApiError>;
+        async fn complete_stream(
+            &self,
+            messages: &[ChatMessage],
+            options: &ChatOptions,
+        ) -> Result<ChatStream, ChatApiError>;
+        fn health(&self) -> Vec<BackendHealth>;
     }
 }
 
+/// Builds a completion with the given content.
+fn completion(content: &str) -> ChatCompletion {
+    ChatCompletion {
+        content: content.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Builds a stream yielding the given fragments, optionally followed by an error.
+fn fragments(parts: &[&str], error: bool) -> ChatStream {
+    let mut items: Vec<Result<String, ChatApiError>> =
+        parts.iter().map(|part| Ok(part.to_string())).collect();
+    if error {
+        items.push(Err(ChatApiError::Transport("stream broke".to_string())));
+    }
+    Box::pin(futures_util::stream::iter(items))
+}
+
+/// Builds a registry with a default persona without system prompt and a "pirate" persona.
+fn personas() -> web::Data<PersonaRegistry> {
+    let registry = PersonaRegistry::new(
+        vec![
+            Persona {
+                name: "default".to_string(),
+                ..Default::default()
+            },
+            Persona {
+                name: "pirate".to_string(),
+                system_prompt: "Talk like a pirate.".to_string(),
+                model: Some("mistral".to_string()),
+                temperature: Some(0.9),
+                backend: Some("localai".to_string()),
+                ..Default::default()
+            },
+        ],
+        None,
+    )
+    .unwrap();
+
+    web::Data::new(registry)
+}
+
 /// Tests successful response from `/chat` endpoint.
 ///
-/// Mocks `call_chat_api` to return "Hi back!" when prompt is "Hello".
+/// Mocks `complete` to return "Hi back!" when the only message is the user's "Hello".
 #[actix_web::test]
 async fn test_chat_endpoint_ok() {
     let mut mock_api = MockChatApi::new();
 
     mock_api
-        .expect_call_chat_api()
-        .with(eq("Hello"))
+        .expect_complete()
+        .withf(|messages, options| {
+            messages == [ChatMessage::user("Hello")] && *options == ChatOptions::default()
+        })
         .times(1)
-        .returning(|_| Ok("Hi back!".to_string()));
+        .returning(|_, _| Ok(completion("Hi back!")));
 
     // Wrap the mock in Arc and then into web::Data to inject into the app state.
     let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
@@ -45,6 +103,7 @@ async fn test_chat_endpoint_ok() {
     let app = test::init_service(
         App::new()
             .app_data(chat_api_data)
+            .app_data(personas())
             .route("/chat", web::post().to(chat_endpoint)),
     )
     .await;
@@ -73,6 +132,7 @@ async fn test_chat_endpoint_empty_prompt() {
     let app = test::init_service(
         App::new()
             .app_data(chat_api_data)
+            .app_data(personas())
             .route("/chat", web::post().to(chat_endpoint)),
     )
     .await;
@@ -92,20 +152,21 @@ async fn test_chat_endpoint_empty_prompt() {
     assert_eq!(resp_str.trim(), "Prompt cannot be empty");
 }
 
-/// Tests `/chat` endpoint returns 500 Internal Server Error when ChatApi fails.
+/// Tests `/chat` endpoint answers a timed-out ChatApi call with 504 Gateway Timeout and the user message.
 #[actix_web::test]
 async fn test_chat_endpoint_api_error() {
     let mut mock_api = MockChatApi::new();
 
     mock_api
-        .expect_call_chat_api()
-        .returning(|_| Err("API failure".into()));
+        .expect_complete()
+        .returning(|_, _| Err(ChatApiError::Timeout));
 
     let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
 
     let app = test::init_service(
         App::new()
             .app_data(chat_api_data)
+            .app_data(personas())
             .route("/chat", web::post().to(chat_endpoint)),
     )
     .await;
@@ -118,9 +179,311 @@ async fn test_chat_endpoint_api_error() {
         .to_request();
 
     let resp = test::call_service(&app, req).await;
-    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
 
     let resp_body = test::read_body(resp).await;
     let resp_str = std::str::from_utf8(&resp_body).unwrap();
-    assert_eq!(resp_str.trim(), "Error calling chat API");
+    assert_eq!(resp_str.trim(), ChatApiError::Timeout.user_message());
+}
+
+/// Tests that the requested persona's system prompt and options are sent to the model.
+#[actix_web::test]
+async fn test_chat_endpoint_with_persona() {
+    let mut mock_api = MockChatApi::new();
+
+    mock_api
+        .expect_complete()
+        .withf(|messages, options| {
+            messages
+                == [
+                    ChatMessage::system("Talk like a pirate."),
+                    ChatMessage::user("Hello"),
+                ]
+                && options.model.as_deref() == Some("mistral")
+                && options.temperature == Some(0.9)
+                && options.backend.as_deref() == Some("localai")
+        })
+        .times(1)
+        .returning(|_, _| Ok(completion("Ahoy!")));
+
+    let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(chat_api_data)
+            .app_data(personas())
+            .route("/chat", web::post().to(chat_endpoint)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/chat")
+        .set_json(json!({ "prompt": "Hello", "persona": "pirate" }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp_body = test::read_body(resp).await;
+    assert_eq!(std::str::from_utf8(&resp_body).unwrap(), "Ahoy!");
+}
+
+/// Tests `/chat` endpoint returns 400 Bad Request for an unknown persona.
+#[actix_web::test]
+async fn test_chat_endpoint_unknown_persona() {
+    let mock_api = MockChatApi::new();
+    let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(chat_api_data)
+            .app_data(personas())
+            .route("/chat", web::post().to(chat_endpoint)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/chat")
+        .set_json(json!({ "prompt": "Hello", "persona": "ninja" }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp_body = test::read_body(resp).await;
+    assert_eq!(
+        std::str::from_utf8(&resp_body).unwrap(),
+        "Unknown persona: ninja"
+    );
+}
+
+/// Tests that `"stream": true` returns the reply fragments as Server-Sent Events.
+#[actix_web::test]
+async fn test_chat_endpoint_stream() {
+    let mut mock_api = MockChatApi::new();
+
+    mock_api
+        .expect_complete_stream()
+        .withf(|messages, _| messages == [ChatMessage::user("Hello")])
+        .times(1)
+        .returning(|_, _| Ok(fragments(&["Hel", "lo!"], false)));
+
+    let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(chat_api_data)
+            .app_data(personas())
+            .route("/chat", web::post().to(chat_endpoint)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/chat")
+        .set_json(json!({ "prompt": "Hello", "stream": true }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+
+    let resp_body = test::read_body(resp).await;
+    assert_eq!(
+        std::str::from_utf8(&resp_body).unwrap(),
+        "data: {\"content\":\"Hel\"}\n\ndata: {\"content\":\"lo!\"}\n\ndata: [DONE]\n\n"
+    );
+}
+
+/// Tests that `Accept: text/event-stream` also enables streaming,
+/// and that a failure mid-way ends the stream with an `error` event.
+#[actix_web::test]
+async fn test_chat_endpoint_stream_accept_header_and_error() {
+    let mut mock_api = MockChatApi::new();
+
+    mock_api
+        .expect_complete_stream()
+        .times(1)
+        .returning(|_, _| Ok(fragments(&["Hel"], true)));
+
+    let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(chat_api_data)
+            .app_data(personas())
+            .route("/chat", web::post().to(chat_endpoint)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/chat")
+        .insert_header(("Accept", "text/event-stream"))
+        .set_json(json!({ "prompt": "Hello" }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp_body = test::read_body(resp).await;
+    assert_eq!(
+        std::str::from_utf8(&resp_body).unwrap(),
+        format!(
+            "data: {{\"content\":\"Hel\"}}\n\nevent: error\ndata: {}\n\n",
+            json!({ "error": ChatApiError::Transport(String::new()).user_message() })
+        )
+    );
+}
+
+/// Tests that each `ChatApiError` is answered with its HTTP status, and rate limits with `Retry-After`.
+#[actix_web::test]
+async fn test_chat_endpoint_error_statuses() {
+    let cases = [
+        (
+            ChatApiError::Transport("connection refused".to_string()),
+            StatusCode::BAD_GATEWAY,
+        ),
+        (
+            ChatApiError::Auth("invalid key".to_string()),
+            StatusCode::BAD_GATEWAY,
+        ),
+        (
+            ChatApiError::RateLimited {
+                retry_after: Some(Duration::from_secs(7)),
+                message: "slow down".to_string(),
+            },
+            StatusCode::TOO_MANY_REQUESTS,
+        ),
+        (
+            ChatApiError::ContextLengthExceeded("too long".to_string()),
+            StatusCode::PAYLOAD_TOO_LARGE,
+        ),
+        (
+            ChatApiError::ContentFiltered("blocked".to_string()),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ),
+        (
+            ChatApiError::MalformedResponse("not JSON".to_string()),
+            StatusCode::BAD_GATEWAY,
+        ),
+        (
+            ChatApiError::Backend {
+                status: 500,
+                message: "boom".to_string(),
+            },
+            StatusCode::BAD_GATEWAY,
+        ),
+    ];
+
+    for (error, status) in cases {
+        let mut mock_api = MockChatApi::new();
+        let returned = error.clone();
+        mock_api
+            .expect_complete()
+            .returning(move |_, _| Err(returned.clone()));
+
+        let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+        let app = test::init_service(
+            App::new()
+                .app_data(chat_api_data)
+                .app_data(personas())
+                .route("/chat", web::post().to(chat_endpoint)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/chat")
+            .set_json(json!({ "prompt": "Hello" }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), status, "{:?}", error);
+        let retry_after = resp
+            .headers()
+            .get("Retry-After")
+            .map(|value| value.to_str().unwrap().to_string());
+        assert_eq!(
+            retry_after,
+            error.retry_after().map(|wait| wait.as_secs().to_string())
+        );
+
+        let resp_body = test::read_body(resp).await;
+        assert_eq!(
+            std::str::from_utf8(&resp_body).unwrap(),
+            error.user_message()
+        );
+    }
+}
+
+/// Builds the health report of backends with the given names.
+fn backends(names: &[&str]) -> Vec<BackendHealth> {
+    names
+        .iter()
+        .map(|name| BackendHealth {
+            name: name.to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Tests that a request can pick the backend that answers.
+#[actix_web::test]
+async fn test_chat_endpoint_with_backend() {
+    let mut mock_api = MockChatApi::new();
+    mock_api
+        .expect_health()
+        .returning(|| backends(&["localai", "anthropic"]));
+    mock_api
+        .expect_complete()
+        .withf(|_, options| options.backend.as_deref() == Some("anthropic"))
+        .times(1)
+        .returning(|_, _| Ok(completion("Hi from Claude")));
+    let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(chat_api_data)
+            .app_data(personas())
+            .route("/chat", web::post().to(chat_endpoint)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/chat")
+        .set_json(json!({ "prompt": "Hello", "backend": "anthropic" }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(test::read_body(resp).await, "Hi from Claude");
+}
+
+/// Tests that an unknown backend is rejected before calling the chat API.
+#[actix_web::test]
+async fn test_chat_endpoint_unknown_backend() {
+    let mut mock_api = MockChatApi::new();
+    mock_api
+        .expect_health()
+        .returning(|| backends(&["localai"]));
+    mock_api.expect_complete().never();
+    let chat_api_data = web::Data::from(Arc::new(mock_api) as Arc<dyn ChatApi>);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(chat_api_data)
+            .app_data(personas())
+            .route("/chat", web::post().to(chat_endpoint)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/chat")
+        .set_json(json!({ "prompt": "Hello", "backend": "gemini" }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(test::read_body(resp).await, "Unknown backend: gemini");
 }
@@ -0,0 +1,591 @@
+use async_trait::async_trait;
+
+use tg_ai_companion::handlers::commands::{
+    parse_command, CommandContext, CommandHandler, CommandRouter, ModelCommand, ParsedCommand,
+};
+use tg_ai_companion::models::chat::ChatMessage;
+use tg_ai_companion::models::persona::Persona;
+use tg_ai_companion::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    ChatType, EditMessageTextRequest, InputFile, SendMediaOptions, SendMessageRequest,
+    TelegramChat, TelegramFile, TelegramMessage, TelegramUpdate, TelegramUser,
+};
+use tg_ai_companion::services::persona_registry::PersonaRegistry;
+use tg_ai_companion::services::telegram_api::{TelegramApi, TelegramApiError};
+use tg_ai_companion::storage::storage_api::Storage;
+use tg_ai_companion::storage::storage_memory_impl::InMemoryStorage;
+
+/// Mock implementation of TelegramApi knowing which users administer the chats.
+/// Commands do not send anything themselves, so the other methods only succeed.
+#[derive(Default)]
+struct AdminsTelegramApi {
+    admins: Vec<i64>,
+}
+
+#[async_trait]
+impl TelegramApi for AdminsTelegramApi {
+    async fn send_message(
+        &self,
+        _request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn edit_message_text(
+        &self,
+        _request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn delete_message(
+        &self,
+        _chat_id: i64,
+        _message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_document_file(
+        &self,
+        _chat_id: i64,
+        _document: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn send_chat_action(
+        &self,
+        _chat_id: i64,
+        _message_thread_id: Option<i64>,
+        _action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn get_updates(
+        &self,
+        _offset: Option<i64>,
+        _timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_chat_member(
+        &self,
+        _chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        let status = if self.admins.contains(&user_id) {
+            "administrator"
+        } else {
+            "member"
+        };
+        Ok(ChatMember {
+            status: status.to_string(),
+            user: TelegramUser {
+                id: user_id,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn answer_callback_query(
+        &self,
+        _request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn answer_inline_query(
+        &self,
+        _request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_photo(
+        &self,
+        _chat_id: i64,
+        _photo: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn send_voice(
+        &self,
+        _chat_id: i64,
+        _voice: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        Ok(TelegramFile {
+            file_id: file_id.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn download_file(&self, _file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        Ok(TelegramUser::default())
+    }
+
+    async fn set_my_commands(&self, _commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+}
+
+/// Builds a message sent by `sender_id` in `chat_id`; negative IDs are groups.
+fn message(chat_id: i64, sender_id: i64) -> TelegramMessage {
+    TelegramMessage {
+        message_id: 1,
+        from: Some(TelegramUser {
+            id: sender_id,
+            ..Default::default()
+        }),
+        chat: TelegramChat {
+            id: chat_id,
+            chat_type: Some(if chat_id < 0 {
+                ChatType::Group
+            } else {
+                ChatType::Private
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Dispatches `text` as a command sent by `sender_id` in `chat_id` and returns the reply.
+async fn dispatch_in(
+    router: &CommandRouter,
+    chat_id: i64,
+    sender_id: i64,
+    text: &str,
+    storage: &dyn Storage,
+    personas: &PersonaRegistry,
+    telegram_api: &dyn TelegramApi,
+) -> Option<String> {
+    let command = parse_command(text).expect("Expected a command");
+    router
+        .dispatch(
+            &command,
+            &message(chat_id, sender_id),
+            storage,
+            personas,
+            telegram_api,
+        )
+        .await
+}
+
+/// Dispatches `text` as a command in the private chat 1 and returns the reply.
+async fn dispatch(
+    router: &CommandRouter,
+    text: &str,
+    storage: &dyn Storage,
+    personas: &PersonaRegistry,
+) -> Option<String> {
+    let telegram_api = AdminsTelegramApi::default();
+    dispatch_in(router, 1, 1, text, storage, personas, &telegram_api).await
+}
+
+/// A registry with a default persona using `mistral` and a `pirate` persona.
+fn personas() -> PersonaRegistry {
+    PersonaRegistry::new(
+        vec![
+            Persona {
+                name: "default".to_string(),
+                model: Some("mistral".to_string()),
+                ..Default::default()
+            },
+            Persona {
+                name: "pirate".to_string(),
+                description: Some("Talks like a pirate".to_string()),
+                ..Default::default()
+            },
+        ],
+        None,
+    )
+    .unwrap()
+}
+
+/// Tests parsing of commands with a bot username and arguments.
+#[test]
+fn test_parse_command() {
+    assert_eq!(
+        parse_command("/persona@my_bot  pirate captain "),
+        Some(ParsedCommand {
+            name: "persona",
+            bot_username: Some("my_bot"),
+            args: "pirate captain",
+        })
+    );
+    assert_eq!(
+        parse_command("/start"),
+        Some(ParsedCommand {
+            name: "start",
+            bot_username: None,
+            args: "",
+        })
+    );
+    assert_eq!(parse_command("Hello /start"), None);
+    assert_eq!(parse_command("/"), None);
+    assert_eq!(parse_command("/path/to/file"), None);
+}
+
+/// Tests that `/help` lists every registered command, including custom ones.
+#[tokio::test]
+async fn test_help_lists_commands() {
+    struct PingCommand;
+
+    #[async_trait]
+    impl CommandHandler for PingCommand {
+        async fn handle(&self, _context: &CommandContext<'_>) -> String {
+            "pong".to_string()
+        }
+    }
+
+    let router = CommandRouter::default().register("ping", "Check the bot is alive", PingCommand);
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+
+    let reply = dispatch(&router, "/help", &storage, &personas)
+        .await
+        .unwrap();
+    for command in ["/start", "/help", "/reset", "/model", "/persona"] {
+        assert!(reply.contains(command), "Missing {} in {}", command, reply);
+    }
+    assert!(reply.contains("/ping — Check the bot is alive"));
+
+    assert_eq!(
+        dispatch(&router, "/PING", &storage, &personas).await,
+        Some("pong".to_string())
+    );
+}
+
+/// Tests that `/reset` clears the chat's history.
+#[tokio::test]
+async fn test_reset_clears_history() {
+    let router = CommandRouter::default();
+    let storage = InMemoryStorage::new(10);
+    storage
+        .append_messages(
+            1,
+            &[ChatMessage::user("Hi"), ChatMessage::assistant("Hello")],
+        )
+        .await
+        .unwrap();
+
+    let reply = dispatch(&router, "/reset", &storage, &personas()).await;
+
+    assert_eq!(reply, Some("Done, I forgot our conversation.".to_string()));
+    assert!(storage.recent_messages(1).await.unwrap().is_empty());
+}
+
+/// Tests that in groups only administrators can clear the shared history.
+#[tokio::test]
+async fn test_reset_needs_admin_in_groups() {
+    let router = CommandRouter::default();
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+    let telegram_api = AdminsTelegramApi { admins: vec![8] };
+    storage
+        .append_messages(-100, &[ChatMessage::user("Hi")])
+        .await
+        .unwrap();
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/reset",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(
+        reply,
+        Some("Only the chat's administrators can reset the conversation.".to_string())
+    );
+    assert_eq!(storage.recent_messages(-100).await.unwrap().len(), 1);
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        8,
+        "/reset",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(reply, Some("Done, I forgot our conversation.".to_string()));
+    assert!(storage.recent_messages(-100).await.unwrap().is_empty());
+}
+
+/// Tests that `/model` shows, overrides and resets the chat's model.
+#[tokio::test]
+async fn test_model_command() {
+    let router = CommandRouter::default().register(
+        "model",
+        "Show or change the model",
+        ModelCommand::new(vec!["gpt-4o-mini".to_string()]),
+    );
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+
+    let reply = dispatch(&router, "/model", &storage, &personas)
+        .await
+        .unwrap();
+    assert!(reply.starts_with("Current model: mistral"));
+
+    let reply = dispatch(&router, "/model gpt-4o-mini", &storage, &personas).await;
+    assert_eq!(reply, Some("Model switched to gpt-4o-mini.".to_string()));
+    assert_eq!(
+        storage.chat_settings(1).await.unwrap().model.as_deref(),
+        Some("gpt-4o-mini")
+    );
+
+    dispatch(&router, "/model default", &storage, &personas).await;
+    assert_eq!(storage.chat_settings(1).await.unwrap().model, None);
+}
+
+/// Tests that `/model` only switches to the personas' models and the allowed models.
+#[tokio::test]
+async fn test_model_command_rejects_unknown_models() {
+    let router = CommandRouter::default().register(
+        "model",
+        "Show or change the model",
+        ModelCommand::new(vec!["gpt-4o-mini".to_string()]),
+    );
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+
+    let reply = dispatch(&router, "/model", &storage, &personas)
+        .await
+        .unwrap();
+    assert!(reply.contains("Available models: mistral, gpt-4o-mini"));
+
+    let reply = dispatch(&router, "/model gpt-5", &storage, &personas).await;
+    assert_eq!(
+        reply,
+        Some("Unknown model: gpt-5. Available models: mistral, gpt-4o-mini".to_string())
+    );
+    assert_eq!(storage.chat_settings(1).await.unwrap().model, None);
+
+    let reply = dispatch(&router, "/model mistral", &storage, &personas).await;
+    assert_eq!(reply, Some("Model switched to mistral.".to_string()));
+}
+
+/// Tests that in groups only administrators can change the model, while anyone can show it.
+#[tokio::test]
+async fn test_model_command_needs_admin_in_groups() {
+    let router = CommandRouter::default();
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+    let telegram_api = AdminsTelegramApi { admins: vec![8] };
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/model",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await
+    .unwrap();
+    assert!(reply.starts_with("Current model: mistral"));
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/model mistral",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(
+        reply,
+        Some("Only the chat's administrators can change the model.".to_string())
+    );
+    assert_eq!(storage.chat_settings(-100).await.unwrap().model, None);
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        8,
+        "/model mistral",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(reply, Some("Model switched to mistral.".to_string()));
+    assert_eq!(
+        storage.chat_settings(-100).await.unwrap().model.as_deref(),
+        Some("mistral")
+    );
+}
+
+/// Tests that `/persona` lists the personas and rejects unknown names.
+#[tokio::test]
+async fn test_persona_command() {
+    let router = CommandRouter::default();
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+
+    let reply = dispatch(&router, "/persona", &storage, &personas)
+        .await
+        .unwrap();
+    assert!(reply.starts_with("Current persona: default"));
+    assert!(reply.contains("• pirate — Talks like a pirate"));
+
+    let reply = dispatch(&router, "/persona ninja", &storage, &personas).await;
+    assert_eq!(
+        reply,
+        Some("Unknown persona: ninja. Available personas: default, pirate".to_string())
+    );
+    assert_eq!(storage.chat_settings(1).await.unwrap().persona, None);
+}
+
+/// Tests that in groups only administrators can change the persona, while anyone can list them.
+#[tokio::test]
+async fn test_persona_command_needs_admin_in_groups() {
+    let router = CommandRouter::default();
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+    let telegram_api = AdminsTelegramApi { admins: vec![8] };
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/persona",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await
+    .unwrap();
+    assert!(reply.starts_with("Current persona: default"));
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/persona pirate",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(
+        reply,
+        Some("Only the chat's administrators can change the persona.".to_string())
+    );
+    assert_eq!(storage.chat_settings(-100).await.unwrap().persona, None);
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        8,
+        "/persona pirate",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(reply, Some("Persona switched to pirate.".to_string()));
+    assert_eq!(
+        storage
+            .chat_settings(-100)
+            .await
+            .unwrap()
+            .persona
+            .as_deref(),
+        Some("pirate")
+    );
+}
+
+/// Tests that in groups unknown commands are ignored unless they name the bot, as they may
+/// be meant for another bot in the group.
+#[tokio::test]
+async fn test_unknown_commands_in_groups() {
+    let router = CommandRouter::default().with_bot_username(Some("my_bot".to_string()));
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+    let telegram_api = AdminsTelegramApi::default();
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/weather",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(reply, None);
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/weather@my_bot",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert_eq!(
+        reply,
+        Some("Unknown command /weather. Use /help to see the available commands.".to_string())
+    );
+
+    let reply = dispatch_in(
+        &router,
+        -100,
+        7,
+        "/help",
+        &storage,
+        &personas,
+        &telegram_api,
+    )
+    .await;
+    assert!(reply.unwrap().starts_with("Available commands:"));
+}
+
+/// Tests that unknown commands get a hint and commands for other bots are ignored.
+#[tokio::test]
+async fn test_unknown_and_foreign_commands() {
+    let router = CommandRouter::default().with_bot_username(Some("my_bot".to_string()));
+    let storage = InMemoryStorage::new(10);
+    let personas = personas();
+
+    assert_eq!(
+        dispatch(&router, "/weather", &storage, &personas).await,
+        Some("Unknown command /weather. Use /help to see the available commands.".to_string())
+    );
+    assert_eq!(
+        dispatch(&router, "/start@other_bot", &storage, &personas).await,
+        None
+    );
+    assert!(dispatch(&router, "/start@My_Bot", &storage, &personas)
+        .await
+        .is_some());
+}
@@ -0,0 +1,124 @@
+//! Integration tests for the `/health` endpoint.
+
+use actix_web::{http::StatusCode, test, web, App};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use tg_ai_companion::models::health::{BackendHealth, CircuitState, HealthReport};
+use tg_ai_companion::routes::health::init_health_routes;
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
+use tg_ai_companion::services::telegram_api_impl::RealTelegramApi;
+use tg_ai_companion::services::telegram_rate_limiter::{RateLimitedTelegramApi, RateLimits};
+
+/// Mock implementation of ChatApi reporting fixed backend health.
+struct HealthChatApi {
+    backends: Vec<BackendHealth>,
+}
+
+#[async_trait]
+impl ChatApi for HealthChatApi {
+    async fn complete(
+        &self,
+        _messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        Ok(ChatCompletion::default())
+    }
+
+    fn health(&self) -> Vec<BackendHealth> {
+        self.backends.clone()
+    }
+}
+
+fn backend(name: &str, state: CircuitState) -> BackendHealth {
+    BackendHealth {
+        name: name.to_string(),
+        state,
+        ..Default::default()
+    }
+}
+
+/// Requests `GET /health` and returns the status and report.
+async fn get_health(backends: Vec<BackendHealth>) -> (StatusCode, HealthReport) {
+    let chat_api: Arc<dyn ChatApi> = Arc::new(HealthChatApi { backends });
+    let app =
+        test::init_service(App::new().service(init_health_routes(web::Data::from(chat_api), None)))
+            .await;
+
+    let req = test::TestRequest::get().uri("/health").to_request();
+    let resp = test::call_service(&app, req).await;
+    let status = resp.status();
+    let report: HealthReport = test::read_body_json(resp).await;
+
+    (status, report)
+}
+
+/// Tests that healthy backends are reported with `200 OK`.
+#[actix_web::test]
+async fn test_health_ok() {
+    let (status, report) = get_health(vec![
+        backend("primary", CircuitState::Open),
+        backend("fallback", CircuitState::Closed),
+    ])
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report.status, "ok");
+    assert_eq!(report.backends.len(), 2);
+    assert_eq!(report.backends[0].state, CircuitState::Open);
+}
+
+/// Tests that the endpoint answers `503` when every backend is down.
+#[actix_web::test]
+async fn test_health_unavailable() {
+    let (status, report) = get_health(vec![BackendHealth {
+        last_error: Some("Request timed out".to_string()),
+        ..backend("primary", CircuitState::Open)
+    }])
+    .await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(report.status, "unavailable");
+    assert_eq!(
+        report.backends[0].last_error.as_deref(),
+        Some("Request timed out")
+    );
+}
+
+/// Tests that an API without health tracking is reported as healthy.
+#[actix_web::test]
+async fn test_health_without_backends() {
+    let (status, report) = get_health(Vec::new()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report.status, "ok");
+    assert!(report.backends.is_empty());
+    assert_eq!(report.telegram_queue_depth, None);
+}
+
+/// Tests that the number of messages queued for Telegram is reported when the app has a rate limiter.
+#[actix_web::test]
+async fn test_health_reports_telegram_queue_depth() {
+    let chat_api: Arc<dyn ChatApi> = Arc::new(HealthChatApi {
+        backends: Vec::new(),
+    });
+    let rate_limiter = Arc::new(RateLimitedTelegramApi::new(
+        Arc::new(RealTelegramApi::new(
+            "http://127.0.0.1:12345".to_string(),
+            "FAKE_TOKEN".to_string(),
+        )),
+        RateLimits::default(),
+    ));
+    let app = test::init_service(App::new().service(init_health_routes(
+        web::Data::from(chat_api),
+        Some(web::Data::from(rate_limiter)),
+    )))
+    .await;
+
+    let req = test::TestRequest::get().uri("/health").to_request();
+    let report: HealthReport = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(report.status, "ok");
+    assert_eq!(report.telegram_queue_depth, Some(0));
+}
@@ -1,34 +1,296 @@
 use actix_web::{http::StatusCode, test, web, App};
 use async_trait::async_trait;
-use std::error::Error;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 use tg_ai_companion::handlers::telegram::telegram_webhook;
-use tg_ai_companion::models::telegram::{TelegramChat, TelegramMessage, TelegramUpdate};
-use tg_ai_companion::services::chat_api::ChatApi;
-use tg_ai_companion::services::telegram_api::TelegramApi;
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use tg_ai_companion::models::persona::Persona;
+use tg_ai_companion::models::storage::{StoredChat, StoredUser};
+use tg_ai_companion::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    ChatType, EditMessageTextRequest, InputFile, MessageEntity, SendMediaOptions,
+    SendMessageRequest, TelegramChat, TelegramFile, TelegramMessage, TelegramUpdate, TelegramUser,
+};
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
+use tg_ai_companion::services::persona_registry::PersonaRegistry;
+use tg_ai_companion::services::reply_streamer::{ReplyStreamer, PLACEHOLDER_TEXT};
+use tg_ai_companion::services::telegram_api::{TelegramApi, TelegramApiError};
+use tg_ai_companion::services::update_processor::UpdateProcessor;
+use tg_ai_companion::storage::storage_api::Storage;
+use tg_ai_companion::storage::storage_memory_impl::InMemoryStorage;
 
 /// Mock implementation of ChatApi for testing.
-/// Simply echoes back the prompt prefixed with "Echo:".
-struct MockChatApi;
+/// Echoes back the last message prefixed with "Echo:" and records every conversation it receives.
+#[derive(Default)]
+struct MockChatApi {
+    received: Mutex<Vec<Vec<ChatMessage>>>,
+}
 
 #[async_trait]
 impl ChatApi for MockChatApi {
-    async fn call_chat_api(&self, prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
-        Ok(format!("Echo: {}", prompt))
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        self.received.lock().unwrap().push(messages.to_vec());
+        let last = messages
+            .last()
+            .ok_or_else(|| ChatApiError::MalformedResponse("No messages".to_string()))?;
+        Ok(ChatCompletion {
+            content: format!("Echo: {}", last.content),
+            ..Default::default()
+        })
     }
 }
 
+/// A call made to the mocked Telegram API.
+#[derive(Debug, PartialEq)]
+enum Sent {
+    /// `send_message` without a reply: `(chat_id, text)`
+    Message(i64, String),
+    /// `send_message` answering a message: `(chat_id, reply_to_message_id, text)`
+    Reply(i64, i64, String),
+    /// `edit_message_text`: `(chat_id, message_id, text)`
+    Edit(i64, i64, String),
+    /// `send_document_file` with an upload: `(chat_id, file_name, content)`
+    Document(i64, String, String),
+    /// `answer_callback_query`: `(callback_query_id, text)`
+    CallbackAnswer(String, Option<String>),
+}
+
 /// Mock implementation of TelegramApi for testing.
-/// Asserts that the message sent matches expected chat ID and text.
-struct MockTelegramApi;
+/// Forwards every call to a channel, so tests can wait for the background task.
+/// Sent messages get the IDs 100, 101, ...
+/// Chat actions are only recorded, as they repeat while a reply is generated.
+struct MockTelegramApi {
+    sent: mpsc::UnboundedSender<Sent>,
+    next_message_id: AtomicI64,
+    actions: Mutex<Vec<(i64, Option<i64>, ChatAction)>>,
+}
+
+impl MockTelegramApi {
+    fn new(sent: mpsc::UnboundedSender<Sent>) -> Self {
+        Self {
+            sent,
+            next_message_id: AtomicI64::new(100),
+            actions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Builds a newly sent message with the next message ID.
+    fn next_message(&self, chat_id: i64) -> TelegramMessage {
+        message(chat_id, self.next_message_id.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+/// Builds the message Telegram answers a send or an edit with.
+fn message(chat_id: i64, message_id: i64) -> TelegramMessage {
+    TelegramMessage {
+        message_id,
+        chat: TelegramChat {
+            id: chat_id,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
 
 #[async_trait]
 impl TelegramApi for MockTelegramApi {
-    async fn send_telegram_message(&self, chat_id: i64, text: String) -> Result<(), String> {
-        assert_eq!(chat_id, 987654321);
-        assert_eq!(text, "Echo: Hello bot");
-        Ok(())
+    async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        let sent = match request.reply_to_message_id {
+            Some(reply_to_message_id) => {
+                Sent::Reply(request.chat_id, reply_to_message_id, request.text.clone())
+            }
+            None => Sent::Message(request.chat_id, request.text.clone()),
+        };
+        self.sent
+            .send(sent)
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        Ok(self.next_message(request.chat_id))
+    }
+
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.sent
+            .send(Sent::Edit(
+                request.chat_id,
+                request.message_id,
+                request.text.clone(),
+            ))
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        Ok(message(request.chat_id, request.message_id))
+    }
+
+    async fn delete_message(
+        &self,
+        _chat_id: i64,
+        _message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_document_file(
+        &self,
+        chat_id: i64,
+        document: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        let InputFile::Upload { file_name, content } = document else {
+            return Err(TelegramApiError::Http(
+                "Only uploads are mocked".to_string(),
+            ));
+        };
+        let content =
+            String::from_utf8(content).map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        self.sent
+            .send(Sent::Document(chat_id, file_name, content))
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        Ok(self.next_message(chat_id))
+    }
+
+    async fn send_chat_action(
+        &self,
+        chat_id: i64,
+        message_thread_id: Option<i64>,
+        action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        self.actions
+            .lock()
+            .unwrap()
+            .push((chat_id, message_thread_id, action));
+        Ok(true)
+    }
+
+    async fn get_updates(
+        &self,
+        _offset: Option<i64>,
+        _timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_chat_member(
+        &self,
+        _chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        Ok(ChatMember {
+            status: "member".to_string(),
+            user: TelegramUser {
+                id: user_id,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn answer_callback_query(
+        &self,
+        request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        self.sent
+            .send(Sent::CallbackAnswer(
+                request.callback_query_id.clone(),
+                request.text.clone(),
+            ))
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        Ok(true)
+    }
+
+    async fn answer_inline_query(
+        &self,
+        _request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_photo(
+        &self,
+        _chat_id: i64,
+        _photo: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn send_voice(
+        &self,
+        _chat_id: i64,
+        _voice: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        Ok(TelegramFile {
+            file_id: file_id.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn download_file(&self, _file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        Ok(TelegramUser::default())
+    }
+
+    async fn set_my_commands(&self, _commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+}
+
+/// Waits for the next call to the mocked Telegram API.
+async fn next_sent(sent_rx: &mut mpsc::UnboundedReceiver<Sent>) -> Sent {
+    tokio::time::timeout(Duration::from_secs(5), sent_rx.recv())
+        .await
+        .expect("Timed out waiting for the Telegram reply")
+        .expect("The Telegram mock was dropped")
+}
+
+/// Builds the update processor served to the webhook handler.
+/// Its reply streamer only edits the placeholder once, with the final reply.
+fn processor(
+    chat_api: Arc<dyn ChatApi>,
+    telegram_api: Arc<dyn TelegramApi>,
+    storage: Arc<dyn Storage>,
+    personas: PersonaRegistry,
+) -> web::Data<UpdateProcessor> {
+    web::Data::new(UpdateProcessor::new(
+        chat_api,
+        telegram_api,
+        storage,
+        Arc::new(personas),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    ))
+}
+
+/// Builds a Telegram update with a text message for the given chat.
+fn text_update(chat_id: i64, text: &str) -> TelegramUpdate {
+    TelegramUpdate {
+        update_id: 123456789,
+        message: Some(TelegramMessage {
+            message_id: 1,
+            chat: TelegramChat {
+                id: chat_id,
+                ..Default::default()
+            },
+            text: Some(text.to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
     }
 }
 
@@ -37,38 +299,32 @@ impl TelegramApi for MockTelegramApi {
 /// This test verifies that:
 /// - The handler accepts a valid Telegram update JSON payload,
 /// - Returns HTTP 200 OK with body "Processing",
-/// - Internally calls the mocked Chat API and Telegram API (asserted inside mocks).
+/// - Internally calls the mocked Chat API, sends a placeholder via the mocked Telegram API
+///   and edits it with the reply.
 #[actix_web::test]
 async fn test_telegram_webhook_success() {
-    // Wrap mocks in Arc and web::Data for dependency injection
-    let chat_api: web::Data<dyn ChatApi> =
-        web::Data::from(Arc::new(MockChatApi) as Arc<dyn ChatApi>);
-    let telegram_api: web::Data<dyn TelegramApi> =
-        web::Data::from(Arc::new(MockTelegramApi) as Arc<dyn TelegramApi>);
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+
+    // Wrap mocks in Arc and build the update processor for dependency injection
+    let processor = processor(
+        Arc::new(MockChatApi::default()),
+        Arc::new(MockTelegramApi::new(sent_tx)),
+        Arc::new(InMemoryStorage::new(10)),
+        PersonaRegistry::default(),
+    );
 
     // Initialize Actix app with injected dependencies and route
     let app = test::init_service(
         App::new()
-            .app_data(chat_api.clone())
-            .app_data(telegram_api.clone())
+            .app_data(processor)
             .route("/webhook", web::post().to(telegram_webhook)),
     )
     .await;
 
-    // Prepare a sample Telegram update with message text
-    let update = TelegramUpdate {
-        update_id: 123456789,
-        message: Some(TelegramMessage {
-            message_id: 1,
-            chat: TelegramChat { id: 987654321 },
-            text: Some("Hello bot".to_string()),
-        }),
-    };
-
     // Build POST request with JSON body
     let req = test::TestRequest::post()
         .uri("/webhook")
-        .set_json(&update)
+        .set_json(text_update(987654321, "Hello bot"))
         .to_request();
 
     // Send request and get response
@@ -81,4 +337,594 @@ async fn test_telegram_webhook_success() {
     let body = test::read_body(resp).await;
     let body_str = std::str::from_utf8(&body).unwrap();
     assert_eq!(body_str, "Processing");
+
+    // The reply is sent from a background task
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Message(987654321, PLACEHOLDER_TEXT.to_string())
+    );
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Edit(987654321, 100, "Echo: Hello bot".to_string())
+    );
+}
+
+/// Tests that the webhook sends the chat's previous turns to the model,
+/// stores the new turn after the reply and records the chat and the sender.
+#[actix_web::test]
+async fn test_telegram_webhook_sends_conversation_history() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+
+    let mock_chat_api = Arc::new(MockChatApi::default());
+    let storage = Arc::new(InMemoryStorage::new(10));
+    storage
+        .append_messages(
+            42,
+            &[
+                ChatMessage::user("My name is Alice"),
+                ChatMessage::assistant("Nice to meet you, Alice"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(processor(
+                mock_chat_api.clone(),
+                Arc::new(MockTelegramApi::new(sent_tx)),
+                storage.clone(),
+                PersonaRegistry::default(),
+            ))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let mut update = text_update(42, "What is my name?");
+    if let Some(message) = update.message.as_mut() {
+        message.chat.chat_type = Some(ChatType::Private);
+        message.from = Some(TelegramUser {
+            id: 5,
+            first_name: "Alice".to_string(),
+            ..Default::default()
+        });
+    }
+    let req = test::TestRequest::post()
+        .uri("/webhook")
+        .set_json(update)
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    next_sent(&mut sent_rx).await;
+    next_sent(&mut sent_rx).await;
+
+    let received = mock_chat_api.received.lock().unwrap().clone();
+    assert_eq!(
+        received,
+        vec![vec![
+            ChatMessage::user("My name is Alice"),
+            ChatMessage::assistant("Nice to meet you, Alice"),
+            ChatMessage::user("What is my name?"),
+        ]]
+    );
+
+    assert_eq!(
+        storage.recent_messages(42).await.unwrap(),
+        vec![
+            ChatMessage::user("My name is Alice"),
+            ChatMessage::assistant("Nice to meet you, Alice"),
+            ChatMessage::user("What is my name?"),
+            ChatMessage::assistant("Echo: What is my name?"),
+        ]
+    );
+
+    assert_eq!(
+        storage.chat(42),
+        Some(StoredChat {
+            id: 42,
+            chat_type: Some("private".to_string()),
+            title: None,
+        })
+    );
+    assert_eq!(
+        storage.user(5),
+        Some(StoredUser {
+            id: 5,
+            first_name: Some("Alice".to_string()),
+            ..Default::default()
+        })
+    );
+}
+
+/// Tests that `/persona <name>` switches the chat's persona without calling the model,
+/// and that later messages are sent with the persona's system prompt.
+#[actix_web::test]
+async fn test_telegram_webhook_persona_command() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+
+    let mock_chat_api = Arc::new(MockChatApi::default());
+    let storage = Arc::new(InMemoryStorage::new(10));
+    let personas = PersonaRegistry::new(
+        vec![
+            Persona {
+                name: "default".to_string(),
+                ..Default::default()
+            },
+            Persona {
+                name: "pirate".to_string(),
+                system_prompt: "Talk like a pirate.".to_string(),
+                ..Default::default()
+            },
+        ],
+        None,
+    )
+    .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(processor(
+                mock_chat_api.clone(),
+                Arc::new(MockTelegramApi::new(sent_tx)),
+                storage.clone(),
+                personas,
+            ))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    for text in ["/persona@my_bot pirate", "Hello"] {
+        let req = test::TestRequest::post()
+            .uri("/webhook")
+            .set_json(text_update(7, text))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        if text.starts_with("/persona") {
+            assert_eq!(
+                next_sent(&mut sent_rx).await,
+                Sent::Message(7, "Persona switched to pirate.".to_string())
+            );
+        } else {
+            next_sent(&mut sent_rx).await;
+            next_sent(&mut sent_rx).await;
+        }
+    }
+
+    assert_eq!(
+        storage.chat_settings(7).await.unwrap().persona.as_deref(),
+        Some("pirate")
+    );
+
+    let received = mock_chat_api.received.lock().unwrap().clone();
+    assert_eq!(
+        received,
+        vec![vec![
+            ChatMessage::system("Talk like a pirate."),
+            ChatMessage::user("Hello"),
+        ]]
+    );
+}
+
+/// Mock implementation of ChatApi that takes a while to answer.
+struct SlowChatApi;
+
+#[async_trait]
+impl ChatApi for SlowChatApi {
+    async fn complete(
+        &self,
+        _messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        Ok(ChatCompletion {
+            content: "Done".to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Tests that the chat shows "typing…" while the reply is generated,
+/// and that the chat action stops once the reply is delivered.
+#[actix_web::test]
+async fn test_telegram_webhook_shows_typing_until_replied() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+    let telegram_api = Arc::new(MockTelegramApi::new(sent_tx));
+
+    let processor = UpdateProcessor::new(
+        Arc::new(SlowChatApi),
+        telegram_api.clone(),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    )
+    .with_typing_interval(Duration::from_millis(50));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(processor))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/webhook")
+        .set_json(text_update(5, "Hello"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Message(5, PLACEHOLDER_TEXT.to_string())
+    );
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Edit(5, 100, "Done".to_string())
+    );
+
+    let actions = telegram_api.actions.lock().unwrap().clone();
+    assert!(
+        actions.len() >= 2,
+        "Expected repeated actions: {:?}",
+        actions
+    );
+    assert!(actions
+        .iter()
+        .all(|action| *action == (5, None, ChatAction::Typing)));
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(telegram_api.actions.lock().unwrap().len(), actions.len());
+}
+
+/// Tests that "typing…" is shown in the forum topic the message was sent to.
+#[actix_web::test]
+async fn test_telegram_webhook_shows_typing_in_topic() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+    let telegram_api = Arc::new(MockTelegramApi::new(sent_tx));
+
+    let processor = UpdateProcessor::new(
+        Arc::new(SlowChatApi),
+        telegram_api.clone(),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    )
+    .with_typing_interval(Duration::from_millis(50));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(processor))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let mut update = text_update(5, "Hello");
+    let message = update.message.as_mut().unwrap();
+    message.message_thread_id = Some(7);
+    message.is_topic_message = Some(true);
+    let req = test::TestRequest::post()
+        .uri("/webhook")
+        .set_json(update)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    next_sent(&mut sent_rx).await;
+    next_sent(&mut sent_rx).await;
+
+    let actions = telegram_api.actions.lock().unwrap().clone();
+    assert!(!actions.is_empty());
+    assert!(actions
+        .iter()
+        .all(|action| *action == (5, Some(7), ChatAction::Typing)));
+}
+
+/// Tests that in groups only messages mentioning the bot are answered, without the mention
+/// and as a reply to the message.
+#[actix_web::test]
+async fn test_telegram_webhook_group_mention() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+    let mock_chat_api = Arc::new(MockChatApi::default());
+
+    let processor = UpdateProcessor::new(
+        mock_chat_api.clone(),
+        Arc::new(MockTelegramApi::new(sent_tx)),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    )
+    .with_bot_username(Some("my_bot".to_string()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(processor))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    for (message_id, text, entities) in [
+        (1, "Hi all", Vec::new()),
+        (
+            2,
+            "@my_bot Hello",
+            vec![MessageEntity {
+                entity_type: "mention".to_string(),
+                offset: 0,
+                length: 7,
+                ..Default::default()
+            }],
+        ),
+    ] {
+        let update = TelegramUpdate {
+            update_id: message_id,
+            message: Some(TelegramMessage {
+                message_id,
+                chat: TelegramChat {
+                    id: -100,
+                    chat_type: Some(ChatType::Group),
+                    ..Default::default()
+                },
+                text: Some(text.to_string()),
+                entities,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let req = test::TestRequest::post()
+            .uri("/webhook")
+            .set_json(update)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Reply(-100, 2, PLACEHOLDER_TEXT.to_string())
+    );
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Edit(-100, 100, "Echo: Hello".to_string())
+    );
+
+    let received = mock_chat_api.received.lock().unwrap().clone();
+    assert_eq!(received, vec![vec![ChatMessage::user("Hello")]]);
+}
+
+/// Tests that updates the bot does not answer are still acknowledged with `200 OK`,
+/// so Telegram does not redeliver them.
+#[actix_web::test]
+async fn test_telegram_webhook_acknowledges_every_update() {
+    let (sent_tx, _sent_rx) = mpsc::unbounded_channel();
+    let mock_chat_api = Arc::new(MockChatApi::default());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(processor(
+                mock_chat_api.clone(),
+                Arc::new(MockTelegramApi::new(sent_tx)),
+                Arc::new(InMemoryStorage::new(10)),
+                PersonaRegistry::default(),
+            ))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let cases = [
+        // A sticker
+        (
+            r#"{"update_id":1,"message":{"message_id":1,"chat":{"id":5,"type":"private"},"sticker":{"file_id":"x","file_unique_id":"y","type":"regular","width":512,"height":512,"is_animated":false,"is_video":false}}}"#,
+            "Processing",
+        ),
+        (
+            r#"{"update_id":2,"edited_message":{"message_id":1,"chat":{"id":5},"text":"Hi!"}}"#,
+            "Processing",
+        ),
+        (
+            r#"{"update_id":3,"callback_query":{"id":"q1","from":{"id":5,"is_bot":false,"first_name":"Ann"},"data":"x"}}"#,
+            "Processing",
+        ),
+        (
+            r#"{"update_id":4,"inline_query":{"id":"q2","from":{"id":5,"is_bot":false,"first_name":"Ann"},"query":"rust","offset":""}}"#,
+            "Processing",
+        ),
+        // An update type the bot does not know
+        (
+            r#"{"update_id":5,"channel_post":{"message_id":1,"chat":{"id":-100,"type":"channel"},"text":"News"}}"#,
+            "Processing",
+        ),
+        ("not json", "Ignored"),
+    ];
+
+    for (body, expected) in cases {
+        let req = test::TestRequest::post()
+            .uri("/webhook")
+            .insert_header(("Content-Type", "application/json"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{}", body);
+
+        let body = test::read_body(resp).await;
+        assert_eq!(std::str::from_utf8(&body).unwrap(), expected);
+    }
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(mock_chat_api.received.lock().unwrap().is_empty());
+}
+
+/// Tests that a callback query is answered, so the client stops showing its progress indicator.
+#[actix_web::test]
+async fn test_telegram_webhook_answers_callback_query() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(processor(
+                Arc::new(MockChatApi::default()),
+                Arc::new(MockTelegramApi::new(sent_tx)),
+                Arc::new(InMemoryStorage::new(10)),
+                PersonaRegistry::default(),
+            ))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/webhook")
+        .set_json(serde_json::json!({
+            "update_id": 1,
+            "callback_query": {
+                "id": "q1",
+                "from": { "id": 5, "is_bot": false, "first_name": "Ann" },
+                "data": "x"
+            }
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::CallbackAnswer("q1".to_string(), None)
+    );
+}
+
+/// Tests that the chat's history is forgotten when the bot is removed from the chat.
+#[actix_web::test]
+async fn test_telegram_webhook_my_chat_member_removed() {
+    let (sent_tx, _sent_rx) = mpsc::unbounded_channel();
+    let storage = Arc::new(InMemoryStorage::new(10));
+    storage
+        .append_messages(-100, &[ChatMessage::user("Hi")])
+        .await
+        .unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(processor(
+                Arc::new(MockChatApi::default()),
+                Arc::new(MockTelegramApi::new(sent_tx)),
+                storage.clone(),
+                PersonaRegistry::default(),
+            ))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/webhook")
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload(
+            r#"{"update_id":1,"my_chat_member":{
+                "chat":{"id":-100,"type":"group","title":"Friends"},
+                "from":{"id":5,"is_bot":false,"first_name":"Ann"},
+                "date":1700000000,
+                "old_chat_member":{"status":"member","user":{"id":42,"is_bot":true,"first_name":"Bot"}},
+                "new_chat_member":{"status":"kicked","user":{"id":42,"is_bot":true,"first_name":"Bot"},"until_date":0}
+            }}"#,
+        )
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    for _ in 0..50 {
+        if storage.recent_messages(-100).await.unwrap().is_empty() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("The chat's history was not cleared");
+}
+
+/// Tests that the service message announcing a supergroup upgrade moves the chat's stored data.
+#[actix_web::test]
+async fn test_migrate_to_chat_id_moves_stored_chat() {
+    let (sent_tx, _sent_rx) = mpsc::unbounded_channel();
+    let storage = Arc::new(InMemoryStorage::new(10));
+    storage
+        .append_messages(-100, &[ChatMessage::user("Hi")])
+        .await
+        .unwrap();
+
+    let processor = processor(
+        Arc::new(MockChatApi::default()),
+        Arc::new(MockTelegramApi::new(sent_tx)),
+        storage.clone(),
+        PersonaRegistry::default(),
+    );
+    let update: TelegramUpdate = serde_json::from_str(
+        r#"{"update_id":1,"message":{
+            "message_id":9,
+            "chat":{"id":-100,"type":"group","title":"Friends"},
+            "date":1700000000,
+            "migrate_to_chat_id":-1001234
+        }}"#,
+    )
+    .unwrap();
+
+    processor.process_update(update).await;
+
+    assert!(storage.recent_messages(-100).await.unwrap().is_empty());
+    assert_eq!(
+        storage.recent_messages(-1001234).await.unwrap(),
+        vec![ChatMessage::user("Hi")]
+    );
+}
+
+/// Mock implementation of ChatApi whose conversations never fit the model.
+struct FailingChatApi;
+
+#[async_trait]
+impl ChatApi for FailingChatApi {
+    async fn complete(
+        &self,
+        _messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        Err(ChatApiError::ContextLengthExceeded(
+            "maximum context length is 4097 tokens".to_string(),
+        ))
+    }
+}
+
+/// Tests that a failing chat API call is explained to the user.
+#[actix_web::test]
+async fn test_telegram_webhook_chat_api_error() {
+    let (sent_tx, mut sent_rx) = mpsc::unbounded_channel();
+
+    let processor = UpdateProcessor::new(
+        Arc::new(FailingChatApi),
+        Arc::new(MockTelegramApi::new(sent_tx)),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    );
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(processor))
+            .route("/webhook", web::post().to(telegram_webhook)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/webhook")
+        .set_json(text_update(5, "Hello"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    assert_eq!(
+        next_sent(&mut sent_rx).await,
+        Sent::Message(
+            5,
+            ChatApiError::ContextLengthExceeded(String::new())
+                .user_message()
+                .to_string()
+        )
+    );
 }
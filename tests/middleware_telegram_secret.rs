@@ -0,0 +1,71 @@
+use actix_web::{http::StatusCode, middleware::from_fn, test, web, App, HttpResponse};
+use std::sync::Arc;
+
+use tg_ai_companion::middleware::telegram_secret::{
+    verify_secret_token, WebhookSecret, SECRET_TOKEN_HEADER,
+};
+
+/// Sends a webhook request with the given secret header (if any) to a route protected by
+/// `verify_secret_token` and returns the response status.
+async fn webhook_status(secret: Option<&str>, header: Option<&str>) -> StatusCode {
+    let secret = secret.map(|secret| Arc::new(WebhookSecret::new(secret).unwrap()));
+
+    let app = test::init_service(
+        App::new().service(
+            web::scope("/telegram")
+                .wrap(from_fn(move |req, next| {
+                    verify_secret_token(secret.clone(), req, next)
+                }))
+                .route("/webhook", web::post().to(HttpResponse::Ok)),
+        ),
+    )
+    .await;
+
+    let mut req = test::TestRequest::post().uri("/telegram/webhook");
+    if let Some(header) = header {
+        req = req.insert_header((SECRET_TOKEN_HEADER, header));
+    }
+
+    match test::try_call_service(&app, req.to_request()).await {
+        Ok(resp) => resp.status(),
+        Err(e) => e.as_response_error().status_code(),
+    }
+}
+
+/// Tests that a request with the configured secret token is let through.
+#[actix_web::test]
+async fn test_matching_secret_token_is_accepted() {
+    let status = webhook_status(Some("s3cr3t_token-1"), Some("s3cr3t_token-1")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Tests that requests with a wrong or missing secret token are rejected.
+#[actix_web::test]
+async fn test_wrong_or_missing_secret_token_is_rejected() {
+    for header in [Some("wrong"), Some("s3cr3t_token-"), Some(""), None] {
+        let status = webhook_status(Some("s3cr3t_token-1"), header).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "header: {:?}", header);
+    }
+}
+
+/// Tests that every request is let through when no secret is configured.
+#[actix_web::test]
+async fn test_no_secret_configured_accepts_all() {
+    assert_eq!(webhook_status(None, None).await, StatusCode::OK);
+    assert_eq!(webhook_status(None, Some("anything")).await, StatusCode::OK);
+}
+
+/// Tests that secrets Telegram would reject are refused, and that `Debug` hides the token.
+#[actix_web::test]
+async fn test_webhook_secret_validation() {
+    assert!(WebhookSecret::new("").is_err());
+    assert!(WebhookSecret::new("has space").is_err());
+    assert!(WebhookSecret::new("a".repeat(257)).is_err());
+    assert!(WebhookSecret::new("a".repeat(256)).is_ok());
+
+    let secret = WebhookSecret::new("Abc_123-xyz").unwrap();
+    assert_eq!(secret.as_str(), "Abc_123-xyz");
+    assert!(secret.matches(b"Abc_123-xyz"));
+    assert!(!secret.matches(b"abc_123-xyz"));
+    assert!(!format!("{:?}", secret).contains("Abc_123-xyz"));
+}
@@ -0,0 +1,211 @@
+use serde_json::Value;
+
+use tg_ai_companion::models::telegram::{ChatType, TelegramUpdate, TelegramUser};
+use tg_ai_companion::services::group_chat::addressed_prompt;
+
+/// Parses a recorded update and checks that it serializes back to the same JSON.
+fn round_trip(fixture: &str) -> TelegramUpdate {
+    let update: TelegramUpdate = serde_json::from_str(fixture).expect("Invalid fixture");
+
+    let expected: Value = serde_json::from_str(fixture).unwrap();
+    assert_eq!(serde_json::to_value(&update).unwrap(), expected);
+
+    update
+}
+
+/// Tests a private text message with a bot command.
+#[test]
+fn test_private_command_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/private_command.json"));
+    assert_eq!(update.kind(), "message");
+
+    let message = update.message.unwrap();
+    assert_eq!(message.chat.chat_type, Some(ChatType::Private));
+    assert!(!message.chat.is_group());
+    let from = message.from.unwrap();
+    assert_eq!(from.language_code.as_deref(), Some("en"));
+    assert_eq!(message.entities[0].entity_type, "bot_command");
+}
+
+/// Tests a reply to the bot in a forum topic, mentioning it.
+#[test]
+fn test_forum_reply_mention_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/forum_reply_mention.json"));
+
+    let message = update.message.unwrap();
+    assert_eq!(message.message_thread_id, Some(61));
+    assert_eq!(message.chat.title.as_deref(), Some("Rust learners"));
+    assert_eq!(message.chat.is_forum, Some(true));
+    assert!(message.chat.is_group());
+    assert_eq!(
+        message.entities[1].url.as_deref(),
+        Some("https://doc.rust-lang.org/book/")
+    );
+    let replied = message.reply_to_message.as_ref().unwrap();
+    assert_eq!(
+        replied.from.as_ref().unwrap().username.as_deref(),
+        Some("companion_bot")
+    );
+    assert_eq!(
+        addressed_prompt(&message, Some("companion_bot")).as_deref(),
+        Some("see the book, what about 'static?")
+    );
+}
+
+/// Tests a photo of an album with a caption.
+#[test]
+fn test_photo_album_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/photo_album.json"));
+
+    let message = update.message.unwrap();
+    assert_eq!(message.text, None);
+    assert_eq!(message.photo.len(), 3);
+    assert_eq!(message.largest_photo().unwrap().width, 1280);
+    assert_eq!(message.caption.as_deref(), Some("What is on this picture?"));
+    assert_eq!(message.caption_entities[0].entity_type, "bold");
+    assert_eq!(message.media_group_id.as_deref(), Some("13725391480517345"));
+}
+
+/// Tests a voice note.
+#[test]
+fn test_voice_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/voice.json"));
+
+    let voice = update.message.unwrap().voice.unwrap();
+    assert_eq!(voice.duration, 4);
+    assert_eq!(voice.mime_type.as_deref(), Some("audio/ogg"));
+}
+
+/// Tests a document with a thumbnail and a caption.
+#[test]
+fn test_document_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/document.json"));
+
+    let message = update.message.unwrap();
+    let document = message.document.unwrap();
+    assert_eq!(document.file_name.as_deref(), Some("notes.pdf"));
+    assert_eq!(document.file_size, Some(254118));
+    assert_eq!(document.thumbnail.unwrap().height, 320);
+    assert_eq!(message.caption.as_deref(), Some("Summarize this, please"));
+}
+
+/// Tests an edited message.
+#[test]
+fn test_edited_message_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/edited_message.json"));
+    assert_eq!(update.kind(), "edited_message");
+
+    assert_eq!(update.edited_message.unwrap().edit_date, Some(1718900012));
+}
+
+/// Tests a channel post, sent on behalf of the channel.
+#[test]
+fn test_channel_post_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/channel_post.json"));
+    assert_eq!(update.kind(), "channel_post");
+
+    let post = update.channel_post.unwrap();
+    assert_eq!(post.from, None);
+    assert_eq!(post.sender_chat.unwrap().chat_type, Some(ChatType::Channel));
+}
+
+/// Tests a press of an inline keyboard button.
+#[test]
+fn test_callback_query_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/callback_query.json"));
+    assert_eq!(update.kind(), "callback_query");
+
+    let query = update.callback_query.unwrap();
+    assert_eq!(query.data.as_deref(), Some("persona:pirate"));
+    let keyboard = query.message.unwrap().reply_markup.unwrap().inline_keyboard;
+    assert_eq!(keyboard.len(), 2);
+    assert_eq!(
+        keyboard[0][0].callback_data.as_deref(),
+        Some("persona:pirate")
+    );
+    assert_eq!(
+        keyboard[1][0].url.as_deref(),
+        Some("https://example.com/personas")
+    );
+}
+
+/// Tests an inline query.
+#[test]
+fn test_inline_query_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/inline_query.json"));
+    assert_eq!(update.kind(), "inline_query");
+
+    let query = update.inline_query.unwrap();
+    assert_eq!(query.query, "explain borrowing");
+    assert_eq!(query.chat_type.as_deref(), Some("sender"));
+}
+
+/// Tests the bot being removed from a group.
+#[test]
+fn test_my_chat_member_round_trip() {
+    let update = round_trip(include_str!("fixtures/telegram/my_chat_member.json"));
+    assert_eq!(update.kind(), "my_chat_member");
+
+    let change = update.my_chat_member.unwrap();
+    assert!(change.old_chat_member.is_present());
+    assert!(!change.new_chat_member.is_present());
+    assert_eq!(change.new_chat_member.until_date, Some(0));
+}
+
+/// Tests the bot's own user, as returned by `getMe`.
+#[test]
+fn test_get_me_round_trip() {
+    let fixture = include_str!("fixtures/telegram/get_me.json");
+    let user: TelegramUser = serde_json::from_str(fixture).unwrap();
+
+    let expected: Value = serde_json::from_str(fixture).unwrap();
+    assert_eq!(serde_json::to_value(&user).unwrap(), expected);
+    assert_eq!(user.can_join_groups, Some(true));
+}
+
+/// Tests that unknown fields and update types are tolerated.
+#[test]
+fn test_unknown_fields_and_update_types_are_ignored() {
+    let update: TelegramUpdate = serde_json::from_str(
+        r#"{
+            "update_id": 1,
+            "message_reaction": {"chat": {"id": 5, "type": "private"}, "message_id": 3},
+            "some_future_field": [1, 2, 3]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(update.kind(), "unknown");
+
+    let update: TelegramUpdate = serde_json::from_str(
+        r#"{
+            "update_id": 2,
+            "message": {
+                "message_id": 4,
+                "chat": {"id": 5, "type": "private", "accent_color_id": 3},
+                "text": "Hi",
+                "link_preview_options": {"is_disabled": true},
+                "story": {"chat": {"id": 5}, "id": 1}
+            }
+        }"#,
+    )
+    .unwrap();
+    let message = update.message.unwrap();
+    assert_eq!(message.text.as_deref(), Some("Hi"));
+    assert_eq!(message.date, 0);
+}
+
+/// Tests that a chat type unknown to this code does not fail the update.
+#[test]
+fn test_unknown_chat_type() {
+    let update: TelegramUpdate = serde_json::from_str(
+        r#"{
+            "update_id": 3,
+            "message": {"message_id": 5, "chat": {"id": 6, "type": "guild"}, "text": "Hi"}
+        }"#,
+    )
+    .unwrap();
+
+    let message = update.message.unwrap();
+    assert_eq!(message.chat.chat_type, Some(ChatType::Unknown));
+    assert!(!message.chat.is_group());
+}
@@ -0,0 +1,405 @@
+use futures_util::StreamExt;
+use httpmock::{Method::POST, MockServer};
+use serde_json::json;
+use std::time::Duration;
+
+use tg_ai_companion::models::backend::BackendConfig;
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions, ChatUsage};
+use tg_ai_companion::services::anthropic_chat_api::AnthropicChatApi;
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
+use tg_ai_companion::services::failover_chat_api::FailoverChatApi;
+use tg_ai_companion::services::resilient_chat_api::ResilienceConfig;
+
+fn api(server: &MockServer) -> AnthropicChatApi {
+    AnthropicChatApi::new(
+        server.base_url(),
+        "claude-test".to_string(),
+        Some("secret".to_string()),
+    )
+}
+
+/// Tests that `complete` sends a Messages API request and joins the reply's text blocks.
+#[tokio::test]
+async fn test_complete() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/messages")
+            .header("x-api-key", "secret")
+            .header("anthropic-version", "2023-06-01")
+            .json_body(json!({
+                "model": "claude-test",
+                "max_tokens": 1024,
+                "system": "Be brief.\n\nTalk like a pirate.",
+                "messages": [
+                    { "role": "user", "content": "Hello" },
+                    { "role": "assistant", "content": "Ahoy!" },
+                    { "role": "user", "content": "How are you?" }
+                ],
+                "temperature": 0.5,
+                "stop_sequences": ["END"]
+            }));
+        then.status(200).json_body(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-test",
+            "content": [
+                { "type": "text", "text": "Fine, " },
+                { "type": "text", "text": "matey!" }
+            ],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 20, "output_tokens": 4 }
+        }));
+    });
+
+    let messages = [
+        ChatMessage::system("Be brief."),
+        ChatMessage::system("Talk like a pirate."),
+        ChatMessage::user("Hello"),
+        ChatMessage::assistant("Ahoy!"),
+        ChatMessage::user("How are you?"),
+    ];
+    let options = ChatOptions {
+        temperature: Some(0.5),
+        stop: Some(vec!["END".to_string()]),
+        ..Default::default()
+    };
+
+    let completion = api(&server).complete(&messages, &options).await.unwrap();
+
+    mock.assert();
+    assert_eq!(
+        completion,
+        ChatCompletion {
+            content: "Fine, matey!".to_string(),
+            model: Some("claude-test".to_string()),
+            finish_reason: Some("end_turn".to_string()),
+            usage: Some(ChatUsage {
+                prompt_tokens: 20,
+                completion_tokens: 4,
+                total_tokens: 24,
+            }),
+        }
+    );
+}
+
+/// Tests that the options override the model and `max_tokens`, and a version can be set.
+#[tokio::test]
+async fn test_complete_with_overrides() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/messages")
+            .header("anthropic-version", "2024-01-01")
+            .json_body(json!({
+                "model": "claude-other",
+                "max_tokens": 1,
+                "messages": [{ "role": "user", "content": "ping" }]
+            }));
+        then.status(200).json_body(json!({
+            "content": [{ "type": "text", "text": "pong" }],
+            "stop_reason": "max_tokens"
+        }));
+    });
+
+    let options = ChatOptions {
+        model: Some("claude-other".to_string()),
+        max_tokens: Some(1),
+        ..Default::default()
+    };
+
+    let completion = api(&server)
+        .with_version("2024-01-01".to_string())
+        .complete(&[ChatMessage::user("ping")], &options)
+        .await
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(completion.content, "pong");
+    assert_eq!(completion.usage, None);
+}
+
+/// Tests that adjacent messages of the same role are merged, as the roles have to alternate.
+#[tokio::test]
+async fn test_complete_merges_adjacent_roles() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages").json_body_partial(
+            json!({
+                "messages": [
+                    { "role": "user", "content": "Hello\n\nAre you there?" },
+                    { "role": "assistant", "content": "Yes." },
+                    { "role": "user", "content": "Good." }
+                ]
+            })
+            .to_string(),
+        );
+        then.status(200).json_body(json!({
+            "content": [{ "type": "text", "text": "Indeed." }],
+            "stop_reason": "end_turn"
+        }));
+    });
+
+    let messages = [
+        ChatMessage::user("Hello"),
+        ChatMessage::system("Be brief."),
+        ChatMessage::user("Are you there?"),
+        ChatMessage::assistant("Yes."),
+        ChatMessage::user("Good."),
+    ];
+
+    let completion = api(&server)
+        .complete(&messages, &ChatOptions::default())
+        .await
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(completion.content, "Indeed.");
+}
+
+/// Tests that tool messages are rejected instead of being sent as user turns.
+#[tokio::test]
+async fn test_complete_rejects_tool_messages() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        then.status(200);
+    });
+
+    let messages = [
+        ChatMessage::user("What's the weather?"),
+        ChatMessage::tool("call_1", "Sunny"),
+    ];
+
+    let result = api(&server)
+        .complete(&messages, &ChatOptions::default())
+        .await;
+
+    assert!(
+        matches!(&result, Err(ChatApiError::Backend { status: 400, message }) if message.contains("Tool messages")),
+        "{:?}",
+        result
+    );
+    mock.assert_hits(0);
+}
+
+/// Sends a request to a mock answering with the given status and error type and message.
+async fn complete_error(status: u16, error_type: &str, message: &str) -> ChatApiError {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        then.status(status)
+            .header("retry-after", "7")
+            .json_body(json!({
+                "type": "error",
+                "error": { "type": error_type, "message": message }
+            }));
+    });
+
+    api(&server)
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap_err()
+}
+
+/// Tests that the Messages API's error types are classified.
+#[tokio::test]
+async fn test_complete_classifies_errors() {
+    assert!(matches!(
+        complete_error(401, "authentication_error", "invalid x-api-key").await,
+        ChatApiError::Auth(_)
+    ));
+    assert!(matches!(
+        complete_error(
+            400,
+            "invalid_request_error",
+            "prompt is too long: 210000 tokens"
+        )
+        .await,
+        ChatApiError::ContextLengthExceeded(_)
+    ));
+    assert_eq!(
+        complete_error(429, "rate_limit_error", "slow down").await,
+        ChatApiError::RateLimited {
+            retry_after: Some(Duration::from_secs(7)),
+            message: "slow down".to_string(),
+        }
+    );
+    assert_eq!(
+        complete_error(529, "overloaded_error", "Overloaded").await,
+        ChatApiError::Backend {
+            status: 529,
+            message: "Overloaded".to_string(),
+        }
+    );
+}
+
+/// Tests that a refusal without text is reported as filtered.
+#[tokio::test]
+async fn test_complete_refusal() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        then.status(200).json_body(json!({
+            "content": [],
+            "stop_reason": "refusal"
+        }));
+    });
+
+    let result = api(&server)
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(matches!(result, Err(ChatApiError::ContentFiltered(_))));
+}
+
+/// Tests that `complete_stream` yields the text deltas of the event stream.
+#[tokio::test]
+async fn test_complete_stream_yields_fragments() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/messages")
+            .json_body_partial(r#"{"stream": true}"#);
+        then.status(200)
+            .header("Content-Type", "text/event-stream")
+            .body(concat!(
+                "event: message_start\n",
+                "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"content\":[]}}\n\n",
+                "event: content_block_start\n",
+                "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+                "event: ping\n",
+                "data: {\"type\":\"ping\"}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo!\"}}\n\n",
+                "event: content_block_stop\n",
+                "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+                "event: message_delta\n",
+                "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":2}}\n\n",
+                "event: message_stop\n",
+                "data: {\"type\":\"message_stop\"}\n\n",
+                "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"ignored\"}}\n\n"
+            ));
+    });
+
+    let fragments: Vec<String> = api(&server)
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap()
+        .map(|fragment| fragment.unwrap())
+        .collect()
+        .await;
+
+    mock.assert();
+    assert_eq!(fragments, vec!["Hel", "lo!"]);
+}
+
+/// Tests that an `error` event ends the stream with a transient backend error.
+#[tokio::test]
+async fn test_complete_stream_error_event() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/messages");
+        then.status(200).body(concat!(
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "event: error\n",
+            "data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n"
+        ));
+    });
+
+    let items: Vec<Result<String, ChatApiError>> = api(&server)
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    assert_eq!(items[0], Ok("Hel".to_string()));
+    assert_eq!(
+        items[1],
+        Err(ChatApiError::Backend {
+            status: 529,
+            message: "Overloaded".to_string(),
+        })
+    );
+    assert!(items[1].as_ref().unwrap_err().is_transient());
+}
+
+/// Tests that a request naming the Anthropic backend of a backends file is sent there only.
+#[tokio::test]
+async fn test_selected_per_request() {
+    let openai = MockServer::start();
+    let anthropic = MockServer::start();
+
+    let openai_mock = openai.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "From OpenAI" } }]
+        }));
+    });
+    let anthropic_mock = anthropic.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/messages")
+            .header("x-api-key", "anthropic-key");
+        then.status(200).json_body(json!({
+            "content": [{ "type": "text", "text": "From Claude" }]
+        }));
+    });
+
+    let file = json!({
+        "backends": [
+            { "name": "openai", "url": openai.base_url(), "model": "gpt-4o-mini" },
+            { "name": "anthropic", "kind": "anthropic", "url": anthropic.base_url(),
+              "model": "claude-test", "api_key": "anthropic-key", "priority": 1 }
+        ]
+    });
+    let api = FailoverChatApi::from_json(&file.to_string(), ResilienceConfig::default()).unwrap();
+
+    let options = ChatOptions {
+        backend: Some("anthropic".to_string()),
+        ..Default::default()
+    };
+    let completion = api
+        .complete(&[ChatMessage::user("Hello")], &options)
+        .await
+        .unwrap();
+    assert_eq!(completion.content, "From Claude");
+
+    let completion = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(completion.content, "From OpenAI");
+
+    anthropic_mock.assert_hits(1);
+    openai_mock.assert_hits(1);
+}
+
+/// Tests that OpenAI-only settings are rejected on an Anthropic backend instead of being ignored.
+#[test]
+fn test_from_config_rejects_ignored_settings() {
+    let config: BackendConfig = serde_json::from_value(json!({
+        "name": "anthropic",
+        "kind": "anthropic",
+        "model": "claude-test",
+        "auth_header": "api-key",
+        "query": { "api-version": "1" }
+    }))
+    .unwrap();
+
+    let error = AnthropicChatApi::from_config(&config).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Backend anthropic does not support query, auth_header"
+    );
+}
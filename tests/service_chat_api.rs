@@ -1,6 +1,14 @@
+use futures_util::StreamExt;
+use httpmock::{Method::POST, MockServer};
+use serde_json::json;
 use std::error::Error;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
 
-use tg_ai_companion::services::chat_api::ChatApi;
+use tg_ai_companion::models::backend::BackendConfig;
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions, ChatUsage};
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
 use tg_ai_companion::services::chat_api_impl::RealChatApi;
 
 /// Integration test for the `RealChatApi::call_chat_api` method.
@@ -41,3 +49,484 @@ async fn test_call_chat_api() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     Ok(())
 }
+
+/// Tests that `complete` sends every role-tagged message and the set options,
+/// and parses the structured completion.
+#[tokio::test]
+async fn test_complete_sends_messages_and_options() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .header("Authorization", "Bearer secret")
+            .json_body(json!({
+                "model": "override-model",
+                "messages": [
+                    { "role": "system", "content": "Be brief." },
+                    { "role": "user", "content": "Hi" },
+                    { "role": "assistant", "content": "Hello!" },
+                    { "role": "tool", "content": "42", "tool_call_id": "call_1" },
+                    { "role": "user", "content": "Thanks" }
+                ],
+                "temperature": 0.5,
+                "max_tokens": 64
+            }));
+
+        then.status(200).json_body(json!({
+            "model": "override-model",
+            "choices": [{
+                "message": { "role": "assistant", "content": "You're welcome" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 }
+        }));
+    });
+
+    let api = RealChatApi::new(
+        server.base_url(),
+        "default-model".to_string(),
+        Some("secret".to_string()),
+    );
+
+    let messages = [
+        ChatMessage::system("Be brief."),
+        ChatMessage::user("Hi"),
+        ChatMessage::assistant("Hello!"),
+        ChatMessage::tool("call_1", "42"),
+        ChatMessage::user("Thanks"),
+    ];
+    let options = ChatOptions {
+        model: Some("override-model".to_string()),
+        temperature: Some(0.5),
+        max_tokens: Some(64),
+        ..Default::default()
+    };
+
+    let completion = api.complete(&messages, &options).await.unwrap();
+
+    assert_eq!(
+        completion,
+        ChatCompletion {
+            content: "You're welcome".to_string(),
+            model: Some("override-model".to_string()),
+            finish_reason: Some("stop".to_string()),
+            usage: Some(ChatUsage {
+                prompt_tokens: 12,
+                completion_tokens: 3,
+                total_tokens: 15,
+            }),
+        }
+    );
+
+    mock.assert();
+}
+
+/// Tests that `call_chat_api` wraps the prompt into a single user message with the configured model.
+#[tokio::test]
+async fn test_call_chat_api_wraps_prompt() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .json_body(json!({
+                "model": "default-model",
+                "messages": [{ "role": "user", "content": "Hello" }]
+            }));
+
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Hi back!" } }]
+        }));
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None);
+
+    let response = api.call_chat_api("Hello").await.unwrap();
+    assert_eq!(response, "Hi back!");
+
+    mock.assert();
+}
+
+/// Tests that a response without `choices[0].message.content` is reported as an error.
+#[tokio::test]
+async fn test_complete_missing_content() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(200).json_body(json!({ "choices": [] }));
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None);
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(
+        matches!(result, Err(ChatApiError::MalformedResponse(_))),
+        "Expected a malformed response error, got: {:?}",
+        result
+    );
+}
+
+/// Tests that `complete_stream` requests a streamed completion and yields the delta contents
+/// until `[DONE]`, skipping empty deltas.
+#[tokio::test]
+async fn test_complete_stream_yields_fragments() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .json_body(json!({
+                "model": "default-model",
+                "messages": [{ "role": "user", "content": "Hello" }],
+                "stream": true
+            }));
+
+        then.status(200)
+            .header("Content-Type", "text/event-stream")
+            .body(concat!(
+                "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+                ": keep-alive comment\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"lo!\"}}]}\r\n\r\n",
+                "data: [DONE]\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n",
+            ));
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None);
+
+    let stream = api
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap();
+    let fragments: Vec<String> = stream.map(|fragment| fragment.unwrap()).collect().await;
+
+    assert_eq!(fragments, vec!["Hel".to_string(), "lo!".to_string()]);
+
+    mock.assert();
+}
+
+/// Tests that `complete_stream` fails when the API answers with an error status.
+#[tokio::test]
+async fn test_complete_stream_error_status() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(500).body("boom");
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None);
+
+    let result = api
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert_eq!(
+        result.err(),
+        Some(ChatApiError::Backend {
+            status: 500,
+            message: "boom".to_string()
+        })
+    );
+}
+
+/// Sends a request to an API answering with the given status, headers and body,
+/// and returns the error of `complete`.
+async fn complete_error(status: u16, headers: &[(&str, &str)], body: &str) -> ChatApiError {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        let mut then = then.status(status);
+        for (name, value) in headers {
+            then = then.header(*name, *value);
+        }
+        then.body(body);
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None);
+
+    api.complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap_err()
+}
+
+/// Tests that error answers are classified by status and `error.code`.
+#[tokio::test]
+async fn test_complete_classifies_errors() {
+    assert_eq!(
+        complete_error(
+            401,
+            &[],
+            r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#
+        )
+        .await,
+        ChatApiError::Auth("Incorrect API key provided".to_string())
+    );
+
+    assert_eq!(
+        complete_error(
+            429,
+            &[("Retry-After", "20")],
+            r#"{"error":{"message":"Rate limit reached","type":"requests"}}"#
+        )
+        .await,
+        ChatApiError::RateLimited {
+            retry_after: Some(Duration::from_secs(20)),
+            message: "Rate limit reached".to_string()
+        }
+    );
+
+    assert_eq!(
+        complete_error(
+            400,
+            &[],
+            r#"{"error":{"message":"This model's maximum context length is 4097 tokens.","code":"context_length_exceeded"}}"#
+        )
+        .await,
+        ChatApiError::ContextLengthExceeded(
+            "This model's maximum context length is 4097 tokens.".to_string()
+        )
+    );
+
+    assert_eq!(
+        complete_error(
+            400,
+            &[],
+            r#"{"error":{"message":"Your request was rejected","code":"content_policy_violation"}}"#
+        )
+        .await,
+        ChatApiError::ContentFiltered("Your request was rejected".to_string())
+    );
+
+    assert_eq!(
+        complete_error(503, &[], "upstream unavailable").await,
+        ChatApiError::Backend {
+            status: 503,
+            message: "upstream unavailable".to_string()
+        }
+    );
+
+    // Some OpenAI-compatible servers report errors with `200 OK`
+    assert_eq!(
+        complete_error(200, &[], r#"{"error":"model not loaded"}"#).await,
+        ChatApiError::Backend {
+            status: 200,
+            message: "model not loaded".to_string()
+        }
+    );
+
+    assert!(matches!(
+        complete_error(200, &[], "not json").await,
+        ChatApiError::MalformedResponse(_)
+    ));
+}
+
+/// Tests that a reply stopped by the content filter is reported as such.
+#[tokio::test]
+async fn test_complete_content_filtered_reply() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": null }, "finish_reason": "content_filter" }]
+        }));
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None);
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(matches!(result, Err(ChatApiError::ContentFiltered(_))));
+}
+
+/// Tests that an unreachable API is a transport error.
+#[tokio::test]
+async fn test_complete_unreachable() {
+    let api = RealChatApi::new(
+        "http://127.0.0.1:9".to_string(),
+        "default-model".to_string(),
+        None,
+    );
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(matches!(result, Err(ChatApiError::Transport(_))));
+}
+
+/// Tests that an API answering slower than the read timeout is a timeout error.
+#[tokio::test]
+async fn test_complete_read_timeout() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(200)
+            .delay(Duration::from_millis(500))
+            .json_body(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "Too late" } }]
+            }));
+    });
+
+    let api = RealChatApi::new(server.base_url(), "default-model".to_string(), None)
+        .with_timeouts(Duration::from_secs(1), Duration::from_millis(100));
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(matches!(result, Err(ChatApiError::Timeout)), "{:?}", result);
+}
+
+/// Tests that a body stalling after the headers were sent is a timeout error, not a
+/// malformed response, so it is retried like any other timeout.
+#[tokio::test]
+async fn test_complete_body_read_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 4096];
+        let _ = socket.read(&mut request).await;
+        let head =
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 100\r\n\r\n";
+        socket.write_all(head.as_bytes()).await.unwrap();
+        socket.write_all(b"{\"choices\":").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    });
+
+    let api = RealChatApi::new(
+        format!("http://{}", address),
+        "default-model".to_string(),
+        None,
+    )
+    .with_timeouts(Duration::from_secs(1), Duration::from_millis(100));
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(matches!(result, Err(ChatApiError::Timeout)), "{:?}", result);
+}
+
+/// Tests that an Azure OpenAI style backend gets the deployment in the path,
+/// the `api-version` query parameter and the key in the `api-key` header.
+#[tokio::test]
+async fn test_complete_azure_endpoint() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/openai/deployments/gpt-4o-mini/chat/completions")
+            .query_param("api-version", "2024-10-21")
+            .header("api-key", "secret")
+            .matches(|request| {
+                !request
+                    .headers
+                    .iter()
+                    .flatten()
+                    .any(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            });
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Hi from Azure" } }]
+        }));
+    });
+
+    let api = RealChatApi::new(
+        server.base_url(),
+        "gpt-4o-mini".to_string(),
+        Some("secret".to_string()),
+    )
+    .with_path("/openai/deployments/{model}/chat/completions")
+    .with_query(vec![("api-version".to_string(), "2024-10-21".to_string())])
+    .with_auth_header(Some("api-key".to_string()));
+
+    let completion = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(completion.content, "Hi from Azure");
+}
+
+/// Tests that a backend's `path`, `query` and `auth_header` are read from its config,
+/// and the path template uses the model a request overrides.
+#[tokio::test]
+async fn test_from_config_custom_endpoint() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/gateway/llm/other-model/completions")
+            .query_param("tenant", "bots")
+            .header("X-Api-Key", "secret");
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Hi" } }]
+        }));
+    });
+
+    let config: BackendConfig = serde_json::from_value(json!({
+        "name": "gateway",
+        "url": format!("{}/", server.base_url()),
+        "model": "default-model",
+        "api_key": "secret",
+        "path": "gateway/llm/{model}/completions",
+        "query": { "tenant": "bots" },
+        "auth_header": "X-Api-Key"
+    }))
+    .unwrap();
+    let api = RealChatApi::from_config(&config).unwrap();
+
+    let options = ChatOptions {
+        model: Some("other-model".to_string()),
+        ..Default::default()
+    };
+    api.complete(&[ChatMessage::user("Hello")], &options)
+        .await
+        .unwrap();
+
+    mock.assert();
+}
+
+/// Tests that the model is percent-encoded in the path, so it cannot change the endpoint.
+#[tokio::test]
+async fn test_complete_encodes_model_in_path() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/openai/deployments/team%2Fgpt%204o%3Fx%3D1/chat/completions");
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Hi" } }]
+        }));
+    });
+
+    let api = RealChatApi::new(server.base_url(), "team/gpt 4o?x=1".to_string(), None)
+        .with_path("/openai/deployments/{model}/chat/completions");
+
+    api.complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap();
+
+    mock.assert();
+}
+
+/// Tests that an invalid `auth_header` is rejected when the backend is created.
+#[test]
+fn test_from_config_invalid_auth_header() {
+    let config = BackendConfig {
+        name: "azure".to_string(),
+        url: "http://localhost:8080".to_string(),
+        model: "gpt-4o-mini".to_string(),
+        auth_header: Some("api key".to_string()),
+        ..Default::default()
+    };
+
+    assert!(RealChatApi::from_config(&config).is_err());
+}
@@ -0,0 +1,374 @@
+use async_trait::async_trait;
+use httpmock::{Method::POST, MockServer};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tg_ai_companion::models::backend::FailoverStrategy;
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use tg_ai_companion::models::health::CircuitState;
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
+use tg_ai_companion::services::failover_chat_api::{FailoverBackend, FailoverChatApi};
+use tg_ai_companion::services::resilient_chat_api::{ResilienceConfig, ResilientChatApi};
+
+/// Mock implementation of ChatApi answering with scripted results, then with its name.
+/// Records the model override of every request.
+struct NamedChatApi {
+    name: String,
+    results: Mutex<VecDeque<Result<String, ChatApiError>>>,
+    calls: AtomicUsize,
+    models: Mutex<Vec<Option<String>>>,
+}
+
+impl NamedChatApi {
+    fn new(name: &str, results: Vec<Result<String, ChatApiError>>) -> Arc<Self> {
+        Arc::new(Self {
+            name: name.to_string(),
+            results: Mutex::new(results.into()),
+            calls: AtomicUsize::new(0),
+            models: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ChatApi for NamedChatApi {
+    async fn complete(
+        &self,
+        _messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.models.lock().unwrap().push(options.model.clone());
+        let result = self
+            .results
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Ok(self.name.clone()));
+
+        result.map(|content| ChatCompletion {
+            content,
+            ..Default::default()
+        })
+    }
+}
+
+/// Settings without retries, opening the circuit on the first failure.
+fn config() -> ResilienceConfig {
+    ResilienceConfig {
+        max_retries: 0,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+        failure_threshold: 1,
+        open_duration: Duration::from_millis(100),
+    }
+}
+
+fn backend(api: &Arc<NamedChatApi>, priority: u32, weight: u32) -> FailoverBackend {
+    FailoverBackend {
+        api: ResilientChatApi::new(api.name.clone(), api.clone(), config()),
+        priority,
+        weight,
+    }
+}
+
+fn down() -> ChatApiError {
+    ChatApiError::Transport("connection refused".to_string())
+}
+
+async fn ask(api: &FailoverChatApi) -> Result<String, ChatApiError> {
+    api.call_chat_api("Hello").await
+}
+
+/// Tests that a failing backend is skipped for the next one by priority.
+#[tokio::test]
+async fn test_fails_over_by_priority() {
+    let localai = NamedChatApi::new("localai", vec![Err(down())]);
+    let openai = NamedChatApi::new("openai", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&openai, 1, 1), backend(&localai, 0, 1)],
+        FailoverStrategy::Priority,
+    );
+
+    assert_eq!(ask(&api).await.unwrap(), "openai");
+    assert_eq!(localai.calls(), 1);
+
+    // The open circuit skips the failed backend without calling it
+    assert_eq!(ask(&api).await.unwrap(), "openai");
+    assert_eq!(localai.calls(), 1);
+
+    let health = api.health();
+    assert_eq!(health.len(), 2);
+    assert_eq!(health[0].name, "openai");
+    assert_eq!(health[1].state, CircuitState::Open);
+}
+
+/// Tests that a model override is only sent to the first backend, not to the one failed over to.
+#[tokio::test]
+async fn test_model_override_is_not_sent_to_fallback() {
+    let localai = NamedChatApi::new("localai", vec![Err(down())]);
+    let openai = NamedChatApi::new("openai", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&openai, 1, 1), backend(&localai, 0, 1)],
+        FailoverStrategy::Priority,
+    );
+    let options = ChatOptions {
+        model: Some("mistral".to_string()),
+        ..Default::default()
+    };
+
+    let completion = api
+        .complete(&[ChatMessage::user("Hello")], &options)
+        .await
+        .unwrap();
+
+    assert_eq!(completion.content, "openai");
+    assert_eq!(
+        *localai.models.lock().unwrap(),
+        vec![Some("mistral".to_string())]
+    );
+    assert_eq!(*openai.models.lock().unwrap(), vec![None]);
+}
+
+/// Tests that with the weighted strategy the model override only goes to the primary
+/// backend, whichever backend the weights pick first.
+#[tokio::test]
+async fn test_weighted_model_override_only_for_primary() {
+    let localai = NamedChatApi::new("localai", Vec::new());
+    let anthropic = NamedChatApi::new("anthropic", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&localai, 0, 1), backend(&anthropic, 1, 3)],
+        FailoverStrategy::Weighted,
+    );
+    let options = ChatOptions {
+        model: Some("mistral".to_string()),
+        ..Default::default()
+    };
+
+    for _ in 0..4 {
+        api.complete(&[ChatMessage::user("Hello")], &options)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        *localai.models.lock().unwrap(),
+        vec![Some("mistral".to_string())]
+    );
+    assert_eq!(*anthropic.models.lock().unwrap(), vec![None; 3]);
+}
+
+/// Tests that a request naming a backend sends that backend the model override.
+#[tokio::test]
+async fn test_model_override_is_sent_to_named_backend() {
+    let localai = NamedChatApi::new("localai", Vec::new());
+    let openai = NamedChatApi::new("openai", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&localai, 0, 1), backend(&openai, 1, 1)],
+        FailoverStrategy::Priority,
+    );
+    let options = ChatOptions {
+        model: Some("gpt-4o".to_string()),
+        backend: Some("openai".to_string()),
+        ..Default::default()
+    };
+
+    let completion = api
+        .complete(&[ChatMessage::user("Hello")], &options)
+        .await
+        .unwrap();
+
+    assert_eq!(completion.content, "openai");
+    assert_eq!(
+        *openai.models.lock().unwrap(),
+        vec![Some("gpt-4o".to_string())]
+    );
+}
+
+/// Tests that the healthy backend with the lowest priority answers.
+#[tokio::test]
+async fn test_prefers_lowest_priority() {
+    let localai = NamedChatApi::new("localai", Vec::new());
+    let openai = NamedChatApi::new("openai", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&openai, 1, 1), backend(&localai, 0, 1)],
+        FailoverStrategy::Priority,
+    );
+
+    assert_eq!(ask(&api).await.unwrap(), "localai");
+    assert_eq!(openai.calls(), 0);
+}
+
+/// Tests that errors caused by the request are not sent to other backends.
+#[tokio::test]
+async fn test_request_errors_do_not_fail_over() {
+    let localai = NamedChatApi::new(
+        "localai",
+        vec![Err(ChatApiError::ContextLengthExceeded(
+            "too long".to_string(),
+        ))],
+    );
+    let openai = NamedChatApi::new("openai", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&localai, 0, 1), backend(&openai, 1, 1)],
+        FailoverStrategy::Priority,
+    );
+
+    assert!(matches!(
+        ask(&api).await,
+        Err(ChatApiError::ContextLengthExceeded(_))
+    ));
+    assert_eq!(openai.calls(), 0);
+}
+
+/// Tests that the last actual failure is returned when every backend fails.
+#[tokio::test]
+async fn test_all_backends_failing() {
+    let localai = NamedChatApi::new("localai", vec![Err(down())]);
+    let openai = NamedChatApi::new("openai", vec![Err(ChatApiError::Timeout)]);
+    let api = FailoverChatApi::new(
+        vec![backend(&localai, 0, 1), backend(&openai, 1, 1)],
+        FailoverStrategy::Priority,
+    );
+
+    assert_eq!(ask(&api).await, Err(ChatApiError::Timeout));
+
+    // Both circuits are open now; the last real error is not known any more
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Unavailable(_))));
+}
+
+/// Tests that the weighted strategy spreads requests by weight.
+#[tokio::test]
+async fn test_weighted_distribution() {
+    let big = NamedChatApi::new("big", Vec::new());
+    let small = NamedChatApi::new("small", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&big, 0, 3), backend(&small, 0, 1)],
+        FailoverStrategy::Weighted,
+    );
+
+    let mut answers = Vec::new();
+    for _ in 0..8 {
+        answers.push(ask(&api).await.unwrap());
+    }
+
+    assert_eq!(big.calls(), 6);
+    assert_eq!(small.calls(), 2);
+    // Smooth round-robin interleaves the backends
+    assert_ne!(answers[..4], ["big", "big", "big", "small"]);
+}
+
+/// Tests that the weighted strategy skips unhealthy backends.
+#[tokio::test]
+async fn test_weighted_skips_unhealthy_backends() {
+    let big = NamedChatApi::new("big", vec![Err(down())]);
+    let small = NamedChatApi::new("small", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&big, 0, 3), backend(&small, 1, 1)],
+        FailoverStrategy::Weighted,
+    );
+
+    for _ in 0..4 {
+        assert_eq!(ask(&api).await.unwrap(), "small");
+    }
+    assert_eq!(big.calls(), 1);
+}
+
+/// Tests that probes recover an unhealthy backend without requests.
+#[tokio::test]
+async fn test_probes_recover_backends() {
+    let localai = NamedChatApi::new("localai", vec![Err(down()), Err(down())]);
+    let openai = NamedChatApi::new("openai", Vec::new());
+    let api = FailoverChatApi::new(
+        vec![backend(&localai, 0, 1), backend(&openai, 1, 1)],
+        FailoverStrategy::Priority,
+    );
+    assert_eq!(ask(&api).await.unwrap(), "openai");
+
+    // Not due yet
+    api.probe_unhealthy().await;
+    assert_eq!(localai.calls(), 1);
+
+    // The first probe fails, the circuit opens again
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    api.probe_unhealthy().await;
+    assert_eq!(localai.calls(), 2);
+    assert_eq!(api.health()[0].state, CircuitState::Open);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    api.probe_unhealthy().await;
+    assert_eq!(localai.calls(), 3);
+    assert_eq!(api.health()[0].state, CircuitState::Closed);
+
+    assert_eq!(ask(&api).await.unwrap(), "localai");
+}
+
+/// Tests failover between backends of a backends file.
+#[tokio::test]
+async fn test_from_json_fails_over() {
+    let primary = MockServer::start();
+    let fallback = MockServer::start();
+
+    let primary_mock = primary.mock(|when, then| {
+        when.method(POST).path("/v1/chat/completions");
+        then.status(503).body("overloaded");
+    });
+    let fallback_mock = fallback.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .header("Authorization", "Bearer fallback-key")
+            .json_body_partial(r#"{"model": "gpt-4o-mini"}"#);
+        then.status(200).json_body(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "From the fallback" } }]
+        }));
+    });
+
+    let file = json!({
+        "strategy": "priority",
+        "backends": [
+            { "name": "fallback", "url": fallback.base_url(), "model": "gpt-4o-mini",
+              "api_key": "fallback-key", "priority": 1 },
+            { "name": "primary", "url": primary.base_url(), "model": "mistral" }
+        ]
+    });
+    let api = FailoverChatApi::from_json(&file.to_string(), config()).unwrap();
+
+    assert_eq!(ask(&api).await.unwrap(), "From the fallback");
+    primary_mock.assert_hits(1);
+    fallback_mock.assert_hits(1);
+}
+
+/// Tests that invalid backends files are rejected.
+#[test]
+fn test_from_json_rejects_invalid_files() {
+    let invalid = [
+        json!({ "backends": [] }),
+        json!({ "backends": [
+            { "name": "a", "url": "http://localhost:1", "model": "m" },
+            { "name": "a", "url": "http://localhost:2", "model": "m" }
+        ] }),
+        json!({ "backends": [{ "name": "a", "url": "", "model": "m" }] }),
+        json!({ "backends": [
+            { "name": "a", "url": "http://localhost:1", "model": "m",
+              "api_key_env": "FAILOVER_TEST_KEY_THAT_IS_NOT_SET" }
+        ] }),
+        json!({ "strategy": "random", "backends": [
+            { "name": "a", "url": "http://localhost:1", "model": "m" }
+        ] }),
+    ];
+
+    for file in invalid {
+        assert!(
+            FailoverChatApi::from_json(&file.to_string(), config()).is_err(),
+            "{}",
+            file
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use tg_ai_companion::models::telegram::{ChatType, MessageEntity, TelegramChat, TelegramMessage};
+use tg_ai_companion::services::group_chat::addressed_prompt;
+
+/// The bot's username, as returned by `getMe`.
+const BOT: Option<&str> = Some("companion_bot");
+
+/// Builds a group message with the given text and entities.
+fn group_message(text: &str, entities: Vec<MessageEntity>) -> TelegramMessage {
+    TelegramMessage {
+        message_id: 2,
+        chat: TelegramChat {
+            id: -100,
+            chat_type: Some(ChatType::Supergroup),
+            ..Default::default()
+        },
+        text: Some(text.to_string()),
+        entities,
+        ..Default::default()
+    }
+}
+
+/// Builds a `mention` entity.
+fn mention(offset: usize, length: usize) -> MessageEntity {
+    MessageEntity {
+        entity_type: "mention".to_string(),
+        offset,
+        length,
+        ..Default::default()
+    }
+}
+
+/// Tests that every message of a private chat is answered as it is.
+#[test]
+fn test_private_chat_is_always_answered() {
+    let message = TelegramMessage {
+        chat: TelegramChat {
+            id: 5,
+            chat_type: Some(ChatType::Private),
+            ..Default::default()
+        },
+        text: Some(" Hello ".to_string()),
+        ..Default::default()
+    };
+
+    assert_eq!(addressed_prompt(&message, BOT).as_deref(), Some("Hello"));
+}
+
+/// Tests that group messages not addressed to the bot are ignored.
+#[test]
+fn test_group_message_without_mention_is_ignored() {
+    let message = group_message("Hello @someone_else", vec![mention(6, 13)]);
+
+    assert_eq!(addressed_prompt(&message, BOT), None);
+}
+
+/// Tests that a mention of the bot is removed from the prompt.
+#[test]
+fn test_group_mention_is_stripped() {
+    let message = group_message("@companion_bot what is Rust?", vec![mention(0, 14)]);
+    assert_eq!(
+        addressed_prompt(&message, BOT).as_deref(),
+        Some("what is Rust?")
+    );
+
+    let message = group_message("Hey @Companion_Bot, tell a joke", vec![mention(4, 14)]);
+    assert_eq!(
+        addressed_prompt(&message, BOT).as_deref(),
+        Some("Hey, tell a joke")
+    );
+}
+
+/// Tests that entity offsets are counted in UTF-16 code units.
+#[test]
+fn test_group_mention_after_emoji() {
+    // "👋" takes two UTF-16 code units
+    let message = group_message("👋 @companion_bot hi", vec![mention(3, 14)]);
+
+    assert_eq!(addressed_prompt(&message, BOT).as_deref(), Some("👋 hi"));
+}
+
+/// Tests that a message consisting only of the mention is ignored.
+#[test]
+fn test_group_mention_without_prompt_is_ignored() {
+    let message = group_message("@companion_bot", vec![mention(0, 14)]);
+
+    assert_eq!(addressed_prompt(&message, BOT), None);
+}
+
+/// Tests that replies to the bot's messages are answered in groups.
+#[test]
+fn test_group_reply_to_bot_is_answered() {
+    let message: TelegramMessage = serde_json::from_str(
+        r#"{
+            "message_id": 3,
+            "from": {"id": 1, "is_bot": false, "first_name": "Ann"},
+            "chat": {"id": -100, "type": "group", "title": "Friends"},
+            "text": "And why?",
+            "reply_to_message": {
+                "message_id": 2,
+                "from": {"id": 42, "is_bot": true, "first_name": "Companion", "username": "companion_bot"},
+                "chat": {"id": -100, "type": "group"},
+                "text": "Because."
+            }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(addressed_prompt(&message, BOT).as_deref(), Some("And why?"));
+    assert_eq!(addressed_prompt(&message, Some("other_bot")), None);
+}
+
+/// Tests that commands are always passed on in groups; the command router
+/// decides whether they are meant for this bot.
+#[test]
+fn test_group_command_is_answered() {
+    let message = group_message("/help@companion_bot", Vec::new());
+    assert_eq!(
+        addressed_prompt(&message, None).as_deref(),
+        Some("/help@companion_bot")
+    );
+
+    let message = group_message("@companion_bot hi", vec![mention(0, 14)]);
+    assert_eq!(addressed_prompt(&message, None), None);
+}
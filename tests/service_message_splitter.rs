@@ -0,0 +1,128 @@
+use tg_ai_companion::services::message_splitter::{split_message, TELEGRAM_MESSAGE_LIMIT};
+
+/// Asserts that every part fits the limit.
+fn assert_fits(parts: &[String], limit: usize) {
+    for part in parts {
+        assert!(
+            part.chars().count() <= limit,
+            "Part is {} characters long: {:?}",
+            part.chars().count(),
+            part
+        );
+    }
+}
+
+/// Tests that short texts are returned as a single trimmed part and blank texts as none.
+#[test]
+fn test_short_text_is_not_split() {
+    assert_eq!(split_message("  Hello!\n", 100), vec!["Hello!".to_string()]);
+    assert!(split_message(" \n ", 100).is_empty());
+}
+
+/// Tests that texts are split at paragraph breaks first.
+#[test]
+fn test_split_on_paragraphs() {
+    let text = "First paragraph here.\n\nSecond paragraph here.\n\nThird paragraph.";
+
+    let parts = split_message(text, 50);
+
+    assert_eq!(
+        parts,
+        vec![
+            "First paragraph here.\n\nSecond paragraph here.".to_string(),
+            "Third paragraph.".to_string(),
+        ]
+    );
+}
+
+/// Tests that a paragraph without line breaks is split at the end of a sentence.
+#[test]
+fn test_split_on_sentences() {
+    let text = "This is the first sentence. This is the second one. And a third.";
+
+    let parts = split_message(text, 55);
+
+    assert_eq!(
+        parts,
+        vec![
+            "This is the first sentence. This is the second one.".to_string(),
+            "And a third.".to_string(),
+        ]
+    );
+}
+
+/// Tests that a code block cut in two is closed and reopened with its language.
+#[test]
+fn test_split_code_block_is_reopened() {
+    let code: String = (1..=8).map(|i| format!("let x{} = {};\n", i, i)).collect();
+    let text = format!("Here is the code:\n\n```rust\n{}```\n\nDone.", code);
+
+    let parts = split_message(&text, 80);
+
+    assert_fits(&parts, 80);
+    assert!(parts.len() >= 2);
+    for part in &parts {
+        assert_eq!(
+            part.matches("```").count() % 2,
+            0,
+            "Unbalanced fences: {:?}",
+            part
+        );
+    }
+    assert!(parts[0].ends_with("\n```"));
+    assert!(parts[1].starts_with("```rust\n"));
+
+    // No line of code is lost or duplicated
+    let joined = parts.join("\n");
+    for i in 1..=8 {
+        assert_eq!(joined.matches(&format!("let x{} = {};", i, i)).count(), 1);
+    }
+    assert!(joined.ends_with("Done."));
+}
+
+/// Tests that a single overlong word is cut, and multi-byte characters are counted as one.
+#[test]
+fn test_split_long_word_and_unicode() {
+    let text = "ж".repeat(250);
+
+    let parts = split_message(&text, 100);
+
+    assert_fits(&parts, 100);
+    assert_eq!(parts.concat(), text);
+}
+
+/// Tests that a long answer fits Telegram's limit.
+#[test]
+fn test_split_respects_telegram_limit() {
+    let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(300);
+
+    let parts = split_message(&text, TELEGRAM_MESSAGE_LIMIT);
+
+    assert!(parts.len() > 1);
+    assert_fits(&parts, TELEGRAM_MESSAGE_LIMIT);
+}
+
+/// Tests that a code block line too long for a part and without spaces is cut in the middle,
+/// instead of reopening the block after its fence for ever.
+#[test]
+fn test_split_long_line_in_code_block() {
+    let blob = "A".repeat(250);
+    let text = format!("```json\n{}\n```", blob);
+
+    let parts = split_message(&text, 100);
+
+    assert_fits(&parts, 100);
+    assert!(parts.len() >= 3);
+    for part in &parts {
+        assert!(part.starts_with("```json\n"), "{:?}", part);
+        assert!(part.ends_with("\n```"), "{:?}", part);
+    }
+    let joined: String = parts
+        .iter()
+        .map(|part| {
+            part.trim_start_matches("```json\n")
+                .trim_end_matches("\n```")
+        })
+        .collect();
+    assert_eq!(joined, blob);
+}
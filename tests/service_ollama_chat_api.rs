@@ -0,0 +1,261 @@
+use futures_util::StreamExt;
+use httpmock::{Method::POST, MockServer};
+use serde_json::json;
+
+use tg_ai_companion::models::backend::{BackendConfig, BackendKind};
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions, ChatUsage};
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
+use tg_ai_companion::services::failover_chat_api::chat_api_from_config;
+use tg_ai_companion::services::ollama_chat_api::OllamaChatApi;
+
+/// Tests that `complete` sends a native `/api/chat` request and parses the reply.
+#[tokio::test]
+async fn test_complete() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/api/chat").json_body(json!({
+            "model": "llama3.2",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": "Hello" }
+            ],
+            "stream": false,
+            "keep_alive": "10m",
+            "options": { "temperature": 0.5, "num_predict": 64, "num_ctx": 8192 }
+        }));
+        then.status(200).json_body(json!({
+            "model": "llama3.2",
+            "message": { "role": "assistant", "content": "Hi!" },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 3
+        }));
+    });
+
+    let api = OllamaChatApi::new(server.base_url(), "llama3.2".to_string())
+        .with_keep_alive(Some("10m".to_string()))
+        .with_num_ctx(Some(8192));
+    let options = ChatOptions {
+        temperature: Some(0.5),
+        max_tokens: Some(64),
+        ..Default::default()
+    };
+
+    let completion = api
+        .complete(
+            &[ChatMessage::system("Be brief."), ChatMessage::user("Hello")],
+            &options,
+        )
+        .await
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(
+        completion,
+        ChatCompletion {
+            content: "Hi!".to_string(),
+            model: Some("llama3.2".to_string()),
+            finish_reason: Some("stop".to_string()),
+            usage: Some(ChatUsage {
+                prompt_tokens: 12,
+                completion_tokens: 3,
+                total_tokens: 15,
+            }),
+        }
+    );
+}
+
+/// Tests that a numeric `keep_alive` is sent as seconds and options are omitted when unset.
+#[tokio::test]
+async fn test_keep_alive_seconds_and_model_override() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/api/chat").json_body(json!({
+            "model": "mistral",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "stream": false,
+            "keep_alive": -1
+        }));
+        then.status(200).json_body(json!({
+            "message": { "role": "assistant", "content": "Hi!" },
+            "done": true
+        }));
+    });
+
+    let api = OllamaChatApi::new(server.base_url(), "llama3.2".to_string())
+        .with_keep_alive(Some("-1".to_string()));
+    let options = ChatOptions {
+        model: Some("mistral".to_string()),
+        ..Default::default()
+    };
+
+    let completion = api
+        .complete(&[ChatMessage::user("Hello")], &options)
+        .await
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(completion.content, "Hi!");
+    assert_eq!(completion.usage, None);
+}
+
+/// Tests that Ollama's error answers are classified.
+#[tokio::test]
+async fn test_complete_errors() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/api/chat");
+        then.status(404)
+            .json_body(json!({ "error": "model 'llama9' not found" }));
+    });
+
+    let api = OllamaChatApi::new(server.base_url(), "llama9".to_string());
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert_eq!(
+        result,
+        Err(ChatApiError::Backend {
+            status: 404,
+            message: "model 'llama9' not found".to_string(),
+        })
+    );
+}
+
+/// Tests that a reply without a message is malformed.
+#[tokio::test]
+async fn test_complete_missing_message() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/api/chat");
+        then.status(200).json_body(json!({ "done": true }));
+    });
+
+    let api = OllamaChatApi::new(server.base_url(), "llama3.2".to_string());
+
+    let result = api
+        .complete(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await;
+    assert!(matches!(result, Err(ChatApiError::MalformedResponse(_))));
+}
+
+/// Tests that `complete_stream` yields the fragments of the NDJSON stream.
+#[tokio::test]
+async fn test_complete_stream_yields_fragments() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/api/chat")
+            .json_body_partial(r#"{"stream": true}"#);
+        then.status(200)
+            .header("Content-Type", "application/x-ndjson")
+            .body(concat!(
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n",
+                "\n",
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"!\"},\"done\":false}\n",
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"eval_count\":3}"
+            ));
+    });
+
+    let api = OllamaChatApi::new(server.base_url(), "llama3.2".to_string());
+
+    let fragments: Vec<String> = api
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap()
+        .map(|fragment| fragment.unwrap())
+        .collect()
+        .await;
+
+    mock.assert();
+    assert_eq!(fragments, vec!["Hel", "lo", "!"]);
+}
+
+/// Tests that an error line ends the stream with an error.
+#[tokio::test]
+async fn test_complete_stream_error_line() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method(POST).path("/api/chat");
+        then.status(200).body(concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+            "{\"error\":\"model runner has unexpectedly stopped\"}\n"
+        ));
+    });
+
+    let api = OllamaChatApi::new(server.base_url(), "llama3.2".to_string());
+
+    let items: Vec<Result<String, ChatApiError>> = api
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    assert_eq!(items[0], Ok("Hel".to_string()));
+    assert!(matches!(
+        &items[1],
+        Err(ChatApiError::Backend { message, .. }) if message.contains("unexpectedly stopped")
+    ));
+}
+
+/// Tests that an Ollama backend is selected by its `kind` in a backends file.
+#[tokio::test]
+async fn test_selected_by_backend_kind() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST).path("/api/chat").json_body_partial(
+            r#"{"model": "llama3.2", "keep_alive": "5m", "options": {"num_ctx": 4096}}"#,
+        );
+        then.status(200).json_body(json!({
+            "message": { "role": "assistant", "content": "From Ollama" },
+            "done": true
+        }));
+    });
+
+    let config: BackendConfig = serde_json::from_value(json!({
+        "name": "ollama",
+        "kind": "ollama",
+        "url": server.base_url(),
+        "model": "llama3.2",
+        "keep_alive": "5m",
+        "num_ctx": 4096
+    }))
+    .unwrap();
+    assert_eq!(config.kind, BackendKind::Ollama);
+
+    let api = chat_api_from_config(&config).unwrap();
+
+    assert_eq!(api.call_chat_api("Hello").await.unwrap(), "From Ollama");
+    mock.assert();
+}
+
+/// Tests that settings Ollama would ignore, such as an API key or an endpoint path, are rejected.
+#[test]
+fn test_from_config_rejects_ignored_settings() {
+    let config: BackendConfig = serde_json::from_value(json!({
+        "name": "ollama",
+        "kind": "ollama",
+        "url": "http://ollama:11434",
+        "model": "llama3.2",
+        "api_key": "secret",
+        "path": "/v1/chat/completions"
+    }))
+    .unwrap();
+
+    let error = chat_api_from_config(&config).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Backend ollama does not support path, api_key"
+    );
+}
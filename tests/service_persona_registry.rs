@@ -0,0 +1,71 @@
+use tg_ai_companion::models::chat::ChatMessage;
+use tg_ai_companion::services::persona_registry::{PersonaRegistry, DEFAULT_PERSONA_NAME};
+
+/// A personas file with two personas, the second one being the default.
+const PERSONAS_JSON: &str = r#"{
+    "default": "pirate",
+    "personas": [
+        { "name": "assistant", "system_prompt": "You are a helpful assistant." },
+        {
+            "name": "pirate",
+            "description": "Talks like a pirate",
+            "system_prompt": "Talk like a pirate.",
+            "model": "mistral",
+            "temperature": 0.9
+        }
+    ]
+}"#;
+
+/// Tests that the default registry has a single persona without system prompt.
+#[test]
+fn test_default_registry() {
+    let registry = PersonaRegistry::default();
+
+    assert_eq!(registry.personas().len(), 1);
+    assert_eq!(registry.default_persona().name, DEFAULT_PERSONA_NAME);
+    assert_eq!(
+        registry
+            .default_persona()
+            .build_messages(vec![ChatMessage::user("Hi")]),
+        vec![ChatMessage::user("Hi")]
+    );
+}
+
+/// Tests parsing a personas file and resolving personas by name.
+#[test]
+fn test_from_json_and_resolve() {
+    let registry = PersonaRegistry::from_json(PERSONAS_JSON).unwrap();
+
+    assert_eq!(registry.default_persona().name, "pirate");
+    assert_eq!(registry.resolve(Some("assistant")).name, "assistant");
+    assert_eq!(registry.resolve(Some("removed")).name, "pirate");
+    assert_eq!(registry.resolve(None).name, "pirate");
+    assert!(registry.get("removed").is_none());
+
+    let pirate = registry.get("pirate").unwrap();
+    let options = pirate.chat_options();
+    assert_eq!(options.model.as_deref(), Some("mistral"));
+    assert_eq!(options.temperature, Some(0.9));
+    assert_eq!(
+        pirate.build_messages(vec![ChatMessage::user("Hi")]),
+        vec![
+            ChatMessage::system("Talk like a pirate."),
+            ChatMessage::user("Hi")
+        ]
+    );
+}
+
+/// Tests that invalid registries are rejected.
+#[test]
+fn test_invalid_registries() {
+    assert!(PersonaRegistry::from_json(r#"{ "personas": [] }"#).is_err());
+    assert!(
+        PersonaRegistry::from_json(r#"{ "personas": [{ "name": "a" }, { "name": "a" }] }"#)
+            .is_err()
+    );
+    assert!(PersonaRegistry::from_json(
+        r#"{ "default": "missing", "personas": [{ "name": "a" }] }"#
+    )
+    .is_err());
+    assert!(PersonaRegistry::from_json("not json").is_err());
+}
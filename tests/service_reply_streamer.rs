@@ -0,0 +1,303 @@
+use async_trait::async_trait;
+use futures_util::stream;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tg_ai_companion::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    EditMessageTextRequest, InputFile, SendMediaOptions, SendMessageRequest, TelegramChat,
+    TelegramFile, TelegramMessage, TelegramUpdate, TelegramUser,
+};
+use tg_ai_companion::services::chat_api::{ChatApiError, ChatStream};
+use tg_ai_companion::services::reply_streamer::{
+    ReplyStreamer, DOCUMENT_FILE_NAME, DOCUMENT_NOTICE, PLACEHOLDER_TEXT,
+};
+use tg_ai_companion::services::telegram_api::{TelegramApi, TelegramApiError};
+
+/// Mock implementation of TelegramApi recording every call as a readable string.
+#[derive(Default)]
+struct RecordingTelegramApi {
+    calls: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl TelegramApi for RecordingTelegramApi {
+    async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("send {}: {}", request.chat_id, request.text));
+        Ok(message(request.chat_id, 10))
+    }
+
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.calls.lock().unwrap().push(format!(
+            "edit {}/{}: {}",
+            request.chat_id, request.message_id, request.text
+        ));
+        Ok(message(request.chat_id, request.message_id))
+    }
+
+    async fn delete_message(
+        &self,
+        _chat_id: i64,
+        _message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_document_file(
+        &self,
+        chat_id: i64,
+        document: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        if let InputFile::Upload { file_name, content } = document {
+            self.calls.lock().unwrap().push(format!(
+                "document {}: {} ({} bytes)",
+                chat_id,
+                file_name,
+                content.len()
+            ));
+        }
+        Ok(message(chat_id, 11))
+    }
+
+    async fn send_chat_action(
+        &self,
+        _chat_id: i64,
+        _message_thread_id: Option<i64>,
+        _action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn get_updates(
+        &self,
+        _offset: Option<i64>,
+        _timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_chat_member(
+        &self,
+        _chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        Ok(ChatMember {
+            status: "member".to_string(),
+            user: TelegramUser {
+                id: user_id,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn answer_callback_query(
+        &self,
+        _request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn answer_inline_query(
+        &self,
+        _request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_photo(
+        &self,
+        _chat_id: i64,
+        _photo: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn send_voice(
+        &self,
+        _chat_id: i64,
+        _voice: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        Ok(TelegramFile {
+            file_id: file_id.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn download_file(&self, _file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        Ok(TelegramUser::default())
+    }
+
+    async fn set_my_commands(&self, _commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+}
+
+/// Builds the message Telegram answers a send or an edit with.
+fn message(chat_id: i64, message_id: i64) -> TelegramMessage {
+    TelegramMessage {
+        message_id,
+        chat: TelegramChat {
+            id: chat_id,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Builds a stream from fragments; `None` stands for a stream error.
+fn fragments(parts: &[Option<&str>]) -> ChatStream {
+    let items: Vec<Result<String, ChatApiError>> = parts
+        .iter()
+        .map(|part| {
+            part.map(str::to_string)
+                .ok_or_else(|| ChatApiError::Transport("broken".to_string()))
+        })
+        .collect();
+    Box::pin(stream::iter(items))
+}
+
+/// Tests that every fragment triggers an edit when the interval is zero,
+/// and that the final edit shows the complete reply.
+#[tokio::test]
+async fn test_deliver_edits_progressively() {
+    let api = RecordingTelegramApi::default();
+    let streamer = ReplyStreamer::new(Duration::ZERO);
+
+    let reply = streamer
+        .deliver(&api, 1, fragments(&[Some("Hel"), Some("lo")]))
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "Hello");
+    assert_eq!(
+        *api.calls.lock().unwrap(),
+        vec![
+            format!("send 1: {}", PLACEHOLDER_TEXT),
+            "edit 1/10: Hel …".to_string(),
+            "edit 1/10: Hello …".to_string(),
+            "edit 1/10: Hello".to_string(),
+        ]
+    );
+}
+
+/// Tests that intermediate edits are skipped within the edit interval.
+#[tokio::test]
+async fn test_deliver_respects_edit_interval() {
+    let api = RecordingTelegramApi::default();
+    let streamer = ReplyStreamer::new(Duration::from_secs(60));
+
+    streamer
+        .deliver(&api, 1, fragments(&[Some("Hel"), Some("lo")]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *api.calls.lock().unwrap(),
+        vec![
+            format!("send 1: {}", PLACEHOLDER_TEXT),
+            "edit 1/10: Hello".to_string(),
+        ]
+    );
+}
+
+/// Tests that a failing stream keeps the partial reply and tells the user it was interrupted.
+#[tokio::test]
+async fn test_deliver_stream_error() {
+    let api = RecordingTelegramApi::default();
+    let streamer = ReplyStreamer::new(Duration::from_secs(60));
+
+    let result = streamer
+        .deliver(&api, 1, fragments(&[Some("Hel"), None]))
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        api.calls.lock().unwrap().last().unwrap(),
+        "edit 1/10: Hel\n\n⚠️ The answer was interrupted."
+    );
+}
+
+/// Tests that an empty reply is reported as an error.
+#[tokio::test]
+async fn test_deliver_empty_reply() {
+    let api = RecordingTelegramApi::default();
+    let streamer = ReplyStreamer::new(Duration::ZERO);
+
+    let result = streamer.deliver(&api, 1, fragments(&[Some("  ")])).await;
+
+    assert!(result.is_err());
+}
+
+/// Tests that a reply longer than one message fills the placeholder with the first part
+/// and sends the other parts as new messages, in order.
+#[tokio::test]
+async fn test_deliver_splits_long_reply() {
+    let api = RecordingTelegramApi::default();
+    let streamer = ReplyStreamer::new(Duration::from_secs(60)).with_message_limit(40);
+
+    let first = "First paragraph of the answer.";
+    let second = "Second paragraph, also long.";
+    let reply = streamer
+        .deliver(
+            &api,
+            1,
+            fragments(&[Some(first), Some("\n\n"), Some(second)]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(reply, format!("{}\n\n{}", first, second));
+    assert_eq!(
+        *api.calls.lock().unwrap(),
+        vec![
+            format!("send 1: {}", PLACEHOLDER_TEXT),
+            format!("edit 1/10: {}", first),
+            format!("send 1: {}", second),
+        ]
+    );
+}
+
+/// Tests that a reply longer than the document threshold is sent as a Markdown file.
+#[tokio::test]
+async fn test_deliver_long_reply_as_document() {
+    let api = RecordingTelegramApi::default();
+    let streamer = ReplyStreamer::new(Duration::from_secs(60))
+        .with_message_limit(40)
+        .with_document_threshold(Some(50));
+
+    let text = "word ".repeat(20);
+    streamer
+        .deliver(&api, 1, fragments(&[Some(&text)]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *api.calls.lock().unwrap(),
+        vec![
+            format!("send 1: {}", PLACEHOLDER_TEXT),
+            format!("edit 1/10: {}", DOCUMENT_NOTICE),
+            format!("document 1: {} (100 bytes)", DOCUMENT_FILE_NAME),
+        ]
+    );
+}
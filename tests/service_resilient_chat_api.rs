@@ -0,0 +1,251 @@
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use tg_ai_companion::models::health::CircuitState;
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError, ChatStream};
+use tg_ai_companion::services::resilient_chat_api::{ResilienceConfig, ResilientChatApi};
+
+/// Mock implementation of ChatApi answering with scripted results, then with "ok".
+#[derive(Default)]
+struct ScriptedChatApi {
+    results: Mutex<VecDeque<Result<String, ChatApiError>>>,
+    calls: AtomicUsize,
+}
+
+impl ScriptedChatApi {
+    fn new(results: Vec<Result<String, ChatApiError>>) -> Arc<Self> {
+        Arc::new(Self {
+            results: Mutex::new(results.into()),
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    fn next(&self) -> Result<String, ChatApiError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.results
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Ok("ok".to_string()))
+    }
+}
+
+#[async_trait]
+impl ChatApi for ScriptedChatApi {
+    async fn complete(
+        &self,
+        _messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        self.next().map(|content| ChatCompletion {
+            content,
+            ..Default::default()
+        })
+    }
+}
+
+/// Settings fast enough for tests.
+fn config() -> ResilienceConfig {
+    ResilienceConfig {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+        failure_threshold: 3,
+        open_duration: Duration::from_millis(100),
+    }
+}
+
+fn server_error() -> ChatApiError {
+    ChatApiError::Backend {
+        status: 503,
+        message: "overloaded".to_string(),
+    }
+}
+
+async fn ask(api: &ResilientChatApi) -> Result<String, ChatApiError> {
+    api.call_chat_api("Hello").await
+}
+
+/// Tests that transient failures are retried until the request succeeds.
+#[tokio::test]
+async fn test_transient_failures_are_retried() {
+    let inner = ScriptedChatApi::new(vec![Err(ChatApiError::Timeout), Err(server_error())]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+
+    assert_eq!(ask(&api).await.unwrap(), "ok");
+    assert_eq!(inner.calls(), 3);
+    assert_eq!(api.state(), CircuitState::Closed);
+    assert_eq!(api.health()[0].consecutive_failures, 0);
+}
+
+/// Tests that the last error is returned once the retries are used up.
+#[tokio::test]
+async fn test_retries_are_limited() {
+    let inner = ScriptedChatApi::new(vec![
+        Err(ChatApiError::Timeout),
+        Err(ChatApiError::Timeout),
+        Err(server_error()),
+    ]);
+    let api = ResilientChatApi::new(
+        "test",
+        inner.clone(),
+        ResilienceConfig {
+            failure_threshold: 10,
+            ..config()
+        },
+    );
+
+    assert_eq!(ask(&api).await, Err(server_error()));
+    assert_eq!(inner.calls(), 3);
+}
+
+/// Tests that errors caused by the request are neither retried nor counted as failures.
+#[tokio::test]
+async fn test_request_errors_are_not_retried() {
+    let inner = ScriptedChatApi::new(vec![Err(ChatApiError::Auth("bad key".to_string()))]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Auth(_))));
+    assert_eq!(inner.calls(), 1);
+    assert_eq!(api.health()[0].consecutive_failures, 0);
+}
+
+/// Tests that errors caused by the request do not reset the failures counted so far.
+#[tokio::test]
+async fn test_request_errors_keep_failure_count() {
+    let inner = ScriptedChatApi::new(vec![
+        Err(server_error()),
+        Err(server_error()),
+        Err(server_error()),
+        Err(ChatApiError::Auth("bad key".to_string())),
+    ]);
+    let api = ResilientChatApi::new(
+        "test",
+        inner.clone(),
+        ResilienceConfig {
+            failure_threshold: 10,
+            ..config()
+        },
+    );
+
+    assert_eq!(ask(&api).await, Err(server_error()));
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Auth(_))));
+    assert_eq!(api.health()[0].consecutive_failures, 3);
+    assert_eq!(api.state(), CircuitState::Closed);
+}
+
+/// Tests that a rate limit asking to wait longer than the maximal backoff is not waited for.
+#[tokio::test]
+async fn test_long_retry_after_is_not_waited_for() {
+    let limited = ChatApiError::RateLimited {
+        retry_after: Some(Duration::from_secs(60)),
+        message: "slow down".to_string(),
+    };
+    let inner = ScriptedChatApi::new(vec![Err(limited.clone())]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+
+    assert_eq!(ask(&api).await, Err(limited));
+    assert_eq!(inner.calls(), 1);
+}
+
+/// Tests that the circuit opens after repeated failures and then fails fast.
+#[tokio::test]
+async fn test_circuit_opens_and_fails_fast() {
+    let inner = ScriptedChatApi::new(vec![Err(server_error()); 3]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+
+    // The failure opening the circuit is returned as it is
+    assert_eq!(ask(&api).await, Err(server_error()));
+    assert_eq!(inner.calls(), 3);
+    assert_eq!(api.state(), CircuitState::Open);
+
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Unavailable(_))));
+    assert_eq!(inner.calls(), 3);
+
+    let health = api.health();
+    assert_eq!(health[0].name, "test");
+    assert_eq!(health[0].consecutive_failures, 3);
+    assert!(health[0]
+        .last_error
+        .as_deref()
+        .unwrap()
+        .contains("overloaded"));
+}
+
+/// Tests that a half-open circuit closes when the trial request succeeds.
+#[tokio::test]
+async fn test_half_open_circuit_recovers() {
+    let inner = ScriptedChatApi::new(vec![Err(server_error()); 3]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+    let _ = ask(&api).await;
+    assert_eq!(api.state(), CircuitState::Open);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(api.state(), CircuitState::HalfOpen);
+
+    assert_eq!(ask(&api).await.unwrap(), "ok");
+    assert_eq!(api.state(), CircuitState::Closed);
+    assert_eq!(inner.calls(), 4);
+}
+
+/// Tests that a half-open circuit opens again when the trial request fails.
+#[tokio::test]
+async fn test_half_open_circuit_reopens() {
+    let inner = ScriptedChatApi::new(vec![Err(server_error()); 4]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+    let _ = ask(&api).await;
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Unavailable(_))));
+    assert_eq!(inner.calls(), 4);
+    assert_eq!(api.state(), CircuitState::Open);
+}
+
+/// Tests that a half-open circuit stays unhealthy when the trial request is rejected
+/// with an error caused by the request, such as a `401 Unauthorized`.
+#[tokio::test]
+async fn test_half_open_circuit_rejected_trial_reopens() {
+    let inner = ScriptedChatApi::new(vec![
+        Err(server_error()),
+        Err(server_error()),
+        Err(server_error()),
+        Err(ChatApiError::Auth("bad key".to_string())),
+    ]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+    let _ = ask(&api).await;
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(api.state(), CircuitState::HalfOpen);
+
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Auth(_))));
+    assert_eq!(inner.calls(), 4);
+    assert_eq!(api.state(), CircuitState::Open);
+    assert_eq!(api.health()[0].consecutive_failures, 3);
+    assert!(matches!(ask(&api).await, Err(ChatApiError::Unavailable(_))));
+}
+
+/// Tests that streams are retried until they start.
+#[tokio::test]
+async fn test_stream_start_is_retried() {
+    let inner = ScriptedChatApi::new(vec![Err(ChatApiError::Timeout)]);
+    let api = ResilientChatApi::new("test", inner.clone(), config());
+
+    let stream: ChatStream = api
+        .complete_stream(&[ChatMessage::user("Hello")], &ChatOptions::default())
+        .await
+        .unwrap();
+    let fragments: Vec<String> = stream.map(|fragment| fragment.unwrap()).collect().await;
+
+    assert_eq!(fragments, vec!["ok"]);
+    assert_eq!(inner.calls(), 2);
+}
@@ -1,13 +1,22 @@
-use httpmock::{Method::POST, MockServer};
+use httpmock::{
+    Method::{GET, POST},
+    MockServer,
+};
+use std::time::Duration;
 
-use tg_ai_companion::models::telegram::SendMessageRequest;
-use tg_ai_companion::services::telegram_api::TelegramApi;
-use tg_ai_companion::services::telegram_api_impl::RealTelegramApi;
+use tg_ai_companion::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction,
+    EditMessageTextRequest, GetUpdatesRequest, InlineKeyboardButton, InlineKeyboardMarkup,
+    InlineQueryResult, InputFile, InputTextMessageContent, ParseMode, SendMediaOptions,
+    SendMessageRequest, SetMyCommandsRequest, SetWebhookRequest,
+};
+use tg_ai_companion::services::telegram_api::{TelegramApi, TelegramApiError};
+use tg_ai_companion::services::telegram_api_impl::{RealTelegramApi, MAX_RATE_LIMIT_RETRIES};
 
 /// A fake token used for mocking Telegram Bot API requests in tests.
 const FAKE_TOKEN: &str = "FAKE_TOKEN";
 
-/// Tests that `send_telegram_message` returns `Ok(())` on a successful API response.
+/// Tests that `send_telegram_message` returns the sent message on a successful API response.
 #[tokio::test]
 async fn test_send_telegram_message_success() {
     let server = MockServer::start();
@@ -17,25 +26,24 @@ async fn test_send_telegram_message_success() {
 
     let mock = server.mock(|when, then| {
         when.method(POST)
-            .path(&format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
             .json_body_obj(&SendMessageRequest {
                 chat_id,
                 text: text.clone(),
+                parse_mode: None,
+                reply_to_message_id: None,
+                ..Default::default()
             });
 
         then.status(200)
             .header("Content-Type", "application/json")
-            .body(r#"{"ok":true,"result":{}}"#);
+            .body(r#"{"ok":true,"result":{"message_id":77,"chat":{"id":123456}}}"#);
     });
 
-    let api = RealTelegramApi {
-        client: reqwest::Client::new(),
-        base_url: server.base_url(),
-        token: FAKE_TOKEN.to_string(),
-    };
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
 
     let result = api.send_telegram_message(chat_id, text).await;
-    assert!(result.is_ok(), "Expected success, got: {:?}", result);
+    assert_eq!(result.map(|message| message.message_id), Ok(77));
 
     mock.assert();
 }
@@ -50,21 +58,20 @@ async fn test_send_telegram_message_api_error() {
 
     let mock = server.mock(|when, then| {
         when.method(POST)
-            .path(&format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
             .json_body_obj(&SendMessageRequest {
                 chat_id,
                 text: text.clone(),
+                parse_mode: None,
+                reply_to_message_id: None,
+                ..Default::default()
             });
 
         then.status(400)
             .body(r#"{"ok":false,"description":"Bad Request"}"#);
     });
 
-    let api = RealTelegramApi {
-        client: reqwest::Client::new(),
-        base_url: server.base_url(),
-        token: FAKE_TOKEN.to_string(),
-    };
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
 
     let result = api.send_telegram_message(chat_id, text).await;
     assert!(
@@ -79,13 +86,736 @@ async fn test_send_telegram_message_api_error() {
 /// Tests that `send_telegram_message` returns an error when the network request fails (e.g. unreachable host).
 #[tokio::test]
 async fn test_send_telegram_message_network_failure() {
-    let api = RealTelegramApi {
-        client: reqwest::Client::new(),
-        base_url: "http://127.0.0.1:12345".to_string(), // unreachable port
-        token: FAKE_TOKEN.to_string(),
-    };
+    let api = RealTelegramApi::new("http://127.0.0.1:12345".to_string(), FAKE_TOKEN.to_string());
 
     let result = api.send_telegram_message(1, "test".to_string()).await;
 
     assert!(result.is_err(), "Expected network error, got: {:?}", result);
 }
+
+/// Tests that `edit_telegram_message` calls `editMessageText` with the message ID and new text.
+#[tokio::test]
+async fn test_edit_telegram_message_success() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/editMessageText", FAKE_TOKEN))
+            .json_body_obj(&EditMessageTextRequest {
+                chat_id: 5,
+                message_id: 77,
+                text: "Updated".to_string(),
+                parse_mode: None,
+                ..Default::default()
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":77,"chat":{"id":5}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api
+        .edit_telegram_message(5, 77, "Updated".to_string())
+        .await;
+    assert_eq!(result.map(|message| message.message_id), Ok(77));
+
+    mock.assert();
+}
+
+/// Tests that `get_updates` sends the offset and timeout and parses the returned updates.
+#[tokio::test]
+async fn test_get_updates_success() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/getUpdates", FAKE_TOKEN))
+            .json_body_obj(&GetUpdatesRequest {
+                offset: Some(11),
+                timeout: 30,
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":[
+                    {"update_id":11,"message":{"message_id":5,"chat":{"id":42},"text":"Hi"}},
+                    {"update_id":12}
+                ]}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let updates = api.get_updates(Some(11), 30).await.unwrap();
+
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].update_id, 11);
+    let message = updates[0].message.as_ref().expect("Expected a message");
+    assert_eq!(message.chat.id, 42);
+    assert_eq!(message.text.as_deref(), Some("Hi"));
+    assert!(updates[1].message.is_none());
+
+    mock.assert();
+}
+
+/// Tests that an update that cannot be parsed is returned with only its `update_id`,
+/// so polling moves past it instead of failing the whole batch.
+#[tokio::test]
+async fn test_get_updates_skips_malformed_update() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/getUpdates", FAKE_TOKEN));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":[
+                    {"update_id":11,"message":{"message_id":"five","chat":{"id":42}}},
+                    {"update_id":12,"message":{"message_id":6,"chat":{"id":42},"text":"Hi"}}
+                ]}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let updates = api.get_updates(None, 0).await.unwrap();
+
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].update_id, 11);
+    assert!(updates[0].message.is_none());
+    assert_eq!(updates[1].update_id, 12);
+    assert_eq!(
+        updates[1]
+            .message
+            .as_ref()
+            .and_then(|message| message.text.as_deref()),
+        Some("Hi")
+    );
+
+    mock.assert();
+}
+
+/// Tests that `set_webhook` registers the URL together with the secret token.
+#[tokio::test]
+async fn test_set_webhook_with_secret_token() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/setWebhook", FAKE_TOKEN))
+            .json_body_obj(&SetWebhookRequest {
+                url: "https://example.com/telegram/webhook".to_string(),
+                secret_token: Some("s3cr3t".to_string()),
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true,"description":"Webhook was set"}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api
+        .set_webhook("https://example.com/telegram/webhook", Some("s3cr3t"))
+        .await;
+    assert_eq!(result, Ok(()));
+
+    mock.assert();
+}
+
+/// Tests that `set_my_commands` publishes the command list.
+#[tokio::test]
+async fn test_set_my_commands() {
+    let server = MockServer::start();
+
+    let commands = vec![BotCommand {
+        command: "help".to_string(),
+        description: "Show the available commands".to_string(),
+    }];
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/setMyCommands", FAKE_TOKEN))
+            .json_body_obj(&SetMyCommandsRequest {
+                commands: commands.clone(),
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    assert_eq!(api.set_my_commands(&commands).await, Ok(true));
+
+    mock.assert();
+}
+
+/// Tests that `send_document` uploads the file as `multipart/form-data`.
+#[tokio::test]
+async fn test_send_document_success() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendDocument", FAKE_TOKEN))
+            .header_exists("Content-Type")
+            .body_contains(r#"name="chat_id""#)
+            .body_contains(r#"filename="answer.md""#)
+            .body_contains("# Long answer");
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":78,"chat":{"id":123456}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api
+        .send_document(
+            123456,
+            "answer.md".to_string(),
+            b"# Long answer".to_vec(),
+            None,
+        )
+        .await;
+    assert_eq!(result.map(|message| message.message_id), Ok(78));
+
+    mock.assert();
+}
+
+/// Tests that `send_markdown_message` sends the Markdown converted to HTML with `parse_mode`.
+#[tokio::test]
+async fn test_send_markdown_message_as_html() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .json_body_obj(&SendMessageRequest {
+                chat_id: 5,
+                text: "<b>Hi</b> &amp; <code>x &lt; y</code>".to_string(),
+                parse_mode: Some(ParseMode::Html),
+                reply_to_message_id: None,
+                ..Default::default()
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":80,"chat":{"id":5}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api
+        .send_markdown_message(5, "**Hi** & `x < y`".to_string())
+        .await;
+    assert_eq!(result.map(|message| message.message_id), Ok(80));
+
+    mock.assert();
+}
+
+/// Tests that an edit Telegram cannot parse is retried with the Markdown as plain text.
+#[tokio::test]
+async fn test_edit_markdown_message_falls_back_to_plain_text() {
+    let server = MockServer::start();
+
+    let html_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/editMessageText", FAKE_TOKEN))
+            .json_body_obj(&EditMessageTextRequest {
+                chat_id: 5,
+                message_id: 77,
+                text: "<i>Hi</i>".to_string(),
+                parse_mode: Some(ParseMode::Html),
+                ..Default::default()
+            });
+
+        then.status(400).body(
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: can't parse entities: Unsupported start tag"}"#,
+        );
+    });
+    let plain_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/editMessageText", FAKE_TOKEN))
+            .json_body_obj(&EditMessageTextRequest {
+                chat_id: 5,
+                message_id: 77,
+                text: "*Hi*".to_string(),
+                parse_mode: None,
+                ..Default::default()
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":77,"chat":{"id":5}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api.edit_markdown_message(5, 77, "*Hi*".to_string()).await;
+    assert_eq!(result.map(|message| message.message_id), Ok(77));
+
+    html_mock.assert();
+    plain_mock.assert();
+}
+
+/// Tests that `send_chat_action` calls `sendChatAction` with the action's name.
+#[tokio::test]
+async fn test_send_chat_action() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendChatAction", FAKE_TOKEN))
+            .json_body(serde_json::json!({"chat_id": 5, "action": "typing"}));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    assert_eq!(
+        api.send_chat_action(5, None, ChatAction::Typing).await,
+        Ok(true)
+    );
+
+    mock.assert();
+}
+
+/// Tests that `send_reply_message` sends the message with `reply_to_message_id`.
+#[tokio::test]
+async fn test_send_reply_message() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .json_body_obj(&SendMessageRequest {
+                chat_id: -100,
+                text: "Hi".to_string(),
+                parse_mode: None,
+                reply_to_message_id: Some(2),
+                ..Default::default()
+            });
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":81,"chat":{"id":-100}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api.send_reply_message(-100, 2, "Hi".to_string()).await;
+    assert_eq!(result.map(|message| message.message_id), Ok(81));
+
+    mock.assert();
+}
+
+/// Tests that `send_message` sends the message options and returns the parsed message.
+#[tokio::test]
+async fn test_send_message_with_keyboard() {
+    let server = MockServer::start();
+
+    let request = SendMessageRequest {
+        chat_id: 5,
+        text: "Pick one".to_string(),
+        reply_markup: Some(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![InlineKeyboardButton {
+                text: "Yes".to_string(),
+                callback_data: Some("yes".to_string()),
+                ..Default::default()
+            }]],
+        }),
+        ..Default::default()
+    };
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .json_body(serde_json::json!({
+                "chat_id": 5,
+                "text": "Pick one",
+                "reply_markup": {"inline_keyboard": [[{"text": "Yes", "callback_data": "yes"}]]}
+            }));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":{"message_id":90,"date":1700000000,"chat":{"id":5,"type":"private"},"text":"Pick one"}}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let message = api.send_message(&request).await.unwrap();
+    assert_eq!(message.message_id, 90);
+    assert_eq!(message.chat.id, 5);
+    assert_eq!(message.text.as_deref(), Some("Pick one"));
+
+    mock.assert();
+}
+
+/// Tests that `delete_message` calls `deleteMessage` and returns its result.
+#[tokio::test]
+async fn test_delete_message() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/deleteMessage", FAKE_TOKEN))
+            .json_body(serde_json::json!({"chat_id": 5, "message_id": 90}));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    assert_eq!(api.delete_message(5, 90).await, Ok(true));
+
+    mock.assert();
+}
+
+/// Tests that `send_photo` sends a photo by URL with its caption.
+#[tokio::test]
+async fn test_send_photo_by_url() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendPhoto", FAKE_TOKEN))
+            .header_exists("content-type")
+            .body_contains("https://example.com/cat.jpg")
+            .body_contains("A cat");
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":{"message_id":91,"chat":{"id":5},"caption":"A cat","photo":[{"file_id":"p1","file_unique_id":"u1","width":90,"height":90}]}}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let options = SendMediaOptions {
+        caption: Some("A cat".to_string()),
+        ..Default::default()
+    };
+    let message = api
+        .send_photo(
+            5,
+            InputFile::Url("https://example.com/cat.jpg".to_string()),
+            &options,
+        )
+        .await
+        .unwrap();
+    assert_eq!(message.message_id, 91);
+    assert_eq!(message.largest_photo().unwrap().file_id, "p1");
+
+    mock.assert();
+}
+
+/// Tests that `send_voice` uploads the audio as a file.
+#[tokio::test]
+async fn test_send_voice_upload() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendVoice", FAKE_TOKEN))
+            .body_contains("filename=\"note.ogg\"")
+            .body_contains("OggS");
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":{"message_id":92,"chat":{"id":5},"voice":{"file_id":"v1","file_unique_id":"u2","duration":1}}}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let voice = InputFile::Upload {
+        file_name: "note.ogg".to_string(),
+        content: b"OggS".to_vec(),
+    };
+    let message = api
+        .send_voice(5, voice, &SendMediaOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(message.voice.unwrap().file_id, "v1");
+
+    mock.assert();
+}
+
+/// Tests that `get_file` returns the file's path and `download_file` fetches its content.
+#[tokio::test]
+async fn test_get_file_and_download() {
+    let server = MockServer::start();
+
+    let get_file_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/getFile", FAKE_TOKEN))
+            .json_body(serde_json::json!({"file_id": "v1"}));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":true,"result":{"file_id":"v1","file_unique_id":"u2","file_size":4,"file_path":"voice/file_1.oga"}}"#,
+            );
+    });
+    let download_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path(format!("/file/bot{}/voice/file_1.oga", FAKE_TOKEN));
+
+        then.status(200).body("OggS");
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let file = api.get_file("v1").await.unwrap();
+    assert_eq!(file.file_path.as_deref(), Some("voice/file_1.oga"));
+    assert_eq!(api.download_file(&file).await, Ok(b"OggS".to_vec()));
+
+    get_file_mock.assert();
+    download_mock.assert();
+}
+
+/// Tests that `answer_callback_query` and `answer_inline_query` send their answers.
+#[tokio::test]
+async fn test_answer_queries() {
+    let server = MockServer::start();
+
+    let callback_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/answerCallbackQuery", FAKE_TOKEN))
+            .json_body(serde_json::json!({"callback_query_id": "c1", "text": "Done"}));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+    let inline_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/answerInlineQuery", FAKE_TOKEN))
+            .json_body(serde_json::json!({
+                "inline_query_id": "q1",
+                "results": [{
+                    "type": "article",
+                    "id": "1",
+                    "title": "Answer",
+                    "input_message_content": {"message_text": "42"}
+                }]
+            }));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":true}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let callback_answer = AnswerCallbackQueryRequest {
+        callback_query_id: "c1".to_string(),
+        text: Some("Done".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(api.answer_callback_query(&callback_answer).await, Ok(true));
+
+    let inline_answer = AnswerInlineQueryRequest {
+        inline_query_id: "q1".to_string(),
+        results: vec![InlineQueryResult::Article {
+            id: "1".to_string(),
+            title: "Answer".to_string(),
+            input_message_content: InputTextMessageContent {
+                message_text: "42".to_string(),
+                parse_mode: None,
+            },
+            description: None,
+        }],
+        ..Default::default()
+    };
+    assert_eq!(api.answer_inline_query(&inline_answer).await, Ok(true));
+
+    callback_mock.assert();
+    inline_mock.assert();
+}
+
+/// Tests that API errors keep Telegram's `error_code`, `description` and `parameters`.
+#[tokio::test]
+async fn test_api_error_is_structured() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/deleteMessage", FAKE_TOKEN));
+
+        then.status(400)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":false,"error_code":400,"description":"Bad Request: message to delete not found"}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let error = api.delete_message(5, 90).await.unwrap_err();
+    assert_eq!(
+        error,
+        TelegramApiError::Api {
+            error_code: 400,
+            description: "Bad Request: message to delete not found".to_string(),
+            retry_after: None,
+            migrate_to_chat_id: None,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "Telegram API error 400: Bad Request: message to delete not found"
+    );
+
+    mock.assert();
+}
+
+/// Tests that requests refused by flood control are repeated, at most `MAX_RATE_LIMIT_RETRIES` times.
+#[tokio::test]
+async fn test_rate_limited_request_is_retried() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendChatAction", FAKE_TOKEN));
+
+        then.status(429)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 0","parameters":{"retry_after":0}}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api.send_chat_action(5, None, ChatAction::Typing).await;
+    assert_eq!(
+        result,
+        Err(TelegramApiError::Api {
+            error_code: 429,
+            description: "Too Many Requests: retry after 0".to_string(),
+            retry_after: Some(0),
+            migrate_to_chat_id: None,
+        })
+    );
+
+    mock.assert_hits(1 + MAX_RATE_LIMIT_RETRIES as usize);
+}
+
+/// Tests that requests asked to wait longer than `MAX_RETRY_AFTER` fail right away.
+#[tokio::test]
+async fn test_long_retry_after_is_not_waited_for() {
+    let server = MockServer::start();
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/deleteMessage", FAKE_TOKEN));
+
+        then.status(429)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 3600","parameters":{"retry_after":3600}}"#,
+            );
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let error = api.delete_message(5, 90).await.unwrap_err();
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(3600)));
+
+    mock.assert_hits(1);
+}
+
+/// Tests that messages to a group upgraded to a supergroup are sent to the supergroup,
+/// and that later messages go there directly.
+#[tokio::test]
+async fn test_migrated_chat_is_retargeted() {
+    let server = MockServer::start();
+
+    let group_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .json_body(serde_json::json!({"chat_id": -100, "text": "Hi"}));
+
+        then.status(400)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1001234}}"#,
+            );
+    });
+    let supergroup_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendMessage", FAKE_TOKEN))
+            .json_body(serde_json::json!({"chat_id": -1001234, "text": "Hi"}));
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":5,"chat":{"id":-1001234,"type":"supergroup"}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api.send_telegram_message(-100, "Hi".to_string()).await;
+    assert_eq!(result.map(|message| message.message_id), Ok(5));
+    let result = api.send_telegram_message(-100, "Hi".to_string()).await;
+    assert_eq!(result.map(|message| message.message_id), Ok(5));
+
+    assert_eq!(api.current_chat_id(-100), -1001234);
+    group_mock.assert_hits(1);
+    supergroup_mock.assert_hits(2);
+}
+
+/// Tests that uploads are sent again to the supergroup a group was migrated to.
+#[tokio::test]
+async fn test_migrated_chat_is_retargeted_for_uploads() {
+    let server = MockServer::start();
+
+    let group_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendDocument", FAKE_TOKEN))
+            .body_contains("-100\r\n");
+
+        then.status(400)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"{"ok":false,"error_code":400,"description":"Bad Request: group chat was upgraded to a supergroup chat","parameters":{"migrate_to_chat_id":-1001234}}"#,
+            );
+    });
+    let supergroup_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path(format!("/bot{}/sendDocument", FAKE_TOKEN))
+            .body_contains("-1001234\r\n")
+            .body_contains("answer.md");
+
+        then.status(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"ok":true,"result":{"message_id":6,"chat":{"id":-1001234}}}"#);
+    });
+
+    let api = RealTelegramApi::new(server.base_url(), FAKE_TOKEN.to_string());
+
+    let result = api
+        .send_document(-100, "answer.md".to_string(), b"# Hi".to_vec(), None)
+        .await;
+    assert_eq!(result.map(|message| message.message_id), Ok(6));
+
+    group_mock.assert();
+    supergroup_mock.assert();
+}
@@ -0,0 +1,60 @@
+use tg_ai_companion::services::telegram_format::{escape_html, markdown_to_telegram_html};
+
+/// Tests inline formatting: bold, italic, strikethrough, inline code and links.
+#[test]
+fn test_inline_formatting() {
+    assert_eq!(
+        markdown_to_telegram_html(
+            "**bold**, *italic*, ~~gone~~, `code` and [a link](https://example.com?a=1&b=2)"
+        ),
+        "<b>bold</b>, <i>italic</i>, <s>gone</s>, <code>code</code> and \
+         <a href=\"https://example.com?a=1&amp;b=2\">a link</a>"
+    );
+}
+
+/// Tests that special characters and raw HTML from the model are escaped.
+#[test]
+fn test_escaping() {
+    assert_eq!(
+        markdown_to_telegram_html("1 < 2 && <script>alert(1)</script>"),
+        "1 &lt; 2 &amp;&amp; &lt;script&gt;alert(1)&lt;/script&gt;"
+    );
+    assert_eq!(
+        escape_html(r#"<a href="x">"#),
+        "&lt;a href=&quot;x&quot;&gt;"
+    );
+}
+
+/// Tests that code blocks keep their content and language and are not formatted further.
+#[test]
+fn test_code_block() {
+    let markdown =
+        "Example:\n\n```rust\nfn main() {\n    println!(\"**hi** <3\");\n}\n```\n\nDone.";
+
+    assert_eq!(
+        markdown_to_telegram_html(markdown),
+        "Example:\n\n<pre><code class=\"language-rust\">fn main() {\n    \
+         println!(&quot;**hi** &lt;3&quot;);\n}</code></pre>\n\nDone."
+    );
+}
+
+/// Tests that an unclosed code block, as seen while a reply is streamed, is still closed.
+#[test]
+fn test_unclosed_code_block() {
+    assert_eq!(
+        markdown_to_telegram_html("```\nlet x = 1;\n"),
+        "<pre><code>let x = 1;</code></pre>"
+    );
+}
+
+/// Tests headings, lists and block quotes.
+#[test]
+fn test_blocks() {
+    let markdown = "# Title\n\n- one\n- two\n  1. first\n  2. second\n\n> quoted *text*\n\nEnd";
+
+    assert_eq!(
+        markdown_to_telegram_html(markdown),
+        "<b>Title</b>\n\n• one\n• two\n  1. first\n  2. second\n\n\
+         <blockquote>quoted <i>text</i></blockquote>\n\nEnd"
+    );
+}
@@ -0,0 +1,349 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use tg_ai_companion::models::chat::{ChatCompletion, ChatMessage, ChatOptions};
+use tg_ai_companion::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    EditMessageTextRequest, InputFile, SendMediaOptions, SendMessageRequest, TelegramChat,
+    TelegramFile, TelegramMessage, TelegramUpdate, TelegramUser,
+};
+use tg_ai_companion::services::chat_api::{ChatApi, ChatApiError};
+use tg_ai_companion::services::persona_registry::PersonaRegistry;
+use tg_ai_companion::services::reply_streamer::ReplyStreamer;
+use tg_ai_companion::services::telegram_api::{TelegramApi, TelegramApiError};
+use tg_ai_companion::services::telegram_polling::{
+    parse_polling_timeout, TelegramPoller, MAX_POLLING_TIMEOUT_SECS,
+};
+use tg_ai_companion::services::update_processor::UpdateProcessor;
+use tg_ai_companion::storage::storage_memory_impl::InMemoryStorage;
+
+/// Mock implementation of ChatApi echoing back the last message prefixed with "Echo:".
+struct EchoChatApi;
+
+#[async_trait]
+impl ChatApi for EchoChatApi {
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        let last = messages
+            .last()
+            .ok_or_else(|| ChatApiError::MalformedResponse("No messages".to_string()))?;
+        Ok(ChatCompletion {
+            content: format!("Echo: {}", last.content),
+            ..Default::default()
+        })
+    }
+}
+
+/// Mock implementation of ChatApi echoing like [`EchoChatApi`], but slowly for the message "slow".
+struct SlowEchoChatApi;
+
+#[async_trait]
+impl ChatApi for SlowEchoChatApi {
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatCompletion, ChatApiError> {
+        if messages.last().is_some_and(|last| last.content == "slow") {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+        EchoChatApi.complete(messages, options).await
+    }
+}
+
+/// Mock implementation of TelegramApi serving one prepared batch of updates.
+///
+/// Records the `getUpdates` offsets it was called with and forwards the final
+/// text of every edited reply to a channel. When `error` is set, `getUpdates` fails with it.
+struct PollingTelegramApi {
+    updates: Mutex<Option<Vec<TelegramUpdate>>>,
+    offsets: Mutex<Vec<Option<i64>>>,
+    replies: mpsc::UnboundedSender<(i64, String)>,
+    error: Option<TelegramApiError>,
+}
+
+#[async_trait]
+impl TelegramApi for PollingTelegramApi {
+    async fn send_message(
+        &self,
+        _request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage {
+            message_id: 1,
+            ..Default::default()
+        })
+    }
+
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.replies
+            .send((request.chat_id, request.text.clone()))
+            .map_err(|e| TelegramApiError::Http(e.to_string()))?;
+        Ok(TelegramMessage {
+            message_id: request.message_id,
+            ..Default::default()
+        })
+    }
+
+    async fn delete_message(
+        &self,
+        _chat_id: i64,
+        _message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_document_file(
+        &self,
+        _chat_id: i64,
+        _document: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage {
+            message_id: 2,
+            ..Default::default()
+        })
+    }
+
+    async fn send_chat_action(
+        &self,
+        _chat_id: i64,
+        _message_thread_id: Option<i64>,
+        _action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn get_updates(
+        &self,
+        offset: Option<i64>,
+        _timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        self.offsets.lock().unwrap().push(offset);
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        Ok(self.updates.lock().unwrap().take().unwrap_or_default())
+    }
+
+    async fn get_chat_member(
+        &self,
+        _chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        Ok(ChatMember {
+            status: "member".to_string(),
+            user: TelegramUser {
+                id: user_id,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn answer_callback_query(
+        &self,
+        _request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn answer_inline_query(
+        &self,
+        _request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_photo(
+        &self,
+        _chat_id: i64,
+        _photo: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn send_voice(
+        &self,
+        _chat_id: i64,
+        _voice: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage::default())
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        Ok(TelegramFile {
+            file_id: file_id.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn download_file(&self, _file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        Ok(TelegramUser::default())
+    }
+
+    async fn set_my_commands(&self, _commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+}
+
+/// Builds a Telegram update with a text message for the given chat.
+fn text_update(update_id: i64, chat_id: i64, text: &str) -> TelegramUpdate {
+    TelegramUpdate {
+        update_id,
+        message: Some(TelegramMessage {
+            message_id: 1,
+            chat: TelegramChat {
+                id: chat_id,
+                ..Default::default()
+            },
+            text: Some(text.to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Tests that a polled batch is processed, and that the next offset acknowledges
+/// every update of the batch (including updates without text).
+#[tokio::test]
+async fn test_poll_once_processes_updates_and_advances_offset() {
+    let (replies_tx, mut replies_rx) = mpsc::unbounded_channel();
+
+    let telegram_api = Arc::new(PollingTelegramApi {
+        updates: Mutex::new(Some(vec![
+            text_update(7, 42, "Hello"),
+            TelegramUpdate {
+                update_id: 8,
+                message: None,
+                ..Default::default()
+            },
+        ])),
+        offsets: Mutex::new(Vec::new()),
+        replies: replies_tx,
+        error: None,
+    });
+    let processor = Arc::new(UpdateProcessor::new(
+        Arc::new(EchoChatApi),
+        telegram_api.clone(),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    ));
+    let poller = TelegramPoller::new(telegram_api.clone(), processor, 0);
+
+    let offset = poller.poll_once(None).await.unwrap();
+    assert_eq!(offset, Some(9));
+
+    let reply = tokio::time::timeout(Duration::from_secs(5), replies_rx.recv())
+        .await
+        .expect("Timed out waiting for the Telegram reply");
+    assert_eq!(reply, Some((42, "Echo: Hello".to_string())));
+
+    // An empty batch keeps the offset
+    let offset = poller.poll_once(offset).await.unwrap();
+    assert_eq!(offset, Some(9));
+
+    assert_eq!(*telegram_api.offsets.lock().unwrap(), vec![None, Some(9)]);
+}
+
+/// Tests that the updates of one chat are answered in order, even when the first reply is slower.
+#[tokio::test]
+async fn test_poll_once_keeps_chat_order() {
+    let (replies_tx, mut replies_rx) = mpsc::unbounded_channel();
+
+    let telegram_api = Arc::new(PollingTelegramApi {
+        updates: Mutex::new(Some(vec![
+            text_update(7, 42, "slow"),
+            text_update(8, 42, "fast"),
+        ])),
+        offsets: Mutex::new(Vec::new()),
+        replies: replies_tx,
+        error: None,
+    });
+    let processor = Arc::new(UpdateProcessor::new(
+        Arc::new(SlowEchoChatApi),
+        telegram_api.clone(),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    ));
+    let poller = TelegramPoller::new(telegram_api.clone(), processor, 0);
+
+    poller.poll_once(None).await.unwrap();
+
+    for expected in ["Echo: slow", "Echo: fast"] {
+        let reply = tokio::time::timeout(Duration::from_secs(5), replies_rx.recv())
+            .await
+            .expect("Timed out waiting for the Telegram reply");
+        assert_eq!(reply, Some((42, expected.to_string())));
+    }
+}
+
+/// Tests that polling stops with the error when Telegram answers `409 Conflict`
+/// instead of retrying forever.
+#[tokio::test]
+async fn test_run_stops_on_conflict() {
+    let (replies_tx, _replies_rx) = mpsc::unbounded_channel();
+
+    let conflict = TelegramApiError::Api {
+        error_code: 409,
+        description: "Conflict: can't use getUpdates method while webhook is active".to_string(),
+        retry_after: None,
+        migrate_to_chat_id: None,
+    };
+    let telegram_api = Arc::new(PollingTelegramApi {
+        updates: Mutex::new(None),
+        offsets: Mutex::new(Vec::new()),
+        replies: replies_tx,
+        error: Some(conflict.clone()),
+    });
+    let processor = Arc::new(UpdateProcessor::new(
+        Arc::new(EchoChatApi),
+        telegram_api.clone(),
+        Arc::new(InMemoryStorage::new(10)),
+        Arc::new(PersonaRegistry::default()),
+        ReplyStreamer::new(Duration::from_secs(60)),
+    ));
+    let poller = TelegramPoller::new(telegram_api.clone(), processor, 0);
+
+    let result = tokio::time::timeout(Duration::from_secs(5), poller.run())
+        .await
+        .expect("Polling did not stop on the conflict");
+    assert_eq!(result, Err(conflict));
+    assert_eq!(telegram_api.offsets.lock().unwrap().len(), 1);
+}
+
+/// Tests that polling timeouts outside of 1 to 50 seconds are rejected.
+#[test]
+fn test_parse_polling_timeout() {
+    assert_eq!(parse_polling_timeout("1").unwrap(), 1);
+    assert_eq!(parse_polling_timeout(" 30 ").unwrap(), 30);
+    assert_eq!(
+        parse_polling_timeout("50").unwrap(),
+        MAX_POLLING_TIMEOUT_SECS
+    );
+
+    for value in ["0", "51", "-1", "soon"] {
+        let error = parse_polling_timeout(value).unwrap_err();
+        assert!(
+            error.to_string().contains("from 1 to 50"),
+            "{}: {}",
+            value,
+            error
+        );
+    }
+}
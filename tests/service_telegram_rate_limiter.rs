@@ -0,0 +1,472 @@
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use tg_ai_companion::models::telegram::{
+    AnswerCallbackQueryRequest, AnswerInlineQueryRequest, BotCommand, ChatAction, ChatMember,
+    EditMessageTextRequest, InputFile, ParseMode, SendMediaOptions, SendMessageRequest,
+    TelegramFile, TelegramMessage, TelegramUpdate, TelegramUser,
+};
+use tg_ai_companion::services::telegram_api::{TelegramApi, TelegramApiError};
+use tg_ai_companion::services::telegram_rate_limiter::{RateLimitedTelegramApi, RateLimits};
+
+/// Mock implementation of TelegramApi recording when each message was sent.
+///
+/// With a gate, every send waits for a permit, which keeps messages in the queue.
+/// With `reject_html`, messages formatted as HTML are refused as unparsable.
+#[derive(Default)]
+struct TimedTelegramApi {
+    sent: Mutex<Vec<(i64, String, Instant)>>,
+    gate: Option<Semaphore>,
+    reject_html: bool,
+}
+
+impl TimedTelegramApi {
+    /// Returns the texts sent to a chat, in order.
+    fn texts(&self, chat_id: i64) -> Vec<String> {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _, _)| *id == chat_id)
+            .map(|(_, text, _)| text.clone())
+            .collect()
+    }
+
+    /// Returns the time between the first and the last sent message.
+    fn span(&self) -> Duration {
+        let sent = self.sent.lock().unwrap();
+        let first = sent.iter().map(|(_, _, at)| *at).min().unwrap();
+        let last = sent.iter().map(|(_, _, at)| *at).max().unwrap();
+        last - first
+    }
+}
+
+#[async_trait]
+impl TelegramApi for TimedTelegramApi {
+    async fn send_message(
+        &self,
+        request: &SendMessageRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        if let Some(gate) = &self.gate {
+            gate.acquire().await.unwrap().forget();
+        }
+        self.sent
+            .lock()
+            .unwrap()
+            .push((request.chat_id, request.text.clone(), Instant::now()));
+        if self.reject_html && request.parse_mode == Some(ParseMode::Html) {
+            return Err(TelegramApiError::Api {
+                error_code: 400,
+                description: "Bad Request: can't parse entities".to_string(),
+                retry_after: None,
+                migrate_to_chat_id: None,
+            });
+        }
+        Ok(TelegramMessage {
+            message_id: 1,
+            ..Default::default()
+        })
+    }
+
+    async fn edit_message_text(
+        &self,
+        request: &EditMessageTextRequest,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage {
+            message_id: request.message_id,
+            ..Default::default()
+        })
+    }
+
+    async fn delete_message(
+        &self,
+        _chat_id: i64,
+        _message_id: i64,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn send_document_file(
+        &self,
+        _chat_id: i64,
+        _document: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        Ok(TelegramMessage {
+            message_id: 2,
+            ..Default::default()
+        })
+    }
+
+    async fn send_chat_action(
+        &self,
+        chat_id: i64,
+        _message_thread_id: Option<i64>,
+        _action: ChatAction,
+    ) -> Result<bool, TelegramApiError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((chat_id, "typing".to_string(), Instant::now()));
+        Ok(true)
+    }
+
+    async fn get_updates(
+        &self,
+        _offset: Option<i64>,
+        _timeout_secs: u64,
+    ) -> Result<Vec<TelegramUpdate>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_chat_member(
+        &self,
+        _chat_id: i64,
+        user_id: i64,
+    ) -> Result<ChatMember, TelegramApiError> {
+        Ok(ChatMember {
+            status: "member".to_string(),
+            user: TelegramUser {
+                id: user_id,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn answer_callback_query(
+        &self,
+        _request: &AnswerCallbackQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+
+    async fn answer_inline_query(
+        &self,
+        request: &AnswerInlineQueryRequest,
+    ) -> Result<bool, TelegramApiError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((0, request.inline_query_id.clone(), Instant::now()));
+        Ok(true)
+    }
+
+    async fn send_photo(
+        &self,
+        chat_id: i64,
+        _photo: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((chat_id, "photo".to_string(), Instant::now()));
+        Ok(TelegramMessage::default())
+    }
+
+    async fn send_voice(
+        &self,
+        chat_id: i64,
+        _voice: InputFile,
+        _options: &SendMediaOptions,
+    ) -> Result<TelegramMessage, TelegramApiError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((chat_id, "voice".to_string(), Instant::now()));
+        Ok(TelegramMessage::default())
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<TelegramFile, TelegramApiError> {
+        Ok(TelegramFile {
+            file_id: file_id.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn download_file(&self, _file: &TelegramFile) -> Result<Vec<u8>, TelegramApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_me(&self) -> Result<TelegramUser, TelegramApiError> {
+        Ok(TelegramUser::default())
+    }
+
+    async fn set_my_commands(&self, _commands: &[BotCommand]) -> Result<bool, TelegramApiError> {
+        Ok(true)
+    }
+}
+
+/// Limits fast enough for tests; each test slows down the one it checks.
+fn limits() -> RateLimits {
+    RateLimits {
+        global_per_second: 1000.0,
+        chat_per_second: 1000.0,
+        group_per_minute: 60_000.0,
+    }
+}
+
+/// Sends messages to chats concurrently, queued in the given order.
+async fn send_all(api: &RateLimitedTelegramApi, messages: &[(i64, &str)]) {
+    let sends = messages
+        .iter()
+        .map(|(chat_id, text)| api.send_telegram_message(*chat_id, text.to_string()));
+    for result in join_all(sends).await {
+        assert!(result.is_ok());
+    }
+}
+
+/// Tests that the messages of a chat are sent in order and paced by the chat rate.
+#[tokio::test]
+async fn test_chat_messages_are_paced_in_order() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            chat_per_second: 20.0,
+            ..limits()
+        },
+    );
+
+    send_all(&api, &[(5, "1"), (5, "2"), (5, "3")]).await;
+
+    assert_eq!(inner.texts(5), vec!["1", "2", "3"]);
+    assert!(
+        inner.span() >= Duration::from_millis(90),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that a slow chat does not hold back the messages of other chats.
+#[tokio::test]
+async fn test_chats_do_not_wait_for_each_other() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            chat_per_second: 1.0,
+            ..limits()
+        },
+    );
+
+    send_all(&api, &[(1, "a"), (2, "b"), (3, "c")]).await;
+
+    assert!(
+        inner.span() < Duration::from_millis(500),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that groups are paced by the group rate.
+#[tokio::test]
+async fn test_group_messages_use_group_rate() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            group_per_minute: 600.0,
+            ..limits()
+        },
+    );
+
+    send_all(&api, &[(-100, "1"), (-100, "2")]).await;
+
+    assert_eq!(inner.texts(-100), vec!["1", "2"]);
+    assert!(
+        inner.span() >= Duration::from_millis(90),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that the global rate applies across chats.
+#[tokio::test]
+async fn test_global_rate_applies_across_chats() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            global_per_second: 10.0,
+            ..limits()
+        },
+    );
+
+    // The first ten use the burst, the next two wait 100 ms each
+    let messages: Vec<(i64, &str)> = (1..=12).map(|chat_id| (chat_id, "hi")).collect();
+    send_all(&api, &messages).await;
+
+    assert!(
+        inner.span() >= Duration::from_millis(180),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that photos and voice notes share the queue and the pacing of the chat's messages.
+#[tokio::test]
+async fn test_media_is_paced_with_messages() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            chat_per_second: 20.0,
+            ..limits()
+        },
+    );
+    let options = SendMediaOptions::default();
+
+    let (message, photo, voice) = tokio::join!(
+        api.send_telegram_message(5, "1".to_string()),
+        api.send_photo(5, InputFile::FileId("p".to_string()), &options),
+        api.send_voice(5, InputFile::FileId("v".to_string()), &options),
+    );
+    assert!(message.is_ok() && photo.is_ok() && voice.is_ok());
+
+    assert_eq!(inner.texts(5), vec!["1", "photo", "voice"]);
+    assert!(
+        inner.span() >= Duration::from_millis(90),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that inline query answers, which are not sent to a chat, take a global token.
+#[tokio::test]
+async fn test_inline_query_answers_use_global_rate() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            global_per_second: 10.0,
+            ..limits()
+        },
+    );
+
+    // The first ten use the burst, the next two wait 100 ms each
+    let requests: Vec<AnswerInlineQueryRequest> = (1..=12)
+        .map(|id| AnswerInlineQueryRequest {
+            inline_query_id: id.to_string(),
+            ..Default::default()
+        })
+        .collect();
+    let answers = requests
+        .iter()
+        .map(|request| api.answer_inline_query(request));
+    for result in join_all(answers).await {
+        assert_eq!(result, Ok(true));
+    }
+
+    assert_eq!(inner.texts(0).len(), 12);
+    assert!(
+        inner.span() >= Duration::from_millis(180),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that the plain-text fallback of a Markdown message takes its own token.
+#[tokio::test]
+async fn test_markdown_fallback_is_paced() {
+    let inner = Arc::new(TimedTelegramApi {
+        reject_html: true,
+        ..Default::default()
+    });
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            chat_per_second: 20.0,
+            ..limits()
+        },
+    );
+
+    api.send_markdown_message(5, "**Hi**".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(inner.texts(5), vec!["<b>Hi</b>", "**Hi**"]);
+    assert!(
+        inner.span() >= Duration::from_millis(45),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that chat actions are not paced.
+#[tokio::test]
+async fn test_chat_actions_are_not_paced() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(
+        inner.clone(),
+        RateLimits {
+            chat_per_second: 1.0,
+            ..limits()
+        },
+    );
+
+    for _ in 0..3 {
+        api.send_chat_action(5, None, ChatAction::Typing)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(inner.texts(5).len(), 3);
+    assert!(
+        inner.span() < Duration::from_millis(500),
+        "{:?}",
+        inner.span()
+    );
+}
+
+/// Tests that the queue depth counts the messages waiting or being sent.
+#[tokio::test]
+async fn test_queue_depth() {
+    let inner = Arc::new(TimedTelegramApi {
+        gate: Some(Semaphore::new(0)),
+        ..Default::default()
+    });
+    let api = Arc::new(RateLimitedTelegramApi::new(inner.clone(), limits()));
+
+    let mut sends = Vec::new();
+    for (chat_id, text) in [(5, "1"), (5, "2"), (6, "3")] {
+        let api = api.clone();
+        sends.push(tokio::spawn(async move {
+            api.send_telegram_message(chat_id, text.to_string()).await
+        }));
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(api.queue_depth(5), 2);
+    assert_eq!(api.queue_depth(6), 1);
+    assert_eq!(api.queue_depth(7), 0);
+    assert_eq!(api.total_queue_depth(), 3);
+
+    inner.gate.as_ref().unwrap().add_permits(3);
+    for send in sends {
+        send.await.unwrap().unwrap();
+    }
+
+    assert_eq!(api.total_queue_depth(), 0);
+}
+
+/// Tests that the queues of idle chats are dropped once a new chat is queued.
+#[tokio::test]
+async fn test_idle_chat_queues_are_dropped() {
+    let inner = Arc::new(TimedTelegramApi::default());
+    let api = RateLimitedTelegramApi::new(inner.clone(), limits());
+
+    send_all(&api, &[(1, "a"), (2, "b"), (3, "c")]).await;
+    assert_eq!(api.queued_chats(), 3);
+
+    // The chat buckets refill within a few milliseconds at the test rates
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    send_all(&api, &[(4, "d")]).await;
+
+    assert_eq!(api.queued_chats(), 1);
+    assert_eq!(api.total_queue_depth(), 0);
+}
@@ -0,0 +1,152 @@
+use tg_ai_companion::models::chat::ChatMessage;
+use tg_ai_companion::models::storage::{ChatSettings, StoredChat, StoredUser};
+use tg_ai_companion::storage::storage_api::Storage;
+use tg_ai_companion::storage::storage_memory_impl::InMemoryStorage;
+
+/// Tests that a chat without history returns an empty vector.
+#[tokio::test]
+async fn test_recent_messages_empty_for_unknown_chat() {
+    let storage = InMemoryStorage::new(4);
+
+    assert!(storage.recent_messages(1).await.unwrap().is_empty());
+}
+
+/// Tests that only the newest messages are kept once the limit is exceeded.
+#[tokio::test]
+async fn test_append_messages_drops_oldest_messages() {
+    let storage = InMemoryStorage::new(3);
+
+    storage
+        .append_messages(
+            1,
+            &[ChatMessage::user("one"), ChatMessage::assistant("two")],
+        )
+        .await
+        .unwrap();
+    storage
+        .append_messages(
+            1,
+            &[ChatMessage::user("three"), ChatMessage::assistant("four")],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        storage.recent_messages(1).await.unwrap(),
+        vec![
+            ChatMessage::assistant("two"),
+            ChatMessage::user("three"),
+            ChatMessage::assistant("four"),
+        ]
+    );
+}
+
+/// Tests that histories are kept per chat and `clear_messages` only forgets the given chat.
+#[tokio::test]
+async fn test_clear_messages_forgets_only_one_chat() {
+    let storage = InMemoryStorage::new(10);
+
+    storage
+        .append_messages(1, &[ChatMessage::user("first chat")])
+        .await
+        .unwrap();
+    storage
+        .append_messages(2, &[ChatMessage::user("second chat")])
+        .await
+        .unwrap();
+
+    storage.clear_messages(1).await.unwrap();
+
+    assert!(storage.recent_messages(1).await.unwrap().is_empty());
+    assert_eq!(
+        storage.recent_messages(2).await.unwrap(),
+        vec![ChatMessage::user("second chat")]
+    );
+}
+
+/// Tests that users, chats and settings are stored and replaced by ID.
+#[tokio::test]
+async fn test_upserts_and_settings() {
+    let storage = InMemoryStorage::new(10);
+
+    assert_eq!(
+        storage.chat_settings(7).await.unwrap(),
+        ChatSettings::default()
+    );
+
+    let user = StoredUser {
+        id: 5,
+        username: Some("alice".to_string()),
+        ..Default::default()
+    };
+    storage.upsert_user(&user).await.unwrap();
+
+    let chat = StoredChat {
+        id: 7,
+        chat_type: Some("private".to_string()),
+        title: None,
+    };
+    storage.upsert_chat(&chat).await.unwrap();
+
+    let settings = ChatSettings {
+        persona: Some("pirate".to_string()),
+        model: None,
+    };
+    storage.save_chat_settings(7, &settings).await.unwrap();
+
+    assert_eq!(storage.user(5), Some(user));
+    assert_eq!(storage.chat(7), Some(chat));
+    assert_eq!(storage.chat_settings(7).await.unwrap(), settings);
+}
+
+/// Tests that updating a chat without a type or title keeps the stored ones.
+#[tokio::test]
+async fn test_upsert_chat_keeps_unset_fields() {
+    let storage = InMemoryStorage::new(10);
+
+    let chat = StoredChat {
+        id: -100,
+        chat_type: Some("supergroup".to_string()),
+        title: Some("Friends".to_string()),
+    };
+    storage.upsert_chat(&chat).await.unwrap();
+    storage
+        .upsert_chat(&StoredChat {
+            id: -100,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(storage.chat(-100), Some(chat));
+}
+
+/// Tests that migrating a chat moves its history and settings, and that repeating it is harmless.
+#[tokio::test]
+async fn test_migrate_chat_moves_history_and_settings() {
+    let storage = InMemoryStorage::new(10);
+
+    let settings = ChatSettings {
+        persona: Some("pirate".to_string()),
+        model: None,
+    };
+    storage
+        .append_messages(-100, &[ChatMessage::user("before the upgrade")])
+        .await
+        .unwrap();
+    storage.save_chat_settings(-100, &settings).await.unwrap();
+
+    storage.migrate_chat(-100, -1001234).await.unwrap();
+    storage.migrate_chat(-100, -1001234).await.unwrap();
+
+    assert!(storage.recent_messages(-100).await.unwrap().is_empty());
+    assert_eq!(
+        storage.chat_settings(-100).await.unwrap(),
+        ChatSettings::default()
+    );
+    assert_eq!(
+        storage.recent_messages(-1001234).await.unwrap(),
+        vec![ChatMessage::user("before the upgrade")]
+    );
+    assert_eq!(storage.chat_settings(-1001234).await.unwrap(), settings);
+}
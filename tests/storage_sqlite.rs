@@ -0,0 +1,257 @@
+#![cfg(feature = "sqlite")]
+
+use rusqlite::Connection;
+
+use tg_ai_companion::models::chat::ChatMessage;
+use tg_ai_companion::models::storage::{ChatSettings, StoredChat, StoredUser};
+use tg_ai_companion::storage::migrations::{run_migrations, MIGRATIONS};
+use tg_ai_companion::storage::storage_api::Storage;
+use tg_ai_companion::storage::storage_memory_impl::InMemoryStorage;
+use tg_ai_companion::storage::storage_sqlite_impl::SqliteStorage;
+
+/// Tests that migrations bring a new database to the latest version and are idempotent.
+#[test]
+fn test_run_migrations_is_idempotent() {
+    let mut connection = Connection::open_in_memory().unwrap();
+
+    assert_eq!(run_migrations(&mut connection).unwrap(), MIGRATIONS.len());
+    assert_eq!(run_migrations(&mut connection).unwrap(), MIGRATIONS.len());
+
+    let version: i64 = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, MIGRATIONS.len() as i64);
+}
+
+/// Tests that a database created by a newer version of the application is rejected.
+#[test]
+fn test_run_migrations_rejects_newer_schema() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    connection
+        .pragma_update(None, "user_version", MIGRATIONS.len() as i64 + 1)
+        .unwrap();
+
+    assert!(run_migrations(&mut connection).is_err());
+}
+
+/// Tests that messages are returned oldest first, limited to the newest ones,
+/// and keep their role, name and tool call ID.
+#[tokio::test]
+async fn test_recent_messages_returns_newest_in_order() {
+    let storage = SqliteStorage::open_in_memory(3).unwrap();
+
+    storage
+        .append_messages(
+            1,
+            &[
+                ChatMessage::system("be nice"),
+                ChatMessage::user("one"),
+                ChatMessage::assistant("two"),
+            ],
+        )
+        .await
+        .unwrap();
+    storage
+        .append_messages(1, &[ChatMessage::tool("call_1", "three")])
+        .await
+        .unwrap();
+    storage
+        .append_messages(2, &[ChatMessage::user("other chat")])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        storage.recent_messages(1).await.unwrap(),
+        vec![
+            ChatMessage::user("one"),
+            ChatMessage::assistant("two"),
+            ChatMessage::tool("call_1", "three"),
+        ]
+    );
+}
+
+/// Tests that `clear_messages` only forgets the given chat.
+#[tokio::test]
+async fn test_clear_messages() {
+    let storage = SqliteStorage::open_in_memory(10).unwrap();
+
+    storage
+        .append_messages(1, &[ChatMessage::user("first chat")])
+        .await
+        .unwrap();
+    storage
+        .append_messages(2, &[ChatMessage::user("second chat")])
+        .await
+        .unwrap();
+
+    storage.clear_messages(1).await.unwrap();
+
+    assert!(storage.recent_messages(1).await.unwrap().is_empty());
+    assert_eq!(storage.recent_messages(2).await.unwrap().len(), 1);
+}
+
+/// Tests that users and chats can be inserted and updated, and settings round-trip.
+#[tokio::test]
+async fn test_upserts_and_settings() {
+    let storage = SqliteStorage::open_in_memory(10).unwrap();
+
+    let mut user = StoredUser {
+        id: 5,
+        username: Some("alice".to_string()),
+        first_name: Some("Alice".to_string()),
+        last_name: None,
+    };
+    storage.upsert_user(&user).await.unwrap();
+    user.username = Some("alice_renamed".to_string());
+    storage.upsert_user(&user).await.unwrap();
+
+    let chat = StoredChat {
+        id: -100,
+        chat_type: Some("supergroup".to_string()),
+        title: Some("Friends".to_string()),
+    };
+    storage.upsert_chat(&chat).await.unwrap();
+    storage.upsert_chat(&chat).await.unwrap();
+
+    assert_eq!(
+        storage.chat_settings(-100).await.unwrap(),
+        ChatSettings::default()
+    );
+
+    let settings = ChatSettings {
+        persona: Some("pirate".to_string()),
+        model: Some("mistral".to_string()),
+    };
+    storage.save_chat_settings(-100, &settings).await.unwrap();
+    assert_eq!(storage.chat_settings(-100).await.unwrap(), settings);
+
+    let updated = ChatSettings {
+        persona: None,
+        model: Some("llama".to_string()),
+    };
+    storage.save_chat_settings(-100, &updated).await.unwrap();
+    assert_eq!(storage.chat_settings(-100).await.unwrap(), updated);
+}
+
+/// Tests that data survives reopening a database file.
+#[tokio::test]
+async fn test_data_persists_across_reopen() {
+    let path = std::env::temp_dir().join(format!("tg_ai_companion_{}.db", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    {
+        let storage = SqliteStorage::open(path_str, 10).unwrap();
+        storage
+            .append_messages(1, &[ChatMessage::user("remember me")])
+            .await
+            .unwrap();
+    }
+
+    let storage = SqliteStorage::open(path_str, 10).unwrap();
+    assert_eq!(
+        storage.recent_messages(1).await.unwrap(),
+        vec![ChatMessage::user("remember me")]
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that migrating a chat moves its history and settings, and that repeating it is harmless.
+#[tokio::test]
+async fn test_migrate_chat_moves_history_and_settings() {
+    let storage = SqliteStorage::open_in_memory(10).unwrap();
+
+    let settings = ChatSettings {
+        persona: Some("pirate".to_string()),
+        model: None,
+    };
+    storage
+        .append_messages(-100, &[ChatMessage::user("before the upgrade")])
+        .await
+        .unwrap();
+    storage.save_chat_settings(-100, &settings).await.unwrap();
+
+    storage.migrate_chat(-100, -1001234).await.unwrap();
+    storage.migrate_chat(-100, -1001234).await.unwrap();
+
+    assert!(storage.recent_messages(-100).await.unwrap().is_empty());
+    assert_eq!(
+        storage.chat_settings(-100).await.unwrap(),
+        ChatSettings::default()
+    );
+    assert_eq!(
+        storage.recent_messages(-1001234).await.unwrap(),
+        vec![ChatMessage::user("before the upgrade")]
+    );
+    assert_eq!(storage.chat_settings(-1001234).await.unwrap(), settings);
+}
+
+/// Tests that migrating a chat with nothing stored creates no chat, like the in-memory storage.
+#[tokio::test]
+async fn test_migrate_empty_chat_matches_in_memory_storage() {
+    let path =
+        std::env::temp_dir().join(format!("tg_ai_companion_migrate_{}.db", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let sqlite = SqliteStorage::open(path_str, 10).unwrap();
+    let memory = InMemoryStorage::new(10);
+    sqlite.migrate_chat(-100, -1001234).await.unwrap();
+    memory.migrate_chat(-100, -1001234).await.unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    let chats: i64 = connection
+        .query_row("SELECT COUNT(*) FROM chats", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(chats, 0);
+    assert!(memory.chat(-1001234).is_none());
+
+    for storage in [&sqlite as &dyn Storage, &memory] {
+        assert!(storage.recent_messages(-1001234).await.unwrap().is_empty());
+        assert_eq!(
+            storage.chat_settings(-1001234).await.unwrap(),
+            ChatSettings::default()
+        );
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that updating a chat without a type or title keeps the stored ones.
+#[tokio::test]
+async fn test_upsert_chat_keeps_unset_fields() {
+    let path = std::env::temp_dir().join(format!("tg_ai_companion_chat_{}.db", std::process::id()));
+    let path_str = path.to_str().unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let storage = SqliteStorage::open(path_str, 10).unwrap();
+    storage
+        .upsert_chat(&StoredChat {
+            id: -100,
+            chat_type: Some("supergroup".to_string()),
+            title: Some("Friends".to_string()),
+        })
+        .await
+        .unwrap();
+    storage
+        .upsert_chat(&StoredChat {
+            id: -100,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    let (chat_type, title): (String, String) = connection
+        .query_row(
+            "SELECT chat_type, title FROM chats WHERE id = -100",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(chat_type, "supergroup");
+    assert_eq!(title, "Friends");
+
+    std::fs::remove_file(&path).unwrap();
+}